@@ -1,6 +1,25 @@
-use std::time::Duration;
+use std::{
+    collections::VecDeque,
+    path::{Path, PathBuf},
+    time::Duration,
+};
 
-use bevy::{ecs::component::Component, time::Timer, transform::components::Transform};
+use bevy::{
+    ecs::{component::Component, system::Resource},
+    math::Vec2,
+    time::Timer,
+    transform::components::Transform,
+};
+use bevy_rapier2d::prelude::Velocity;
+use chrono::{DateTime, Utc};
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+use punchafriend::{
+    client::Settings,
+    game::pawns::HeldMovement,
+    networking::{GameInput, ServerTickUpdate},
+};
+use tokio::sync::mpsc::{channel, Receiver};
+use uuid::Uuid;
 
 #[derive(Debug, Component, Default)]
 /// This struct serves as a way for clients to keep track of the other players' ticks.
@@ -90,3 +109,452 @@ impl LastTransformState {
         &self.0
     }
 }
+
+/// The position of the pawn in the last update received from the server, the debug overlay draws a ghost here.
+/// The pawn is displayed elsewhere if the client has predicted its movement since the update, or if it is the local pawn being corrected.
+#[derive(Debug, Default, Clone, Copy, Component)]
+pub struct ServerPosition(pub Vec2);
+
+#[derive(Debug, Component, Clone, Copy, Default)]
+/// The camera the game's world is rendered with.
+/// The viewport of this camera is limited to [`LETTERBOX_ASPECT_RATIO`] when letterboxing is enabled.
+pub struct GameCamera;
+
+/// The aspect ratio of the game camera's viewport while letterboxing is enabled.
+pub const LETTERBOX_ASPECT_RATIO: f32 = 16. / 9.;
+
+#[derive(Debug, Component, Clone)]
+/// A ping placed in the world by a player with the ping wheel.
+/// The entity holding this component is despawned when the inner timer finishes.
+pub struct PingMarker {
+    /// The lifetime of the ping.
+    pub timer: Timer,
+}
+
+impl PingMarker {
+    /// Creates a new [`PingMarker`] instance.
+    pub fn new(timer: Timer) -> Self {
+        Self { timer }
+    }
+}
+
+#[derive(Debug, Component, Clone)]
+/// A brief spark displayed where the local player's attack has hit a pawn, pointing in the direction of the knockback.
+/// The entity holding this component is despawned when the inner timer finishes.
+pub struct HitSpark {
+    /// The lifetime of the spark.
+    pub timer: Timer,
+}
+
+impl HitSpark {
+    /// Creates a new [`HitSpark`] instance.
+    pub fn new(timer: Timer) -> Self {
+        Self { timer }
+    }
+}
+
+#[derive(Debug, Component, Clone)]
+/// A part of an arrow displayed where a training dummy has been hit, showing the direction and the strength of the knockback.
+/// The entity holding this component is despawned when the inner timer finishes.
+pub struct KnockbackArrow {
+    /// The lifetime of the arrow.
+    pub timer: Timer,
+}
+
+impl KnockbackArrow {
+    /// Creates a new [`KnockbackArrow`] instance.
+    pub fn new(timer: Timer) -> Self {
+        Self { timer }
+    }
+}
+
+/// The length of a knockback arrow per unit of the knockback's velocity.
+pub const KNOCKBACK_ARROW_SCALE: f32 = 0.15;
+
+/// The amount of time a knockback arrow is displayed for.
+pub const KNOCKBACK_ARROW_SECS: f32 = 1.;
+
+/// The fraction of the distance the local pawn is moved towards its position on the server with every update.
+/// The local pawn is moved by the player's inputs immediately, so it is only corrected gently instead of being snapped back.
+pub const LOCAL_PAWN_CORRECTION_FACTOR: f32 = 0.5;
+
+/// The local pawn is snapped to its position on the server if it is further away than this, this happens when the pawn is respawned or knocked back.
+pub const LOCAL_PAWN_SNAP_DISTANCE: f32 = 100.;
+
+/// The amount of time it takes for a music track to fade in or out.
+pub const MUSIC_FADE_SECS: f32 = 1.5;
+
+/// The maximum amount of pawns the client spawns, the updates of any further pawns are ignored.
+/// This keeps a misbehaving server from exhausting the client's memory by sending the updates of countless fake pawns.
+pub const MAX_REMOTE_PAWNS: usize = 256;
+
+/// The pawn updates ignored because of [`MAX_REMOTE_PAWNS`] are reported at most this often, so that the log isn't flooded every tick.
+pub const IGNORED_PAWN_UPDATES_LOG_SECS: f32 = 5.;
+
+#[derive(Debug, Component, Clone)]
+/// A music track being played, the tracks are faded into each other when the music changes.
+/// The track's file may be missing, in which case the entity doesn't play anything but still stands for the current track.
+pub struct MusicPlayer {
+    /// The path of the track's file, this is [`None`] if the current map doesn't have music.
+    pub path: Option<PathBuf>,
+
+    /// Whether the track is being faded out, the entity is despawned when its volume reaches 0.
+    pub fading_out: bool,
+}
+
+impl MusicPlayer {
+    /// Creates a new [`MusicPlayer`] instance.
+    pub fn new(path: Option<PathBuf>) -> Self {
+        Self {
+            path,
+            fading_out: false,
+        }
+    }
+}
+
+#[derive(Debug, Component, Clone)]
+/// A projectile simulated by the server, the client only moves it to the positions received from the server.
+/// The entity holding this component is despawned when the inner timer finishes, which is restarted by every update of the projectile.
+pub struct RemoteProjectile {
+    /// The id of the projectile, see [`punchafriend::networking::ProjectileUpdate::id`].
+    pub id: u64,
+
+    /// The time left until the projectile is despawned if no updates are received about it.
+    pub timeout: Timer,
+}
+
+impl RemoteProjectile {
+    /// The amount of time a projectile is kept without receiving any updates about it.
+    pub const TIMEOUT_SECS: f32 = 0.2;
+
+    /// Creates a new [`RemoteProjectile`] instance.
+    pub fn new(id: u64) -> Self {
+        Self {
+            id,
+            timeout: Timer::from_seconds(Self::TIMEOUT_SECS, bevy::time::TimerMode::Once),
+        }
+    }
+}
+
+#[derive(Debug, Component, Clone)]
+/// A fading copy of a fast moving pawn's sprite.
+/// The entity holding this component is despawned when the inner timer finishes.
+pub struct Afterimage {
+    /// The lifetime of the afterimage.
+    pub timer: Timer,
+
+    /// The opacity of the afterimage when it was spawned.
+    pub initial_alpha: f32,
+}
+
+impl Afterimage {
+    /// Creates a new [`Afterimage`] instance.
+    pub fn new(timer: Timer, initial_alpha: f32) -> Self {
+        Self {
+            timer,
+            initial_alpha,
+        }
+    }
+}
+
+#[derive(Debug, Component, Clone)]
+/// Every pawn has this component, so that the afterimages of the pawn are spawned in a consistent interval.
+pub struct AfterimageSpawner(Timer);
+
+impl Default for AfterimageSpawner {
+    fn default() -> Self {
+        Self(Timer::new(
+            Duration::from_secs_f32(0.04),
+            bevy::time::TimerMode::Repeating,
+        ))
+    }
+}
+
+impl AfterimageSpawner {
+    /// Increments the inner timer, returns whether an afterimage should be spawned in this tick.
+    pub fn tick(&mut self, delta: Duration) -> bool {
+        self.0.tick(delta);
+
+        self.0.just_finished()
+    }
+}
+
+/// The amount of time the settings have to stay untouched before they are saved.
+pub const AUTO_SAVE_DEBOUNCE_SECS: f32 = 2.;
+
+#[derive(Resource)]
+/// Keeps track of when the [`punchafriend::client::ApplicationCtx`] should be saved to the disk.
+pub struct AutoSaveState {
+    /// The interval the application's state is saved in regardless of any changes.
+    pub interval: Timer,
+
+    /// This timer is restarted every time the settings change, the state is saved when it finishes.
+    pub debounce: Option<Timer>,
+
+    /// The settings the last time they were checked.
+    pub observed_settings: Option<Settings>,
+}
+
+impl Default for AutoSaveState {
+    fn default() -> Self {
+        Self {
+            interval: Timer::new(Duration::from_secs(30), bevy::time::TimerMode::Repeating),
+            debounce: None,
+            observed_settings: None,
+        }
+    }
+}
+
+/// The amount of time the resource pack's files have to stay untouched before it is reloaded.
+pub const HOT_RELOAD_DEBOUNCE_SECS: f32 = 0.5;
+
+#[derive(Resource, Default)]
+/// Watches the active resource pack's directory for changes, this is only used if hot reloading is enabled.
+pub struct ResourcePackWatcher {
+    /// The watcher is stopped when this is dropped.
+    watcher: Option<RecommendedWatcher>,
+
+    /// Receives a message every time a file in the watched directory changes.
+    change_receiver: Option<Receiver<()>>,
+
+    /// The directory currently watched.
+    pub watched_path: Option<PathBuf>,
+
+    /// This timer is restarted every time a file changes, the resource pack is reloaded when it finishes.
+    pub debounce: Option<Timer>,
+}
+
+impl ResourcePackWatcher {
+    /// Starts watching the directory recursively.
+    pub fn watch(&mut self, path: &Path) -> anyhow::Result<()> {
+        let (change_sender, change_receiver) = channel::<()>(255);
+
+        let mut watcher =
+            notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+                // If the channel is full there is already a change waiting to be handled
+                if event.is_ok() {
+                    let _ = change_sender.try_send(());
+                }
+            })?;
+
+        watcher.watch(path, RecursiveMode::Recursive)?;
+
+        self.watcher = Some(watcher);
+        self.change_receiver = Some(change_receiver);
+
+        Ok(())
+    }
+
+    /// Returns whether any of the files have changed since the last call.
+    pub fn has_changed(&mut self) -> bool {
+        let Some(change_receiver) = &mut self.change_receiver else {
+            return false;
+        };
+
+        let mut has_changed = false;
+
+        // Empty the channel
+        while change_receiver.try_recv().is_ok() {
+            has_changed = true;
+        }
+
+        has_changed
+    }
+}
+
+#[derive(Resource)]
+/// Measures the rate the updates of the local player's pawn are received from the server at.
+/// Comparing this to the server's tick rate shows how many of the updates are lost on the way.
+pub struct PawnUpdateRate {
+    /// The timer of the current measurement window.
+    pub timer: Timer,
+    /// The amount of updates received in the current measurement window.
+    pub received: u32,
+    /// The amount of updates received in the last full measurement window.
+    pub rate: u32,
+    /// The tick of the last update received, this is included in the match info copied by the players.
+    pub last_tick: u64,
+    /// The smallest difference seen between the ticks of two consecutive updates, a larger gap between two updates means that updates were lost.
+    tick_stride: Option<u64>,
+    /// The amount of updates estimated to be lost in the current measurement window.
+    lost: u32,
+    /// The share of the updates lost in the last full measurement window, ranging from 0 to 1.
+    pub loss: f32,
+}
+
+impl Default for PawnUpdateRate {
+    fn default() -> Self {
+        Self {
+            timer: Timer::from_seconds(1., bevy::time::TimerMode::Repeating),
+            received: 0,
+            rate: 0,
+            last_tick: 0,
+            tick_stride: None,
+            lost: 0,
+            loss: 0.,
+        }
+    }
+}
+
+impl PawnUpdateRate {
+    /// Counts a received update in the current measurement window.
+    /// The updates missing between the last and this update's tick are counted as lost, the updates arriving out of order are ignored by the estimate.
+    pub fn record(&mut self, tick: u64) {
+        self.received += 1;
+
+        if self.last_tick != 0 && tick > self.last_tick {
+            let gap = tick - self.last_tick;
+            let tick_stride = self
+                .tick_stride
+                .map_or(gap, |tick_stride| tick_stride.min(gap));
+
+            self.tick_stride = Some(tick_stride);
+
+            self.lost += (gap / tick_stride).saturating_sub(1) as u32;
+        }
+
+        self.last_tick = self.last_tick.max(tick);
+    }
+
+    /// Advances the measurement window, the rate and the loss are updated every time a window finishes.
+    pub fn tick(&mut self, delta: Duration) {
+        self.timer.tick(delta);
+
+        if self.timer.just_finished() {
+            let lost = std::mem::take(&mut self.lost);
+
+            self.rate = std::mem::take(&mut self.received);
+            self.loss = if self.rate + lost == 0 {
+                0.
+            } else {
+                lost as f32 / (self.rate + lost) as f32
+            };
+        }
+    }
+}
+
+#[derive(Resource, Default)]
+/// Collects the discrete inputs pressed between two fixed updates, so that they are sent exactly once regardless of the framerate.
+pub struct InputQueue {
+    discrete_inputs: Vec<GameInput>,
+
+    /// The movement inputs the server has last been sent, this is [`None`] if the server hasn't been sent the movement inputs yet.
+    pub sent_movement: Option<HeldMovement>,
+}
+
+impl InputQueue {
+    /// Queues the discrete input, an input already waiting in the queue isn't queued again.
+    pub fn push(&mut self, game_input: GameInput) {
+        if !self.discrete_inputs.contains(&game_input) {
+            self.discrete_inputs.push(game_input);
+        }
+    }
+
+    /// Empties the queue and returns the queued inputs in the order they were pressed.
+    pub fn drain(&mut self) -> Vec<GameInput> {
+        std::mem::take(&mut self.discrete_inputs)
+    }
+}
+
+/// The interval in seconds the state of every movement input is resent in, so that a lost press or release is corrected.
+pub const MOVEMENT_KEEP_ALIVE_SECS: f32 = 0.5;
+
+/// The amount of the last received tick updates kept for the netcode reports.
+pub const TICK_UPDATE_HISTORY_LENGTH: usize = 128;
+
+/// The folder the netcode reports are written to.
+pub const NETCODE_REPORT_DIRECTORY: &str = "netcode_reports";
+
+/// The length of a pawn's velocity vector in the debug overlay per unit of the pawn's velocity.
+pub const DEBUG_VELOCITY_SCALE: f32 = 0.1;
+
+/// The vertical speed below which a pawn with all of its jumps is displayed as grounded in the debug overlay.
+pub const DEBUG_GROUNDED_SPEED: f32 = 1.;
+
+/// The opacity of the ghosts drawn at the pawns' [`ServerPosition`] in the debug overlay.
+pub const SERVER_POSITION_GHOST_ALPHA: f32 = 0.4;
+
+#[derive(Resource)]
+/// The state of the debug overlay, which displays the pawns' velocity and whether they are grounded on top of the colliders' outlines.
+/// The overlay is only available in debug builds.
+pub struct DebugOverlay {
+    pub enabled: bool,
+}
+
+impl Default for DebugOverlay {
+    fn default() -> Self {
+        Self { enabled: true }
+    }
+}
+
+#[derive(Resource, Default)]
+/// The last tick updates received from the server, these are included in the netcode reports.
+pub struct TickUpdateHistory {
+    updates: VecDeque<ServerTickUpdate>,
+}
+
+impl TickUpdateHistory {
+    /// Stores the update, the oldest update is dropped if there are more than [`TICK_UPDATE_HISTORY_LENGTH`] updates stored.
+    pub fn push(&mut self, update: ServerTickUpdate) {
+        if self.updates.len() >= TICK_UPDATE_HISTORY_LENGTH {
+            self.updates.pop_front();
+        }
+
+        self.updates.push_back(update);
+    }
+
+    /// Returns the stored updates from the oldest to the newest.
+    pub fn updates(&self) -> impl Iterator<Item = &ServerTickUpdate> {
+        self.updates.iter()
+    }
+}
+
+#[derive(Debug, serde::Serialize)]
+/// A snapshot of the client's netcode state, this is written to the disk to help diagnosing the desyncs reported by the players.
+pub struct NetcodeReport {
+    /// The time the report was created at.
+    pub created_at: DateTime<Utc>,
+
+    /// The address of the server as it was entered by the user.
+    pub address: String,
+
+    /// The id of the local player.
+    pub client_uuid: Uuid,
+
+    /// The last measured round trip time in milliseconds.
+    pub rtt_ms: i64,
+
+    /// The estimated offset of the server's clock in milliseconds.
+    pub clock_offset_ms: i64,
+
+    /// The amount of tick updates received but not handled yet.
+    pub tick_update_backlog: usize,
+
+    /// The amount of server requests received but not handled yet.
+    pub server_request_backlog: usize,
+
+    /// The amount of input batches waiting to be sent to the server.
+    pub input_backlog: usize,
+
+    /// The amount of client requests waiting to be sent to the server.
+    pub client_request_backlog: usize,
+
+    /// The pawns known by the client.
+    pub pawns: Vec<PawnSnapshot>,
+
+    /// The last tick updates received from the server, from the oldest to the newest.
+    pub recent_tick_updates: Vec<ServerTickUpdate>,
+}
+
+#[derive(Debug, serde::Serialize)]
+/// The state of a pawn known by the client, see [`NetcodeReport::pawns`].
+pub struct PawnSnapshot {
+    pub uuid: Uuid,
+
+    /// The tick of the last update applied to the pawn.
+    pub last_tick: u64,
+
+    pub transform: Transform,
+
+    pub velocity: Velocity,
+}
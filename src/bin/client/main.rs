@@ -2,8 +2,11 @@ mod app;
 mod systems;
 mod ui;
 
+use app::lib::{
+    AutoSaveState, DebugOverlay, InputQueue, PawnUpdateRate, ResourcePackWatcher, TickUpdateHistory,
+};
 use bevy::{
-    app::{App, FixedUpdate, PluginGroup, Startup, Update},
+    app::{App, FixedUpdate, Last, PluginGroup, Startup, Update},
     ecs::schedule::IntoSystemConfigs,
     log::LogPlugin,
     render::texture::ImagePlugin,
@@ -16,9 +19,14 @@ use bevy_rapier2d::{
 };
 use punchafriend::{client::ApplicationCtx, game::collision::CollisionGroupSet};
 use systems::{
-    exit_handler, handle_last_entity_transform, handle_server_output, handle_user_input, setup_game,
+    apply_hitbox_display, apply_letterboxing, apply_present_mode, auto_save, color_hill_zones,
+    draw_debug_overlay, exit_handler, handle_afterimages, handle_hit_sparks,
+    handle_knockback_arrows, handle_last_entity_transform, handle_ping_markers,
+    handle_remote_projectiles, handle_server_output, handle_user_input, hot_reload_resource_pack,
+    play_music, send_user_input, setup_game, toggle_debug_overlay, track_personal_bests,
+    write_netcode_report,
 };
-use ui::ui_system;
+use ui::{ping_wheel_system, ui_system};
 
 fn main() {
     let mut app = App::new();
@@ -44,16 +52,41 @@ fn main() {
 
     app.insert_resource(ApplicationCtx::default());
     app.insert_resource(CollisionGroupSet::default());
+    app.insert_resource(AutoSaveState::default());
+    app.insert_resource(ResourcePackWatcher::default());
+    app.insert_resource(InputQueue::default());
+    app.insert_resource(PawnUpdateRate::default());
+    app.insert_resource(TickUpdateHistory::default());
+    app.insert_resource(DebugOverlay::default());
 
     app.add_systems(Startup, setup_game);
     app.add_systems(Update, ui_system);
+    app.add_systems(Update, ping_wheel_system.after(ui_system));
     app.add_systems(
         FixedUpdate,
         handle_server_output.before(handle_last_entity_transform),
     );
     app.add_systems(FixedUpdate, handle_last_entity_transform);
     app.add_systems(Update, handle_user_input);
-    app.add_systems(Update, exit_handler);
+    app.add_systems(FixedUpdate, send_user_input);
+    app.add_systems(Update, handle_ping_markers);
+    app.add_systems(Update, handle_hit_sparks);
+    app.add_systems(Update, handle_knockback_arrows);
+    app.add_systems(Update, toggle_debug_overlay);
+    app.add_systems(Update, draw_debug_overlay.after(toggle_debug_overlay));
+    app.add_systems(Update, apply_hitbox_display.after(toggle_debug_overlay));
+    app.add_systems(Update, handle_remote_projectiles);
+    app.add_systems(Update, play_music);
+    app.add_systems(Update, apply_letterboxing);
+    app.add_systems(Update, apply_present_mode);
+    app.add_systems(Update, color_hill_zones);
+    app.add_systems(Update, track_personal_bests);
+    app.add_systems(Update, handle_afterimages);
+    app.add_systems(Update, auto_save);
+    app.add_systems(Update, hot_reload_resource_pack);
+    app.add_systems(Update, write_netcode_report);
+    // The window's close request is turned into an `AppExit` in `PostUpdate`, the app exits before the next `Update`
+    app.add_systems(Last, exit_handler);
 
     app.run();
 }
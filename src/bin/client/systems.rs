@@ -1,44 +1,91 @@
-use std::{fs, path::PathBuf, time::Duration};
+use std::{
+    f32::consts::FRAC_PI_4,
+    fs,
+    path::{Path, PathBuf},
+    sync::atomic::Ordering,
+    time::{Duration, Instant},
+};
 
 use bevy::{
     app::AppExit,
-    asset::{AssetServer, Assets},
+    asset::{AssetId, AssetServer, Assets},
+    audio::{AudioPlayer, AudioSink, AudioSinkPlayback, PlaybackSettings, Volume},
+    color::{Alpha, Color},
     core_pipeline::core_2d::Camera2d,
     ecs::{
         entity::Entity,
         event::EventReader,
-        query::{Changed, Without},
-        system::{Commands, Query, Res, ResMut},
+        query::{Changed, With, Without},
+        system::{Commands, Local, Query, Res, ResMut},
     },
+    gizmos::gizmos::Gizmos,
     input::{keyboard::KeyCode, ButtonInput},
-    math::UVec2,
-    render::mesh::Mesh,
-    sprite::{ColorMaterial, Sprite, TextureAtlas, TextureAtlasLayout},
-    time::{Time, Timer},
+    math::{Quat, UVec2, Vec2, Vec3},
+    render::{
+        camera::{Camera, ClearColorConfig, Viewport},
+        mesh::Mesh,
+        view::RenderLayers,
+    },
+    sprite::{Anchor, ColorMaterial, Sprite, TextureAtlas, TextureAtlasLayout},
+    text::{Text2d, TextColor},
+    time::{Time, Timer, TimerMode},
     transform::components::Transform,
+    window::{PrimaryWindow, Window},
     winit::{UpdateMode, WinitSettings},
 };
-use bevy_framepace::FramepaceSettings;
-use bevy_rapier2d::prelude::{
-    ActiveEvents, AdditionalMassProperties, Ccd, Collider, LockedAxes, RigidBody, Velocity,
+use bevy_framepace::{FramepaceSettings, Limiter};
+use bevy_rapier2d::{
+    prelude::{
+        ActiveEvents, AdditionalMassProperties, Ccd, Collider, LockedAxes, RigidBody, Velocity,
+    },
+    render::{ColliderDebug, DebugRenderContext},
 };
+use bevy_tokio_tasks::TokioTasksRuntime;
 use egui_toast::{Toast, ToastOptions};
 
 use miniz_oxide::deflate::CompressionLevel;
 use punchafriend::{
-    client::{ApplicationCtx, UiState},
+    client::{
+        music_path, player_slot_color, ApplicationCtx, CustomTexture, HitboxDisplay, KillFeedEntry,
+        PawnTexture, PresentModeSetting, ResourcePack, UiState, DEBUG_OVERLAY_KEY,
+        KILL_FEED_LENGTH, MENU_MUSIC_TRACK, NETCODE_REPORT_KEY,
+    },
     game::{
         collision::CollisionGroupSet,
-        map::{load_map_from_mapinstance, MapElement},
-        pawns::Pawn,
+        combat::{AttackType, EffectType},
+        hill::{HillState, HillZone},
+        map::{apply_map_gravity, load_map_from_mapinstance, MapElement},
+        modifiers::MatchModifiers,
+        pawns::{HeldMovement, Pawn, MAX_JUMPS, PAWN_HALF_EXTENTS},
+        physics::apply_physics_settings,
     },
-    networking::GameInput,
-    PauseWindowState, UiLayer,
+    networking::{error::NetError, GameInput},
+    Direction, PauseWindowState, UiLayer,
 };
 use tokio_util::sync::CancellationToken;
 use uuid::Uuid;
 
-use crate::app::lib::{AnimationState, LastTransformState, UniqueLastTickCount};
+use crate::app::lib::{
+    Afterimage, AfterimageSpawner, AnimationState, AutoSaveState, DebugOverlay, GameCamera,
+    HitSpark, InputQueue, KnockbackArrow, LastTransformState, MusicPlayer, NetcodeReport,
+    PawnSnapshot, PawnUpdateRate, PingMarker, RemoteProjectile, ResourcePackWatcher,
+    ServerPosition, TickUpdateHistory, UniqueLastTickCount, AUTO_SAVE_DEBOUNCE_SECS,
+    DEBUG_GROUNDED_SPEED, DEBUG_VELOCITY_SCALE, HOT_RELOAD_DEBOUNCE_SECS,
+    IGNORED_PAWN_UPDATES_LOG_SECS, KNOCKBACK_ARROW_SCALE, KNOCKBACK_ARROW_SECS,
+    LETTERBOX_ASPECT_RATIO, LOCAL_PAWN_CORRECTION_FACTOR, LOCAL_PAWN_SNAP_DISTANCE,
+    MAX_REMOTE_PAWNS, MOVEMENT_KEEP_ALIVE_SECS, MUSIC_FADE_SECS, NETCODE_REPORT_DIRECTORY,
+    SERVER_POSITION_GHOST_ALPHA,
+};
+
+/// Returns the local pawn's translation moved towards its translation on the server by [`LOCAL_PAWN_CORRECTION_FACTOR`].
+/// The pawn is snapped to the server's translation if it is further away than [`LOCAL_PAWN_SNAP_DISTANCE`].
+fn correct_local_pawn_translation(local_translation: Vec3, server_translation: Vec3) -> Vec3 {
+    if local_translation.distance(server_translation) > LOCAL_PAWN_SNAP_DISTANCE {
+        return server_translation;
+    }
+
+    local_translation.lerp(server_translation, LOCAL_PAWN_CORRECTION_FACTOR)
+}
 
 pub fn handle_last_entity_transform(
     mut moved_players: Query<(&mut LastTransformState, &Transform), Changed<Transform>>,
@@ -62,6 +109,7 @@ pub fn handle_server_output(
             &mut Sprite,
             &mut AnimationState,
             &LastTransformState,
+            &mut ServerPosition,
         ),
     >,
     mut commands: Commands<'_, '_>,
@@ -69,13 +117,38 @@ pub fn handle_server_output(
     asset_server: Res<AssetServer>,
     time: Res<Time>,
     mut current_game_objects: Query<(Entity, &MapElement, &mut Transform), Without<Pawn>>,
+    mut projectiles: Query<
+        (&mut RemoteProjectile, &mut Transform),
+        (Without<Pawn>, Without<MapElement>),
+    >,
+    runtime: Res<TokioTasksRuntime>,
+    mut pawn_update_rate: ResMut<PawnUpdateRate>,
+    mut tick_update_history: ResMut<TickUpdateHistory>,
+    mut unreported_pawn_updates: Local<usize>,
+    mut ignored_pawn_updates_log: Local<Option<Timer>>,
 ) {
     let layout = app_ctx.texture_atlas_layouts.clone();
+    let custom_textures = app_ctx.custom_textures.clone();
+
+    pawn_update_rate.tick(time.delta());
 
     if let Some(client_connection) = &mut app_ctx.client_connection {
+        let local_uuid = client_connection.server_metadata.client_uuid;
+
+        // The pawns are spawned with commands, so the pawns spawned in this update are counted separately
+        let mut pawn_count = pawns.iter().count();
+        let mut ignored_pawn_updates = 0;
+
         while let Ok(server_tick_update) = client_connection.server_tick_receiver.try_recv() {
+            tick_update_history.push(server_tick_update.clone());
+
             match &server_tick_update.tick_update_type {
                 punchafriend::networking::TickUpdateType::Pawn(pawn_update) => {
+                    // Only the local player's updates are counted, as exactly one of them is sent every tick
+                    if pawn_update.player.uuid == local_uuid {
+                        pawn_update_rate.record(pawn_update.tick_count);
+                    }
+
                     if !pawns.iter_mut().any(
                         |(
                             _e,
@@ -86,6 +159,7 @@ pub fn handle_server_output(
                             mut sprite,
                             mut animation_state,
                             _last_transform_state,
+                            mut server_position,
                         )| {
                             // Check if the player was found
                             let player_found = player.uuid == pawn_update.player.uuid;
@@ -106,13 +180,25 @@ pub fn handle_server_output(
                                     }
                                 }
 
+                                // The local pawn has already been moved by the inputs, so it is only corrected towards the server's position
+                                let translation = if player.uuid == local_uuid {
+                                    correct_local_pawn_translation(
+                                        transfrom.translation,
+                                        pawn_update.position.translation,
+                                    )
+                                } else {
+                                    pawn_update.position.translation
+                                };
+
                                 // Set new infromation
                                 *player = pawn_update.player.clone();
-                                *transfrom = pawn_update.position;
+                                *transfrom = pawn_update.position.with_translation(translation);
                                 *velocity = pawn_update.velocity;
+                                server_position.0 = pawn_update.position.translation.truncate();
 
                                 // Change the animation to walk
-                                sprite.image = asset_server.load("../assets/walk.png");
+                                sprite.image = asset_server
+                                    .load(PawnTexture::Walk.asset_path(&custom_textures));
 
                                 // Set the max idx
                                 animation_state.set_idx_max(7);
@@ -125,6 +211,12 @@ pub fn handle_server_output(
                             player_found
                         },
                     ) {
+                        if pawn_count >= MAX_REMOTE_PAWNS {
+                            ignored_pawn_updates += 1;
+
+                            continue;
+                        }
+
                         let animation_state = AnimationState::new(
                             Timer::new(
                                 Duration::from_secs_f32(0.1),
@@ -136,11 +228,14 @@ pub fn handle_server_output(
 
                         let starting_anim_idx = animation_state.animation_idx;
 
+                        pawn_count += 1;
+
                         spawn_pawn(
                             &mut commands,
                             &collision_groups,
                             &asset_server,
                             &layout,
+                            &custom_textures,
                             pawn_update,
                             animation_state,
                             starting_anim_idx,
@@ -158,14 +253,56 @@ pub fn handle_server_output(
                         }
                     }
                 }
+                punchafriend::networking::TickUpdateType::Projectile(projectile_update) => {
+                    let position = projectile_update.position;
+
+                    if let Some((mut projectile, mut transform)) = projectiles
+                        .iter_mut()
+                        .find(|(projectile, _)| projectile.id == projectile_update.id)
+                    {
+                        transform.translation = position.extend(transform.translation.z);
+
+                        projectile.timeout.reset();
+
+                        continue;
+                    }
+
+                    // The projectile's size is the size of its collider on the server
+                    let collider_size = AttackType::Projectile(Direction::default())
+                        .attack_profile()
+                        .collider_size;
+
+                    commands
+                        .spawn(Sprite::from_color(
+                            Color::srgb(1., 0.6, 0.2),
+                            collider_size * 2.,
+                        ))
+                        .insert(Transform::from_xyz(position.x, position.y, 5.))
+                        .insert(RemoteProjectile::new(projectile_update.id));
+                }
             }
         }
 
-        for (_, _, transform, _, _, mut sprite, mut anim_state, last_transform_state) in
+        *unreported_pawn_updates += ignored_pawn_updates;
+
+        let ignored_pawn_updates_log = ignored_pawn_updates_log.get_or_insert_with(|| {
+            Timer::from_seconds(IGNORED_PAWN_UPDATES_LOG_SECS, TimerMode::Repeating)
+        });
+
+        // The ignored updates are summed up, so that they are only reported once every few seconds
+        if ignored_pawn_updates_log.tick(time.delta()).just_finished()
+            && *unreported_pawn_updates > 0
+        {
+            eprintln!("Ignored {} pawn updates in the last {IGNORED_PAWN_UPDATES_LOG_SECS}s, the maximum of {MAX_REMOTE_PAWNS} pawns has been reached.", *unreported_pawn_updates);
+
+            *unreported_pawn_updates = 0;
+        }
+
+        for (_, _, transform, _, _, mut sprite, mut anim_state, last_transform_state, _) in
             pawns.iter_mut()
         {
             if *last_transform_state.get_inner() == *transform {
-                sprite.image = asset_server.load("../assets/idle.png");
+                sprite.image = asset_server.load(PawnTexture::Idle.asset_path(&custom_textures));
 
                 anim_state.set_idx_max(0);
                 anim_state.set_current_idx(0);
@@ -176,7 +313,7 @@ pub fn handle_server_output(
             match remote_request.request {
                 punchafriend::networking::ServerRequest::PlayerDisconnect(uuid) => {
                                 // Find the Entity with the designated uuid
-                                for (entity, player, _, _, _, _, _, _) in pawns.iter() {
+                                for (entity, player, _, _, _, _, _, _, _) in pawns.iter() {
                                     // Check for the correct uuid
                                     if player.uuid == uuid {
                                         // Despawn the entity
@@ -186,6 +323,12 @@ pub fn handle_server_output(
                                         break;
                                     }
                                 }
+
+                                // The player's row is dropped from the scoreboard too, it reappears with the next statistics broadcast if the player reconnects
+                                client_connection
+                                    .connected_clients_stats
+                                    .write()
+                                    .retain(|client_stats| client_stats.uuid != uuid);
                             }
                 punchafriend::networking::ServerRequest::PlayersStatisticsChange(
                                 updated_stat_entries,
@@ -193,6 +336,17 @@ pub fn handle_server_output(
                                 let mut client_stats = client_connection.connected_clients_stats.write();
 
                                 for updated_stat_entry in updated_stat_entries {
+                                    // The pawns of eliminated players are not respawned by the server, so remove them
+                                    if updated_stat_entry.lives == Some(0) {
+                                        for (entity, player, _, _, _, _, _, _, _) in pawns.iter() {
+                                            if player.uuid == updated_stat_entry.uuid {
+                                                commands.entity(entity).despawn();
+
+                                                break;
+                                            }
+                                        }
+                                    }
+
                                     if let Some(log_entry) = client_stats
                                         .iter()
                                         .find(|stat| stat.uuid == updated_stat_entry.uuid)
@@ -206,6 +360,13 @@ pub fn handle_server_output(
                                     }
                                 }
                             }
+                punchafriend::networking::ServerRequest::PlayerStatsRemoved(uuid) => {
+                                // The player has left, so its row is dropped from the leaderboard
+                                client_connection
+                                    .connected_clients_stats
+                                    .write()
+                                    .retain(|client_stats| client_stats.uuid != uuid);
+                            }
                 punchafriend::networking::ServerRequest::ServerGameStateControl(
                                 game_state_control,
                             ) => match game_state_control {
@@ -216,8 +377,14 @@ pub fn handle_server_output(
                                     // Set the application's state
                                     app_ctx.ui_layer = UiLayer::Intermission(intermission_data);
 
+                                    // The round has ended, so the sudden death has ended too
+                                    app_ctx.sudden_death = None;
+
                                     // Make the user able to vote again
-                                    app_ctx.has_voted = false;
+                                    app_ctx.voted_map = None;
+
+                                    // Start the map carousel from its first page
+                                    app_ctx.ui_state.intermission_page = 0;
                                 }
                                 punchafriend::networking::ServerGameState::OngoingGame(ongoing_game_data) => {
                                     // Setup map for client-side from a mapinstance
@@ -225,19 +392,41 @@ pub fn handle_server_output(
                                         ongoing_game_data.current_map.clone(),
                                         &mut commands,
                                         collision_groups.clone(),
-                                        current_game_objects,
+                                        // The client only simulates its own arena
+                                        0..1,
+                                        &current_game_objects,
+                                    );
+
+                                    apply_map_gravity(
+                                        &mut commands,
+                                        &ongoing_game_data.current_map,
+                                        &ongoing_game_data.modifiers,
                                     );
 
+                                    // The round has started, so the server isn't waiting for players anymore
+                                    app_ctx.waiting_for_players = None;
+
+                                    // The queued player's pawn is spawned with the round's start
+                                    app_ctx.queued_for_next_round = false;
+
+                                    // Clear the summary of the previous round
+                                    app_ctx.elimination_summary = None;
+
+                                    app_ctx.sudden_death = None;
+
+                                    // Nobody controls the hill of the new map yet
+                                    app_ctx.hill_state = HillState::default();
+
                                     // Set the application's state
                                     app_ctx.ui_layer = UiLayer::Game(ongoing_game_data);
                                 }
                             },
-                punchafriend::networking::ServerRequest::RTTMeasurement(_) => {
+                punchafriend::networking::ServerRequest::RTTMeasurement { .. } => {
                                 unreachable!("The RTT measurement should be evaluated by the TCP messsage receiver thread.")
                             }
                 punchafriend::networking::ServerRequest::ClientPawnSync(pawn_updates) => {
                                 // Iterate over all of the players
-                                for (entity, _, _, _, _, _, _, _) in pawns.iter() {
+                                for (entity, _, _, _, _, _, _, _, _) in pawns.iter() {
                                     // Despawn all of the existing players, to clear out players left from a different match
                                     commands.entity(entity).despawn();
                                 }
@@ -281,6 +470,189 @@ pub fn handle_server_output(
                         }
                     }
                 },
+                punchafriend::networking::ServerRequest::VoteTally(vote_tally) => {
+                    // Display the server's vote counts
+                    if let UiLayer::Intermission(intermission_data) = &mut app_ctx.ui_layer {
+                        intermission_data.selectable_maps = vote_tally;
+                    }
+                }
+                punchafriend::networking::ServerRequest::Ping { uuid, kind, position } => {
+                    // Fetch the username of the player who has placed the ping
+                    let username = client_connection
+                        .connected_clients_stats
+                        .read()
+                        .iter()
+                        .find(|stats| stats.uuid == uuid)
+                        .map(|stats| stats.username.clone())
+                        .unwrap_or_default();
+
+                    // The pings of the muted players are hidden
+                    if !app_ctx.ui_state.muted_players.contains(&uuid) {
+                        spawn_ping_marker(&mut commands, format!("{username}: {kind}"), position);
+                    }
+                }
+                punchafriend::networking::ServerRequest::QueuedForNextRound => {
+                    app_ctx.queued_for_next_round = true;
+                }
+                punchafriend::networking::ServerRequest::WaitingForPlayers { current, needed } => {
+                    app_ctx.waiting_for_players = Some((current, needed));
+                }
+                punchafriend::networking::ServerRequest::EliminationSummary(
+                    elimination_summary,
+                ) => {
+                    app_ctx.elimination_summary = Some(elimination_summary);
+                }
+                punchafriend::networking::ServerRequest::MapChange(map_instance) => {
+                    // The round's modifiers stay in effect on the new map
+                    let modifiers = match &app_ctx.ui_layer {
+                        UiLayer::Game(ongoing_game_data) => ongoing_game_data.modifiers,
+                        _ => MatchModifiers::default(),
+                    };
+
+                    // Swap the map, the pawns are pushed out of the new objects by the server
+                    load_map_from_mapinstance(
+                        map_instance.clone(),
+                        &mut commands,
+                        collision_groups.clone(),
+                        // The client only simulates its own arena
+                        0..1,
+                        &current_game_objects,
+                    );
+
+                    apply_map_gravity(&mut commands, &map_instance, &modifiers);
+
+                    // Keep the round's state in sync with the new map
+                    if let UiLayer::Game(ongoing_game_data) = &mut app_ctx.ui_layer {
+                        ongoing_game_data.current_map = map_instance;
+                    }
+                }
+                punchafriend::networking::ServerRequest::ServerShutdown => {
+                    reset_connection_and_ui(&mut app_ctx, &runtime);
+
+                    app_ctx.egui_toasts.add(
+                        Toast::new()
+                            .kind(egui_toast::ToastKind::Info)
+                            .text("The server has been shut down.")
+                            .options(
+                                ToastOptions::default()
+                                    .duration(Some(Duration::from_secs(3)))
+                                    .show_progress(true),
+                            ),
+                    );
+                }
+                punchafriend::networking::ServerRequest::ConnectionLost => {
+                    reset_connection_and_ui(&mut app_ctx, &runtime);
+
+                    app_ctx.egui_toasts.add(
+                        Toast::new()
+                            .kind(egui_toast::ToastKind::Error)
+                            .text("The connection to the server has been lost.")
+                            .options(
+                                ToastOptions::default()
+                                    .duration(Some(Duration::from_secs(3)))
+                                    .show_progress(true),
+                            ),
+                    );
+                }
+                // The keep-alives are answered by the connection's handler
+                punchafriend::networking::ServerRequest::KeepAlive => {}
+                punchafriend::networking::ServerRequest::SuddenDeath(players) => {
+                    app_ctx.sudden_death = Some(players);
+                }
+                punchafriend::networking::ServerRequest::HitRegistered {
+                    attacker,
+                    victim: _,
+                    position,
+                    direction,
+                    knockback,
+                    on_training_dummy,
+                } => {
+                    // Only the hits landed by the local player are confirmed with a spark
+                    if app_ctx.settings.hit_sparks
+                        && attacker == client_connection.server_metadata.client_uuid
+                    {
+                        spawn_hit_spark(&mut commands, position, direction);
+                    }
+
+                    // The knockback is only visualized on the training dummies, where the players practice their attacks
+                    if app_ctx.settings.knockback_arrows
+                        && on_training_dummy
+                        && knockback != Vec2::ZERO
+                    {
+                        spawn_knockback_arrow(&mut commands, position, knockback);
+                    }
+                }
+                punchafriend::networking::ServerRequest::PlayerKnockedOut {
+                    victim,
+                    killer,
+                    assists,
+                } => {
+                    let client_stats = client_connection
+                        .connected_clients_stats
+                        .read()
+                        .iter()
+                        .cloned()
+                        .collect::<Vec<_>>();
+
+                    app_ctx.kill_feed.push(KillFeedEntry::new(
+                        victim,
+                        killer,
+                        &assists,
+                        &client_stats,
+                    ));
+
+                    // Only the latest knockouts fit in the kill feed
+                    if app_ctx.kill_feed.len() > KILL_FEED_LENGTH {
+                        app_ctx.kill_feed.remove(0);
+                    }
+                }
+                punchafriend::networking::ServerRequest::RespawnIn(respawn_delay) => {
+                    app_ctx.respawn_at =
+                        Some(Instant::now() + Duration::from_secs_f32(respawn_delay.max(0.)));
+                }
+                punchafriend::networking::ServerRequest::HillStateChange(hill_state) => {
+                    app_ctx.hill_state = hill_state;
+                }
+                punchafriend::networking::ServerRequest::ConnectionRejected(reason) => {
+                    reset_connection_and_ui(&mut app_ctx, &runtime);
+
+                    app_ctx.egui_toasts.add(
+                        Toast::new()
+                            .kind(egui_toast::ToastKind::Error)
+                            .text(format!("Connection Failed: {}", reason))
+                            .options(
+                                ToastOptions::default()
+                                    .duration(Some(Duration::from_secs(3)))
+                                    .show_progress(true),
+                            ),
+                    );
+                }
+                punchafriend::networking::ServerRequest::Kicked(reason) => {
+                    reset_connection_and_ui(&mut app_ctx, &runtime);
+
+                    app_ctx.egui_toasts.add(
+                        Toast::new()
+                            .kind(egui_toast::ToastKind::Error)
+                            .text(format!("Kicked from the server: {}", reason))
+                            .options(
+                                ToastOptions::default()
+                                    .duration(Some(Duration::from_secs(5)))
+                                    .show_progress(true),
+                            ),
+                    );
+                }
+                punchafriend::networking::ServerRequest::RequestRejected(reason) => {
+                    app_ctx.egui_toasts.add(
+                        Toast::new()
+                            .kind(egui_toast::ToastKind::Error)
+                            .text(reason)
+                            .options(
+                                ToastOptions::default()
+                                    .duration(Some(Duration::from_secs(3)))
+                                    .show_progress(true),
+                            ),
+                    );
+                }
             }
         }
     } else {
@@ -289,19 +661,46 @@ pub fn handle_server_output(
             match connection {
                 Ok(client_connection) => {
                     // Iterate over all of the players
-                    for (entity, _, _, _, _, _, _, _) in pawns.iter() {
+                    for (entity, _, _, _, _, _, _, _, _) in pawns.iter() {
                         // Despawn all of the existing players, to clear out players left from a different match
                         commands.entity(entity).despawn();
                     }
 
+                    // Simulate with the same physics settings as the server, so that the local simulation doesn't diverge
+                    apply_physics_settings(
+                        &mut commands,
+                        client_connection.server_metadata.physics_settings,
+                    );
+
+                    // Remember the server so that the player can reconnect to it from the main menu
+                    app_ctx.ui_state.last_server_address = Some(client_connection.address.clone());
+
                     // Set the client connection variable
                     app_ctx.client_connection = Some(client_connection);
                 }
                 Err(error) => {
+                    // Let the user know what has gone wrong, so that they can act on it
+                    let message = match error {
+                        NetError::InvalidAddress(_) => String::from(
+                            "The address is invalid, it should contain the port, e.g. 127.0.0.1:8080",
+                        ),
+                        NetError::ConnectionRefused => String::from(
+                            "The connection has been refused, there is no server running at the address",
+                        ),
+                        NetError::Timeout => String::from("The server hasn't answered in time"),
+                        NetError::ConnectionClosed => {
+                            String::from("The server has closed the connection")
+                        }
+                        NetError::InvalidMetadata(_) => String::from(
+                            "The server has sent invalid data, it may be running a different version of the game",
+                        ),
+                        error => error.to_string(),
+                    };
+
                     app_ctx.egui_toasts.add(
                         Toast::new()
                             .kind(egui_toast::ToastKind::Error)
-                            .text(format!("Connection Failed: {}", error))
+                            .text(format!("Connection Failed: {}", message))
                             .options(
                                 ToastOptions::default()
                                     .duration(Some(Duration::from_secs(3)))
@@ -319,13 +718,14 @@ fn spawn_pawn(
     collision_groups: &Res<'_, CollisionGroupSet>,
     asset_server: &Res<'_, AssetServer>,
     layout: &bevy::asset::Handle<TextureAtlasLayout>,
+    custom_textures: &Option<CustomTexture>,
     pawn_update: &punchafriend::networking::PawnUpdate,
     animation_state: AnimationState,
     starting_anim_idx: usize,
 ) {
     commands
         .spawn(RigidBody::Dynamic)
-        .insert(Collider::cuboid(20.0, 30.0))
+        .insert(Collider::cuboid(PAWN_HALF_EXTENTS.x, PAWN_HALF_EXTENTS.y))
         .insert(pawn_update.position)
         .insert(AdditionalMassProperties::Mass(0.1))
         .insert(ActiveEvents::COLLISION_EVENTS)
@@ -336,19 +736,492 @@ fn spawn_pawn(
         .insert(Ccd::enabled())
         .insert(animation_state)
         .insert(LastTransformState::default())
-        .insert(Sprite::from_atlas_image(
-            asset_server.load("../assets/idle.png"),
-            TextureAtlas {
-                layout: layout.clone(),
-                index: starting_anim_idx,
-            },
-        ))
+        .insert(ServerPosition(pawn_update.position.translation.truncate()))
+        .insert(AfterimageSpawner::default())
+        .insert(Sprite {
+            color: pawn_update.player.tint_color(),
+            ..Sprite::from_atlas_image(
+                asset_server.load(PawnTexture::Idle.asset_path(custom_textures)),
+                TextureAtlas {
+                    layout: layout.clone(),
+                    index: starting_anim_idx,
+                },
+            )
+        })
         .insert(pawn_update.player.clone());
 }
 
+fn spawn_ping_marker(commands: &mut Commands<'_, '_>, text: String, position: Vec2) {
+    commands
+        .spawn(Text2d::new(text))
+        .insert(TextColor(Color::WHITE))
+        .insert(Transform::from_xyz(position.x, position.y, 10.))
+        .insert(PingMarker::new(Timer::from_seconds(3., TimerMode::Once)));
+}
+
+fn spawn_hit_spark(commands: &mut Commands<'_, '_>, position: Vec2, direction: Vec2) {
+    // The spark points in the direction of the knockback, horizontal if there is no knockback
+    let angle = if direction == Vec2::ZERO {
+        0.
+    } else {
+        direction.to_angle()
+    };
+
+    commands
+        .spawn(Sprite::from_color(
+            Color::srgb(1., 0.9, 0.4),
+            Vec2::new(40., 8.),
+        ))
+        .insert(
+            Transform::from_xyz(position.x, position.y, 5.)
+                .with_rotation(Quat::from_rotation_z(angle)),
+        )
+        .insert(HitSpark::new(Timer::from_seconds(0.15, TimerMode::Once)));
+}
+
+fn spawn_knockback_arrow(commands: &mut Commands<'_, '_>, position: Vec2, knockback: Vec2) {
+    let length = knockback.length() * KNOCKBACK_ARROW_SCALE;
+    let rotation = Quat::from_rotation_z(knockback.to_angle());
+    let color = Color::srgb(0.4, 0.8, 1.);
+
+    // The shaft starts at the hit pawn and points in the direction of the knockback
+    commands
+        .spawn(Sprite {
+            anchor: Anchor::CenterLeft,
+            ..Sprite::from_color(color, Vec2::new(length, 4.))
+        })
+        .insert(Transform::from_xyz(position.x, position.y, 6.).with_rotation(rotation))
+        .insert(KnockbackArrow::new(Timer::from_seconds(
+            KNOCKBACK_ARROW_SECS,
+            TimerMode::Once,
+        )));
+
+    // The head is a square turned on its corner at the end of the shaft
+    let head_position = position + knockback.normalize() * length;
+
+    commands
+        .spawn(Sprite::from_color(color, Vec2::splat(10.)))
+        .insert(
+            Transform::from_xyz(head_position.x, head_position.y, 6.)
+                .with_rotation(rotation * Quat::from_rotation_z(FRAC_PI_4)),
+        )
+        .insert(KnockbackArrow::new(Timer::from_seconds(
+            KNOCKBACK_ARROW_SECS,
+            TimerMode::Once,
+        )));
+}
+
+/// Records the local player's personal bests from the updates received about its own pawn.
+/// The survival time is restarted every time the player's death count changes.
+pub fn track_personal_bests(
+    mut app_ctx: ResMut<ApplicationCtx>,
+    pawns: Query<&Pawn>,
+    time: Res<Time>,
+) {
+    // Reborrow the resource so that its fields can be borrowed separately
+    let app_ctx = &mut *app_ctx;
+
+    let Some(client_connection) = &app_ctx.client_connection else {
+        app_ctx.current_survival = None;
+
+        return;
+    };
+
+    // The survival time only counts during a round
+    if matches!(
+        app_ctx.ui_layer,
+        UiLayer::MainMenu | UiLayer::GameMenu | UiLayer::Intermission(_)
+    ) {
+        app_ctx.current_survival = None;
+
+        return;
+    }
+
+    let local_uuid = client_connection.server_metadata.client_uuid;
+
+    let Some(local_pawn) = pawns.iter().find(|pawn| pawn.uuid == local_uuid) else {
+        return;
+    };
+
+    if let Some(combo) = &local_pawn.combo_stats {
+        app_ctx.personal_bests.highest_combo = app_ctx
+            .personal_bests
+            .highest_combo
+            .max(combo.combo_counter);
+    }
+
+    let deaths = client_connection
+        .connected_clients_stats
+        .read()
+        .iter()
+        .find(|client_stats| client_stats.uuid == local_uuid)
+        .map(|client_stats| client_stats.deaths)
+        .unwrap_or_default();
+
+    match &mut app_ctx.current_survival {
+        // The player is still on the same life
+        Some((survival_deaths, survived)) if *survival_deaths == deaths => {
+            *survived += time.delta();
+
+            app_ctx.personal_bests.longest_survival =
+                app_ctx.personal_bests.longest_survival.max(*survived);
+        }
+        // The player has just joined the round or has fallen off
+        _ => {
+            app_ctx.current_survival = Some((deaths, Duration::ZERO));
+        }
+    }
+}
+
+/// Fades out the pings placed in the world, and despawns them after their lifetime has ended.
+pub fn handle_ping_markers(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut ping_markers: Query<(Entity, &mut PingMarker, &mut TextColor)>,
+) {
+    for (entity, mut ping_marker, mut text_color) in ping_markers.iter_mut() {
+        ping_marker.timer.tick(time.delta());
+
+        // Despawn the ping if it has expired
+        if ping_marker.timer.finished() {
+            commands.entity(entity).despawn();
+
+            continue;
+        }
+
+        // Fade out the ping as it gets closer to expiring
+        text_color
+            .0
+            .set_alpha(ping_marker.timer.fraction_remaining());
+    }
+}
+
+/// Fades out the hit sparks, and despawns the expired ones.
+pub fn handle_hit_sparks(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut hit_sparks: Query<(Entity, &mut HitSpark, &mut Sprite)>,
+) {
+    for (entity, mut hit_spark, mut sprite) in hit_sparks.iter_mut() {
+        hit_spark.timer.tick(time.delta());
+
+        // Despawn the spark if it has expired
+        if hit_spark.timer.finished() {
+            commands.entity(entity).despawn();
+
+            continue;
+        }
+
+        sprite.color.set_alpha(hit_spark.timer.fraction_remaining());
+    }
+}
+
+/// Fades out the knockback arrows, and despawns the expired ones.
+pub fn handle_knockback_arrows(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut knockback_arrows: Query<(Entity, &mut KnockbackArrow, &mut Sprite)>,
+) {
+    for (entity, mut knockback_arrow, mut sprite) in knockback_arrows.iter_mut() {
+        knockback_arrow.timer.tick(time.delta());
+
+        // Despawn the arrow if it has expired
+        if knockback_arrow.timer.finished() {
+            commands.entity(entity).despawn();
+
+            continue;
+        }
+
+        sprite
+            .color
+            .set_alpha(knockback_arrow.timer.fraction_remaining());
+    }
+}
+
+/// Despawns the projectiles which haven't received any updates for [`RemoteProjectile::TIMEOUT_SECS`], this happens when the server has despawned them.
+pub fn handle_remote_projectiles(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut projectiles: Query<(Entity, &mut RemoteProjectile)>,
+) {
+    for (entity, mut projectile) in projectiles.iter_mut() {
+        projectile.timeout.tick(time.delta());
+
+        if projectile.timeout.finished() {
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
+/// Returns the music track which should be played in the ui layer, this is [`None`] if the current map doesn't have music.
+fn music_track(ui_layer: &UiLayer) -> Option<String> {
+    match ui_layer {
+        UiLayer::Game(ongoing_game_data) => ongoing_game_data.current_map.music.clone(),
+        // The music keeps playing while the pause window is open
+        UiLayer::PauseWindow((_, ui_layer)) => music_track(ui_layer),
+        UiLayer::Intermission(_) | UiLayer::MainMenu | UiLayer::GameMenu => {
+            Some(MENU_MUSIC_TRACK.to_string())
+        }
+    }
+}
+
+/// Plays the current map's music track in the rounds, and the menu's track outside of them.
+/// The previous track is faded out while the new one is faded in, the missing tracks are not played.
+pub fn play_music(
+    mut commands: Commands,
+    app_ctx: Res<ApplicationCtx>,
+    asset_server: Res<AssetServer>,
+    time: Res<Time>,
+    mut music_players: Query<(Entity, &mut MusicPlayer, Option<&AudioSink>)>,
+) {
+    let music_volume = app_ctx.settings.music_volume;
+
+    let path =
+        music_track(&app_ctx.ui_layer).map(|track| music_path(&track, &app_ctx.custom_music));
+
+    let mut is_playing = false;
+
+    for (entity, mut music_player, audio_sink) in music_players.iter_mut() {
+        // Fade out the track if the music has changed
+        if music_player.path != path {
+            music_player.fading_out = true;
+        }
+
+        is_playing |= !music_player.fading_out;
+
+        // The track is missing or it is still being loaded
+        let Some(audio_sink) = audio_sink else {
+            if music_player.fading_out {
+                commands.entity(entity).despawn();
+            }
+
+            continue;
+        };
+
+        let target_volume = if music_player.fading_out {
+            0.
+        } else {
+            music_volume
+        };
+
+        // The volume is moved towards the target with a constant speed, so that a full fade takes MUSIC_FADE_SECS
+        let fade_step = time.delta_secs() / MUSIC_FADE_SECS;
+        let volume = audio_sink.volume();
+
+        audio_sink.set_volume(if volume < target_volume {
+            (volume + fade_step).min(target_volume)
+        } else {
+            (volume - fade_step).max(target_volume)
+        });
+
+        if music_player.fading_out && audio_sink.volume() <= 0. {
+            commands.entity(entity).despawn();
+        }
+    }
+
+    if is_playing {
+        return;
+    }
+
+    let mut entity_commands = commands.spawn(MusicPlayer::new(path.clone()));
+
+    // Missing tracks are represented by a silent music player, so that the file isn't looked for every frame
+    if let Some(path) = path.filter(|path| path.exists()) {
+        entity_commands
+            .insert(AudioPlayer::new(
+                // The asset paths are relative to the assets folder
+                asset_server.load(format!("../{}", path.display())),
+            ))
+            .insert(PlaybackSettings::LOOP.with_volume(Volume::new(0.)));
+    }
+}
+
+/// Applies the [`punchafriend::client::Settings::present_mode`] to the primary window on startup and whenever it changes.
+/// The framerate limit is replaced with the monitor's refresh rate while VSync is on, as the two would fight over the frame timing.
+pub fn apply_present_mode(
+    app_ctx: Res<ApplicationCtx>,
+    mut windows: Query<&mut Window, With<PrimaryWindow>>,
+    mut framepace: ResMut<FramepaceSettings>,
+    mut applied_present_mode: Local<Option<PresentModeSetting>>,
+) {
+    let present_mode = app_ctx.settings.present_mode;
+
+    if *applied_present_mode == Some(present_mode) {
+        return;
+    }
+
+    let Ok(mut window) = windows.get_single_mut() else {
+        return;
+    };
+
+    window.present_mode = present_mode.present_mode();
+
+    framepace.limiter = if present_mode.limits_framerate() {
+        Limiter::Auto
+    } else {
+        Limiter::from_framerate(app_ctx.settings.fps)
+    };
+
+    *applied_present_mode = Some(present_mode);
+}
+
+/// Limits the [`GameCamera`]'s viewport to [`LETTERBOX_ASPECT_RATIO`] while letterboxing is enabled, centering it in the window.
+/// The rest of the window is cleared by the letterbox camera, which doesn't render anything.
+pub fn apply_letterboxing(
+    app_ctx: Res<ApplicationCtx>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    mut cameras: Query<&mut Camera, With<GameCamera>>,
+) {
+    let Ok(window) = windows.get_single() else {
+        return;
+    };
+
+    let window_size = window.physical_size();
+
+    // The window is minimized
+    if window_size.x == 0 || window_size.y == 0 {
+        return;
+    }
+
+    let viewport = app_ctx.settings.letterboxing.then(|| {
+        // Fit the largest viewport with the aspect ratio into the window
+        let viewport_size = UVec2::new(
+            window_size
+                .x
+                .min((window_size.y as f32 * LETTERBOX_ASPECT_RATIO) as u32),
+            window_size
+                .y
+                .min((window_size.x as f32 / LETTERBOX_ASPECT_RATIO) as u32),
+        );
+
+        (viewport_size, (window_size - viewport_size) / 2)
+    });
+
+    for mut camera in cameras.iter_mut() {
+        let current_viewport = camera
+            .viewport
+            .as_ref()
+            .map(|viewport| (viewport.physical_size, viewport.physical_position));
+
+        // Only modify the camera if the viewport has changed, so that the camera isn't marked as changed every frame
+        if current_viewport == viewport {
+            continue;
+        }
+
+        camera.viewport = viewport.map(|(physical_size, physical_position)| Viewport {
+            physical_position,
+            physical_size,
+            ..Default::default()
+        });
+    }
+}
+
+/// Colors the map's hill by its state, the hill takes the color of the player controlling it.
+pub fn color_hill_zones(
+    app_ctx: Res<ApplicationCtx>,
+    mut hill_zones: Query<&mut Sprite, With<HillZone>>,
+) {
+    let color = match &app_ctx.hill_state {
+        HillState::Uncontrolled => Color::srgba(1., 1., 1., 0.15),
+        HillState::Controlled(uuid) => {
+            let player_slot = app_ctx
+                .client_connection
+                .as_ref()
+                .and_then(|client_connection| {
+                    client_connection
+                        .connected_clients_stats
+                        .read()
+                        .iter()
+                        .find(|client_stats| client_stats.uuid == *uuid)
+                        .map(|client_stats| client_stats.player_slot)
+                });
+
+            match player_slot {
+                Some(player_slot) => {
+                    let slot_color = player_slot_color(player_slot);
+
+                    Color::srgba_u8(slot_color.r(), slot_color.g(), slot_color.b(), 90)
+                }
+                None => Color::srgba(1., 1., 1., 0.35),
+            }
+        }
+        HillState::Contested(_) => Color::srgba(1., 0.5, 0., 0.35),
+    };
+
+    for mut sprite in hill_zones.iter_mut() {
+        // Only modify the sprite if the color has changed, so that the sprite isn't marked as changed every frame
+        if sprite.color != color {
+            sprite.color = color;
+        }
+    }
+}
+
+/// The minimum speed a pawn has to move at for afterimages to be spawned behind it.
+pub const AFTERIMAGE_VELOCITY_THRESHOLD: f32 = 750.;
+
+/// Spawns afterimages behind fast moving pawns, and fades out the already existing ones.
+pub fn handle_afterimages(
+    mut commands: Commands,
+    app_ctx: Res<ApplicationCtx>,
+    time: Res<Time>,
+    mut pawns: Query<(&Velocity, &Transform, &Sprite, &mut AfterimageSpawner), With<Pawn>>,
+    mut afterimages: Query<(Entity, &mut Afterimage, &mut Sprite), Without<Pawn>>,
+) {
+    // Fade out the existing afterimages
+    for (entity, mut afterimage, mut sprite) in afterimages.iter_mut() {
+        afterimage.timer.tick(time.delta());
+
+        // Despawn the afterimage if it has expired
+        if afterimage.timer.finished() {
+            commands.entity(entity).despawn();
+
+            continue;
+        }
+
+        sprite
+            .color
+            .set_alpha(afterimage.initial_alpha * afterimage.timer.fraction_remaining());
+    }
+
+    if !app_ctx.settings.afterimages || app_ctx.settings.afterimage_intensity <= 0. {
+        return;
+    }
+
+    for (velocity, transform, sprite, mut spawner) in pawns.iter_mut() {
+        // Only spawn the afterimages in the set interval
+        if !spawner.tick(time.delta()) {
+            continue;
+        }
+
+        if velocity.linvel.length() < AFTERIMAGE_VELOCITY_THRESHOLD {
+            continue;
+        }
+
+        let mut afterimage_sprite = sprite.clone();
+
+        afterimage_sprite
+            .color
+            .set_alpha(app_ctx.settings.afterimage_intensity);
+
+        // Place the afterimage behind the pawn
+        let mut afterimage_transform = *transform;
+        afterimage_transform.translation.z -= 1.;
+
+        commands
+            .spawn(afterimage_sprite)
+            .insert(afterimage_transform)
+            .insert(Afterimage::new(
+                Timer::from_seconds(0.25, TimerMode::Once),
+                app_ctx.settings.afterimage_intensity,
+            ));
+    }
+}
+
+/// Queues the discrete inputs pressed this frame, the queue is sent to the server by [`send_user_input`].
 pub fn handle_user_input(
     mut app_ctx: ResMut<'_, ApplicationCtx>,
     keyboard_input: Res<'_, ButtonInput<KeyCode>>,
+    mut input_queue: ResMut<'_, InputQueue>,
 ) {
     if !(matches!(app_ctx.ui_layer, UiLayer::Game(_))
         || matches!(app_ctx.ui_layer, UiLayer::Intermission(_)))
@@ -360,30 +1233,102 @@ pub fn handle_user_input(
     if keyboard_input.just_pressed(KeyCode::Escape) {
         app_ctx.ui_layer =
             UiLayer::PauseWindow((PauseWindowState::Main, Box::new(app_ctx.ui_layer.clone())));
+
+        // The toggled scoreboard would cover the pause menu
+        app_ctx.ui_state.scoreboard_open = false;
     }
 
     if !matches!(app_ctx.ui_layer, UiLayer::Game(_)) {
         return;
     }
 
+    let key_bindings = app_ctx.settings.key_bindings;
+
+    for just_pressed in keyboard_input.get_just_pressed() {
+        match key_bindings.game_input(*just_pressed) {
+            // The held movement inputs are sent by `send_user_input`
+            Some(GameInput::MoveLeft | GameInput::MoveRight | GameInput::MoveDuck) | None => {
+                continue
+            }
+            Some(game_input) => input_queue.push(game_input),
+        }
+    }
+
+    // The releases are only sent for the inputs whose hold duration matters to the server
+    for just_released in keyboard_input.get_just_released() {
+        match key_bindings.game_input(*just_released) {
+            Some(GameInput::MoveJump) => input_queue.push(GameInput::MoveJumpRelease),
+            Some(GameInput::ChargeAttack) => input_queue.push(GameInput::ChargeAttackRelease),
+            Some(GameInput::Defend) => input_queue.push(GameInput::DefendRelease),
+            _ => continue,
+        }
+    }
+}
+
+/// Sends the queued discrete inputs and the changes of the held movement inputs to the server.
+/// This runs once per fixed update, so that the inputs are sent at the server's tick rate regardless of the client's framerate.
+/// The server keeps applying the held movement inputs until they are released, the state of every movement input is resent every [`MOVEMENT_KEEP_ALIVE_SECS`].
+pub fn send_user_input(
+    mut app_ctx: ResMut<'_, ApplicationCtx>,
+    keyboard_input: Res<'_, ButtonInput<KeyCode>>,
+    mut input_queue: ResMut<'_, InputQueue>,
+    runtime: Res<'_, TokioTasksRuntime>,
+    time: Res<'_, Time>,
+    mut pawns: Query<'_, '_, (&Pawn, &mut Transform)>,
+    mut keep_alive: Local<Option<Timer>>,
+) {
+    // The queued inputs are discarded outside of a round, so that they aren't sent when the next round starts
+    let queued_inputs = input_queue.drain();
+
+    let is_in_game = matches!(app_ctx.ui_layer, UiLayer::Game(_));
+
     // Send the inputs to the sender thread
     if let Some(client_connection) = &app_ctx.client_connection {
-        let mut game_inputs: Vec<GameInput> = vec![];
-
-        for pressed in keyboard_input.get_pressed() {
-            match pressed {
-                KeyCode::KeyD => game_inputs.push(GameInput::MoveRight),
-                KeyCode::KeyA => game_inputs.push(GameInput::MoveLeft),
-                KeyCode::KeyS => game_inputs.push(GameInput::MoveDuck),
-                _ => continue,
-            }
+        let key_bindings = app_ctx.settings.key_bindings;
+
+        // The movement inputs are released outside of a round, so that the pawn doesn't keep moving while the game is paused
+        let held_movement = HeldMovement {
+            left: is_in_game && keyboard_input.pressed(key_bindings.left),
+            right: is_in_game && keyboard_input.pressed(key_bindings.right),
+            duck: is_in_game && keyboard_input.pressed(key_bindings.duck),
+        };
+
+        let keep_alive = keep_alive.get_or_insert_with(|| {
+            Timer::from_seconds(MOVEMENT_KEEP_ALIVE_SECS, TimerMode::Repeating)
+        });
+
+        // Only the changes of the movement inputs are sent, except for the periodic keep-alive
+        let previous_movement = if keep_alive.tick(time.delta()).just_finished() {
+            None
+        } else {
+            input_queue.sent_movement
+        };
+
+        let mut game_inputs = held_movement.inputs_since(previous_movement);
+
+        input_queue.sent_movement = Some(held_movement);
+
+        if is_in_game {
+            game_inputs.extend(queued_inputs);
         }
 
-        for just_pressed in keyboard_input.get_just_pressed() {
-            match just_pressed {
-                KeyCode::Space => game_inputs.push(GameInput::Attack),
-                KeyCode::KeyW => game_inputs.push(GameInput::MoveJump),
-                _ => continue,
+        // Move the local pawn right away, so that it doesn't lag behind the inputs until the server's update arrives
+        if let Some((pawn, mut transform)) = pawns
+            .iter_mut()
+            .find(|(pawn, _)| pawn.uuid == client_connection.server_metadata.client_uuid)
+        {
+            if !pawn.has_effect(EffectType::Stunned) {
+                for game_input in held_movement.held_inputs() {
+                    match game_input {
+                        GameInput::MoveLeft => {
+                            transform.translation.x -= pawn.movement_speed() * time.delta_secs()
+                        }
+                        GameInput::MoveRight => {
+                            transform.translation.x += pawn.movement_speed() * time.delta_secs()
+                        }
+                        _ => continue,
+                    }
+                }
             }
         }
 
@@ -407,18 +1352,36 @@ pub fn handle_user_input(
                     ),
             );
 
-            reset_connection_and_ui(&mut app_ctx);
+            reset_connection_and_ui(&mut app_ctx, &runtime);
         }
+    } else {
+        // The next connection is sent the state of every movement input
+        input_queue.sent_movement = None;
     }
 }
 
-pub fn reset_connection_and_ui(app_ctx: &mut ResMut<'_, ApplicationCtx>) {
-    app_ctx.cancellation_token.cancel();
+pub fn reset_connection_and_ui(
+    app_ctx: &mut ResMut<'_, ApplicationCtx>,
+    runtime: &TokioTasksRuntime,
+) {
+    // Disconnect in the background, so that the frame isn't blocked while the server is notified
+    if let Some(client_connection) = app_ctx.client_connection.take() {
+        runtime.spawn_background_task(|_ctx| async move {
+            client_connection.disconnect().await;
+        });
+    }
 
-    app_ctx.client_connection = None;
+    // Stop the pending connection attempts too
+    app_ctx.cancellation_token.cancel();
 
     app_ctx.ui_layer = UiLayer::MainMenu;
 
+    app_ctx.queued_for_next_round = false;
+
+    app_ctx.kill_feed.clear();
+
+    app_ctx.respawn_at = None;
+
     app_ctx.cancellation_token = CancellationToken::new();
 }
 
@@ -432,10 +1395,19 @@ pub fn setup_game(
     framerate: ResMut<FramepaceSettings>,
     mut app_ctx: ResMut<'_, ApplicationCtx>,
 ) {
-    let camera = Camera2d;
-
     // Setup graphics
-    commands.spawn(camera);
+    commands.spawn((Camera2d, GameCamera));
+
+    // The letterbox camera only clears the window, so that the parts outside of the game camera's viewport are black
+    commands.spawn((
+        Camera2d,
+        Camera {
+            order: -1,
+            clear_color: ClearColorConfig::Custom(Color::BLACK),
+            ..Default::default()
+        },
+        RenderLayers::none(),
+    ));
 
     commands
         .spawn(Collider::cuboid(500.0, 10.0))
@@ -446,26 +1418,13 @@ pub fn setup_game(
             object_type: punchafriend::game::map::ObjectType::Static,
             id: Uuid::new_v4(),
             initial_position: None,
+            arena: 0,
         });
 
     winit_settings.unfocused_mode = UpdateMode::Continuous;
 
-    // Get the path of the %APPDATA% key.
-    #[cfg(target_os = "windows")]
-    let mut app_data_path = PathBuf::from(std::env::var("APPDATA").unwrap());
-
-    // Get the path of the opt key.
-    #[cfg(target_os = "linux")]
-    let mut app_data_path = PathBuf::from(std::env::var("opt").unwrap());
-
-    // Push the application's folder name to the path.
-    app_data_path.push("PunchAFriend");
-
-    // Push the file name
-    app_data_path.push("temp");
-
     // Read data and decompress it
-    match fs::read(app_data_path) {
+    match fs::read(application_ctx_save_path()) {
         Ok(read_bytes) => {
             // Decompress data
             let decompressed_data = miniz_oxide::inflate::decompress_to_vec(&read_bytes).unwrap();
@@ -492,7 +1451,59 @@ pub fn setup_game(
     ));
 }
 
-pub fn exit_handler(_exit_events: EventReader<AppExit>, ui_state: Res<ApplicationCtx>) {
+/// Saves the [`ApplicationCtx`] when the application is exiting.
+/// This runs in `Last`, so that the exit sent in `PostUpdate` when the window is closed is read too.
+pub fn exit_handler(mut exit_events: EventReader<AppExit>, app_ctx: Res<ApplicationCtx>) {
+    // Only save when the application is exiting
+    if exit_events.read().next().is_none() {
+        return;
+    }
+
+    if let Err(err) = save_application_ctx(&app_ctx) {
+        eprintln!("Failed to save the application's state: {err}");
+    }
+}
+
+/// Periodically saves the [`ApplicationCtx`], so that the changes survive a crash.
+/// Changes to the settings are saved after they have settled, so that dragging a slider doesn't write to the disk every frame.
+pub fn auto_save(
+    mut auto_save_state: ResMut<AutoSaveState>,
+    app_ctx: Res<ApplicationCtx>,
+    time: Res<Time>,
+) {
+    auto_save_state.interval.tick(time.delta());
+
+    // Restart the debounce timer every time the settings are modified
+    if auto_save_state.observed_settings.as_ref() != Some(&app_ctx.settings) {
+        auto_save_state.observed_settings = Some(app_ctx.settings.clone());
+
+        auto_save_state.debounce = Some(Timer::from_seconds(
+            AUTO_SAVE_DEBOUNCE_SECS,
+            TimerMode::Once,
+        ));
+    }
+
+    let settings_settled = if let Some(debounce) = &mut auto_save_state.debounce {
+        debounce.tick(time.delta());
+
+        debounce.finished()
+    } else {
+        false
+    };
+
+    if !(settings_settled || auto_save_state.interval.just_finished()) {
+        return;
+    }
+
+    auto_save_state.debounce = None;
+
+    if let Err(err) = save_application_ctx(&app_ctx) {
+        eprintln!("Failed to auto-save the application's state: {err}");
+    }
+}
+
+/// Returns the path of the file the [`ApplicationCtx`] is saved to.
+fn application_ctx_save_path() -> PathBuf {
     // Get the path of the %APPDATA% key.
     #[cfg(target_os = "windows")]
     let mut app_data_path = PathBuf::from(std::env::var("APPDATA").unwrap());
@@ -504,22 +1515,353 @@ pub fn exit_handler(_exit_events: EventReader<AppExit>, ui_state: Res<Applicatio
     // Push the application's folder name to the path.
     app_data_path.push("PunchAFriend");
 
-    // Create all of the folders which are needed for the path to exist
-    fs::create_dir_all(app_data_path.clone()).unwrap();
-
     // Push the file name
     app_data_path.push("temp");
 
+    app_data_path
+}
+
+/// Serializes and compresses the [`ApplicationCtx`], then writes it to the disk.
+/// The data is written to a temporary file first which is then renamed, so that a crash mid-write can't corrupt the existing save.
+pub fn save_application_ctx(app_ctx: &ApplicationCtx) -> anyhow::Result<()> {
+    let save_path = application_ctx_save_path();
+
+    // Create all of the folders which are needed for the path to exist
+    if let Some(parent) = save_path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
     // Serialize data
-    let serialized_data = rmp_serde::to_vec(&*ui_state).unwrap();
+    let serialized_data = rmp_serde::to_vec(app_ctx)?;
+
+    let temp_path = save_path.with_extension("tmp");
 
     // Write data before compressing it
     fs::write(
-        app_data_path,
+        &temp_path,
         miniz_oxide::deflate::compress_to_vec(
             &serialized_data,
             CompressionLevel::BestCompression as u8,
         ),
-    )
-    .unwrap();
+    )?;
+
+    // Replace the old save with the new one
+    fs::rename(temp_path, save_path)?;
+
+    Ok(())
+}
+
+/// Removes all of the existing texture atlas layouts, and creates a new one for the pawns' textures.
+/// The already spawned pawns' sprites are updated to use the new layout, as the old layouts are removed.
+pub fn reload_texture_atlas(
+    materials: &mut Assets<TextureAtlasLayout>,
+    app_ctx: &mut ApplicationCtx,
+    pawn_sprites: &mut Query<&mut Sprite, With<Pawn>>,
+) {
+    for material in materials
+        .iter()
+        .map(|(mat, _la)| mat)
+        .collect::<Vec<AssetId<TextureAtlasLayout>>>()
+        .into_iter()
+    {
+        materials.remove(material);
+    }
+
+    app_ctx.texture_atlas_layouts = materials.add(TextureAtlasLayout::from_grid(
+        UVec2::new(50, 64),
+        7,
+        1,
+        Some(UVec2::new(20, 0)),
+        None,
+    ));
+
+    // Point the existing sprites to the new layout
+    for mut sprite in pawn_sprites.iter_mut() {
+        if let Some(texture_atlas) = &mut sprite.texture_atlas {
+            texture_atlas.layout = app_ctx.texture_atlas_layouts.clone();
+        }
+    }
+}
+
+/// Watches the active resource pack's directory if hot reloading is enabled, and reloads the textures when the files in it change.
+/// The changes are debounced, as saving an image usually modifies the file multiple times.
+pub fn hot_reload_resource_pack(
+    mut app_ctx: ResMut<ApplicationCtx>,
+    mut watcher: ResMut<ResourcePackWatcher>,
+    mut materials: ResMut<Assets<TextureAtlasLayout>>,
+    mut pawn_sprites: Query<&mut Sprite, With<Pawn>>,
+    asset_server: Res<AssetServer>,
+    time: Res<Time>,
+) {
+    let watched_path = app_ctx
+        .ui_state
+        .current_resource_pack
+        .clone()
+        .filter(|_| app_ctx.settings.hot_reload_resource_pack);
+
+    // Start watching the new directory if the active resource pack has changed
+    if watcher.watched_path != watched_path {
+        *watcher = ResourcePackWatcher::default();
+
+        if let Some(path) = &watched_path {
+            if let Err(err) = watcher.watch(path) {
+                app_ctx.egui_toasts.add(
+                    Toast::new()
+                        .kind(egui_toast::ToastKind::Error)
+                        .text(format!("Failed to watch the resource pack: {err}"))
+                        .options(
+                            ToastOptions::default()
+                                .duration(Some(Duration::from_secs(3)))
+                                .show_progress(true),
+                        ),
+                );
+            }
+        }
+
+        watcher.watched_path = watched_path;
+    }
+
+    // Restart the debounce timer every time a file changes
+    if watcher.has_changed() {
+        watcher.debounce = Some(Timer::from_seconds(
+            HOT_RELOAD_DEBOUNCE_SECS,
+            TimerMode::Once,
+        ));
+    }
+
+    let Some(debounce) = &mut watcher.debounce else {
+        return;
+    };
+
+    debounce.tick(time.delta());
+
+    if !debounce.finished() {
+        return;
+    }
+
+    watcher.debounce = None;
+
+    let Some(path) = watcher.watched_path.clone() else {
+        return;
+    };
+
+    // Load the modified manifest, so that renamed textures are picked up too
+    match ResourcePack::load_from_dir(&path) {
+        Ok(resource_pack) => {
+            app_ctx.custom_textures = Some(resource_pack.textures);
+            app_ctx.custom_music = resource_pack.music;
+            app_ctx.custom_map_thumbnails = resource_pack.map_thumbnails;
+
+            // Reload the images which may have been modified
+            for texture in [
+                PawnTexture::Walk,
+                PawnTexture::Idle,
+                PawnTexture::Attack,
+                PawnTexture::Hurt,
+                PawnTexture::Jump,
+            ] {
+                asset_server.reload(texture.asset_path(&app_ctx.custom_textures));
+            }
+
+            reload_texture_atlas(&mut materials, &mut app_ctx, &mut pawn_sprites);
+        }
+        Err(err) => {
+            app_ctx.egui_toasts.add(
+                Toast::new()
+                    .kind(egui_toast::ToastKind::Error)
+                    .text(format!("Failed to reload the resource pack: {err:#}"))
+                    .options(
+                        ToastOptions::default()
+                            .duration(Some(Duration::from_secs(3)))
+                            .show_progress(true),
+                    ),
+            );
+        }
+    }
+}
+
+/// Toggles the [`DebugOverlay`] with the [`DEBUG_OVERLAY_KEY`], the colliders' outlines are toggled together with it by [`apply_hitbox_display`].
+pub fn toggle_debug_overlay(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut debug_overlay: ResMut<DebugOverlay>,
+) {
+    if !cfg!(debug_assertions) || !keyboard_input.just_pressed(DEBUG_OVERLAY_KEY) {
+        return;
+    }
+
+    debug_overlay.enabled = !debug_overlay.enabled;
+}
+
+/// Outlines the colliders picked by the player's [`HitboxDisplay`] while the [`DebugOverlay`] is enabled.
+/// In [`HitboxDisplay::SelfOnly`] only the local player's pawn is outlined, the other colliders are hidden.
+pub fn apply_hitbox_display(
+    mut commands: Commands,
+    app_ctx: Res<ApplicationCtx>,
+    debug_overlay: Res<DebugOverlay>,
+    debug_render_context: Option<ResMut<DebugRenderContext>>,
+    pawns: Query<(Entity, &Pawn, Option<&ColliderDebug>)>,
+) {
+    // The physics engine's debug renderer is only added in debug builds
+    let Some(mut debug_render_context) = debug_render_context else {
+        return;
+    };
+
+    let hitbox_display = app_ctx.settings.hitbox_display;
+
+    let enabled = debug_overlay.enabled && hitbox_display != HitboxDisplay::Off;
+
+    let default_collider_debug = if hitbox_display == HitboxDisplay::All {
+        ColliderDebug::AlwaysRender
+    } else {
+        ColliderDebug::NeverRender
+    };
+
+    // Only modify the context if it has changed, so that it isn't marked as changed every frame
+    if debug_render_context.enabled != enabled {
+        debug_render_context.enabled = enabled;
+    }
+
+    if debug_render_context.default_collider_debug != default_collider_debug {
+        debug_render_context.default_collider_debug = default_collider_debug;
+    }
+
+    let local_uuid = app_ctx
+        .client_connection
+        .as_ref()
+        .map(|client_connection| client_connection.server_metadata.client_uuid);
+
+    for (entity, pawn, collider_debug) in pawns.iter() {
+        let pawn_collider_debug = if Some(pawn.uuid) == local_uuid {
+            ColliderDebug::AlwaysRender
+        } else {
+            default_collider_debug
+        };
+
+        if collider_debug != Some(&pawn_collider_debug) {
+            commands.entity(entity).insert(pawn_collider_debug);
+        }
+    }
+}
+
+/// Draws every pawn's velocity, colored by whether the pawn is grounded or airborne, while the [`DebugOverlay`] is enabled.
+/// A ghost of the pawns is drawn at their [`ServerPosition`] too, if the player has enabled it in the settings.
+pub fn draw_debug_overlay(
+    app_ctx: Res<ApplicationCtx>,
+    debug_overlay: Res<DebugOverlay>,
+    mut gizmos: Gizmos,
+    pawns: Query<(&Pawn, &Transform, &Velocity, &ServerPosition)>,
+) {
+    if !cfg!(debug_assertions) || !debug_overlay.enabled {
+        return;
+    }
+
+    for (pawn, transform, velocity, server_position) in pawns.iter() {
+        let position = transform.translation.truncate();
+
+        if app_ctx.settings.server_position_ghosts {
+            gizmos.rect_2d(
+                server_position.0,
+                PAWN_HALF_EXTENTS * 2. * transform.scale.truncate(),
+                pawn.tint_color().with_alpha(SERVER_POSITION_GHOST_ALPHA),
+            );
+        }
+
+        // The pawns get their jumps back when they land, so a pawn with all of its jumps which isn't falling is standing on the map
+        let is_grounded =
+            pawn.jumps_remaining == MAX_JUMPS && velocity.linvel.y.abs() < DEBUG_GROUNDED_SPEED;
+
+        let color = if is_grounded {
+            Color::srgb(0.2, 1., 0.2)
+        } else {
+            Color::srgb(1., 0.6, 0.2)
+        };
+
+        gizmos.circle_2d(position, 5., color);
+
+        if velocity.linvel != Vec2::ZERO {
+            gizmos.arrow_2d(
+                position,
+                position + velocity.linvel * DEBUG_VELOCITY_SCALE,
+                color,
+            );
+        }
+    }
+}
+
+/// Writes a [`NetcodeReport`] to the [`NETCODE_REPORT_DIRECTORY`] when the [`NETCODE_REPORT_KEY`] is pressed.
+/// The reports are only meant for debugging the netcode, so the key is only bound in debug builds.
+pub fn write_netcode_report(
+    mut app_ctx: ResMut<ApplicationCtx>,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    tick_update_history: Res<TickUpdateHistory>,
+    pawns: Query<(&Pawn, &UniqueLastTickCount, &Transform, &Velocity)>,
+) {
+    if !cfg!(debug_assertions) || !keyboard_input.just_pressed(NETCODE_REPORT_KEY) {
+        return;
+    }
+
+    let Some(client_connection) = &app_ctx.client_connection else {
+        return;
+    };
+
+    let input_sender = &client_connection.server_input_sender;
+    let request_sender = &client_connection.remote_server_sender;
+
+    let report = NetcodeReport {
+        created_at: chrono::Local::now().to_utc(),
+        address: client_connection.address.clone(),
+        client_uuid: client_connection.server_metadata.client_uuid,
+        rtt_ms: client_connection.rtt_ms.load(Ordering::Relaxed),
+        clock_offset_ms: client_connection.clock_offset_ms.load(Ordering::Relaxed),
+        tick_update_backlog: client_connection.server_tick_receiver.len(),
+        server_request_backlog: client_connection.remote_receiver.len(),
+        input_backlog: input_sender.max_capacity() - input_sender.capacity(),
+        client_request_backlog: request_sender.max_capacity() - request_sender.capacity(),
+        pawns: pawns
+            .iter()
+            .map(|(pawn, last_tick, transform, velocity)| PawnSnapshot {
+                uuid: pawn.uuid,
+                last_tick: last_tick.get_inner(),
+                transform: *transform,
+                velocity: *velocity,
+            })
+            .collect(),
+        recent_tick_updates: tick_update_history.updates().cloned().collect(),
+    };
+
+    let (kind, text) = match save_netcode_report(&report) {
+        Ok(path) => (
+            egui_toast::ToastKind::Success,
+            format!("Saved the netcode report to {}", path.display()),
+        ),
+        Err(err) => (
+            egui_toast::ToastKind::Error,
+            format!("Failed to save the netcode report: {err}"),
+        ),
+    };
+
+    app_ctx.egui_toasts.add(
+        Toast::new().kind(kind).text(text).options(
+            ToastOptions::default()
+                .duration(Some(Duration::from_secs(3)))
+                .show_progress(true),
+        ),
+    );
+}
+
+/// Writes the report to a file named after its creation time, returns the path of the written file.
+fn save_netcode_report(report: &NetcodeReport) -> anyhow::Result<PathBuf> {
+    fs::create_dir_all(NETCODE_REPORT_DIRECTORY)?;
+
+    let path = Path::new(NETCODE_REPORT_DIRECTORY).join(format!(
+        "netcode_report_{}.ron",
+        report.created_at.format("%Y-%m-%d_%H-%M-%S")
+    ));
+
+    fs::write(
+        &path,
+        ron::ser::to_string_pretty(report, ron::ser::PrettyConfig::default())?,
+    )?;
+
+    Ok(path)
 }
@@ -1,18 +1,31 @@
+use std::{
+    f32::consts::TAU,
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+
 use bevy::{
-    asset::{AssetId, Assets},
+    app::AppExit,
+    asset::Assets,
     ecs::{
         entity::Entity,
+        event::EventWriter,
+        query::With,
         system::{Commands, Query, Res, ResMut},
     },
     input::{keyboard::KeyCode, ButtonInput},
-    math::UVec2,
-    render::mesh::Mesh,
-    sprite::TextureAtlasLayout,
+    math::Vec2,
+    render::{camera::Camera, mesh::Mesh},
+    sprite::{Sprite, TextureAtlasLayout},
     time::Time,
-    transform::components::Transform,
+    transform::components::{GlobalTransform, Transform},
+    window::{PrimaryWindow, Window},
 };
 use bevy_egui::{
-    egui::{self, vec2, Align2, Color32, Grid, Layout, Pos2, RichText, ScrollArea, Sense, Slider},
+    egui::{
+        self, vec2, Align2, Color32, FontId, LayerId, Layout, Order, Pos2, RichText, ScrollArea,
+        Sense, Slider, Stroke,
+    },
     EguiContexts,
 };
 use bevy_framepace::{FramepaceSettings, Limiter};
@@ -20,18 +33,36 @@ use bevy_tokio_tasks::TokioTasksRuntime;
 
 use chrono::Local;
 use egui_extras::{Column, TableBuilder};
+use egui_toast::{Toast, ToastOptions};
 use punchafriend::{
-    client::ApplicationCtx,
+    client::{
+        player_slot_color, scan_resource_packs, ApplicationCtx, ConnectionQuality, HitboxDisplay,
+        KeyBindings, PersonalBests, PresentModeSetting, ScoreboardColumn, ScoreboardMode,
+        ScoreboardSort, KILL_FEED_DURATION, MODS_DIRECTORY, PING_WHEEL_KEY,
+    },
     game::{
         collision::CollisionGroupSet,
         pawns::{Pawn, PawnType},
     },
-    networking::{client::ClientConnection, RemoteClientRequest},
+    networking::{client::ClientConnection, ClientRequest, PingKind, RemoteClientRequest},
     PauseWindowState, UiLayer,
 };
 use strum::VariantArray;
+use uuid::Uuid;
+
+use crate::{
+    app::lib::{GameCamera, PawnUpdateRate},
+    systems::{reload_texture_atlas, reset_connection_and_ui},
+};
+
+/// The radius of the ping wheel in points.
+pub const PING_WHEEL_RADIUS: f32 = 90.;
 
-use crate::systems::reset_connection_and_ui;
+/// The distance the cursor has to be moved from the wheel's origin to select a ping.
+pub const PING_WHEEL_DEADZONE: f32 = 15.;
+
+/// The size of the map previews displayed in the intermission's map carousel.
+pub const MAP_PREVIEW_SIZE: egui::Vec2 = egui::Vec2::new(200., 150.);
 
 pub fn ui_system(
     mut context: EguiContexts,
@@ -45,6 +76,9 @@ pub fn ui_system(
     mut materials: ResMut<Assets<TextureAtlasLayout>>,
     collision_groups: Res<CollisionGroupSet>,
     mut framepace: ResMut<FramepaceSettings>,
+    mut pawn_sprites: Query<&mut Sprite, With<Pawn>>,
+    mut app_exit: EventWriter<AppExit>,
+    pawn_update_rate: Res<PawnUpdateRate>,
 ) {
     // Get context
     let ctx = context.ctx_mut();
@@ -55,7 +89,12 @@ pub fn ui_system(
     // Show toasts
     app_ctx.egui_toasts.show(ctx);
 
-    let local_utc_time = Local::now().to_utc();
+    // The countdowns are displayed in the server's time, so that the difference of the clocks doesn't skew them
+    let server_utc_time = app_ctx
+        .client_connection
+        .as_ref()
+        .map(ClientConnection::server_time)
+        .unwrap_or_else(|| Local::now().to_utc());
 
     // Match the UiLayer enum's state
     match app_ctx.ui_layer.clone() {
@@ -63,15 +102,45 @@ pub fn ui_system(
             // Show RTT when there is an ongoing game
             if let Some(client_connection) = &app_ctx.client_connection {
                 egui::Area::new("rtt_display".into()).show(ctx, |ui| {
-                    ui.label(
-                        RichText::from(format!(
-                            "Ping: {}ms",
-                            client_connection
-                                .rtt_ms
-                                .load(std::sync::atomic::Ordering::Relaxed)
-                        ))
-                        .color(Color32::WHITE),
+                    let rtt_ms = client_connection
+                        .rtt_ms
+                        .load(std::sync::atomic::Ordering::Relaxed);
+
+                    // The players can tell from the indicator whether their own connection is the cause of the lag
+                    let connection_quality = ConnectionQuality::from_metrics(
+                        rtt_ms,
+                        pawn_update_rate.loss,
+                        &app_ctx.settings.connection_quality_thresholds,
                     );
+
+                    ui.horizontal(|ui| {
+                        ui.label(RichText::from("⏺").color(connection_quality.color()))
+                            .on_hover_text(format!(
+                                "{connection_quality} connection, {:.0}% of the updates lost",
+                                pawn_update_rate.loss * 100.
+                            ));
+
+                        ui.label(RichText::from(format!("Ping: {rtt_ms}ms")).color(Color32::WHITE));
+                    });
+
+                    // A lower update rate than the tick rate means that some of the updates are lost on the way
+                    if app_ctx.settings.network_stats {
+                        ui.label(
+                            RichText::from(format!(
+                                "Server tick rate: {}Hz",
+                                client_connection.server_metadata.tick_rate
+                            ))
+                            .color(Color32::WHITE),
+                        );
+
+                        ui.label(
+                            RichText::from(format!(
+                                "Updates received: {}/s",
+                                pawn_update_rate.rate
+                            ))
+                            .color(Color32::WHITE),
+                        );
+                    }
                 });
             }
 
@@ -79,24 +148,206 @@ pub fn ui_system(
             let time_delta = ongoing_game_data
                 .round_end_date
                 .time()
-                .signed_duration_since(local_utc_time.time());
+                .signed_duration_since(server_utc_time.time());
 
             egui::Area::new("hud".into())
                 .anchor(Align2::CENTER_TOP, vec2(0., 20.))
                 .show(ctx, |ui| {
-                    ui.label(
-                        RichText::from(format!(
-                            "Round time: {:.2}s",
-                            time_delta.num_milliseconds() as f32 / 1000.
-                        ))
-                        .color(Color32::WHITE),
-                    );
+                    // The round timer is frozen while the server is waiting for players
+                    if let Some((current, needed)) = app_ctx.waiting_for_players {
+                        ui.label(
+                            RichText::from(format!("Waiting for players ({current}/{needed})"))
+                                .size(20.)
+                                .color(Color32::WHITE),
+                        );
+                    } else {
+                        ui.label(
+                            RichText::from(format!(
+                                "Round time: {:.2}s",
+                                time_delta.num_milliseconds() as f32 / 1000.
+                            ))
+                            .color(Color32::WHITE),
+                        );
+
+                        // The elapsed part of the round
+                        let round_length = ongoing_game_data
+                            .round_end_date
+                            .signed_duration_since(ongoing_game_data.round_start_date)
+                            .num_milliseconds();
+
+                        let elapsed = server_utc_time
+                            .signed_duration_since(ongoing_game_data.round_start_date)
+                            .num_milliseconds();
+
+                        if round_length > 0 {
+                            ui.add(
+                                egui::ProgressBar::new(
+                                    (elapsed as f32 / round_length as f32).clamp(0., 1.),
+                                )
+                                .desired_width(200.),
+                            );
+                        }
+                    }
+
+                    // Display the local player's charge while they are charging an attack
+                    if let Some(client_connection) = &app_ctx.client_connection {
+                        if let Some((_, pawn, _)) = players.iter().find(|(_, pawn, _)| {
+                            pawn.uuid == client_connection.server_metadata.client_uuid
+                                && pawn.charge.is_some()
+                        }) {
+                            ui.add(
+                                egui::ProgressBar::new(f32::from(pawn.charge_level()) / 100.)
+                                    .desired_width(200.)
+                                    .text("Charge"),
+                            );
+                        }
+                    }
+                });
+
+            if let Some(client_connection) = &app_ctx.client_connection {
+                let mut client_stats = client_connection
+                    .connected_clients_stats
+                    .read()
+                    .iter()
+                    .cloned()
+                    .collect::<Vec<_>>();
+
+                // List the players in the order of their slots
+                client_stats.sort_by_key(|client| client.player_slot);
+
+                // Display the remaining lives of the players if the lives are limited
+                if client_stats.iter().any(|client| client.lives.is_some()) {
+                    egui::Area::new("lives_display".into())
+                        .anchor(Align2::LEFT_TOP, vec2(10., 30.))
+                        .show(ctx, |ui| {
+                            for client in client_stats.iter() {
+                                if let Some(lives) = client.lives {
+                                    ui.label(
+                                        RichText::from(format!(
+                                            "{}: {}",
+                                            client.username,
+                                            display_lives(lives)
+                                        ))
+                                        .color(player_slot_color(client.player_slot)),
+                                    );
+                                }
+                            }
+                        });
+                }
+
+                // Display the spectator overlay if the local player has been eliminated
+                let is_eliminated = client_stats.iter().any(|client| {
+                    client.uuid == client_connection.server_metadata.client_uuid
+                        && client.lives == Some(0)
                 });
 
+                // Display the overtime overlay with the players competing in the sudden death
+                if let Some(sudden_death_players) = &app_ctx.sudden_death {
+                    egui::Area::new("sudden_death_overlay".into())
+                        .anchor(Align2::CENTER_TOP, vec2(0., 60.))
+                        .show(ctx, |ui| {
+                            ui.vertical_centered(|ui| {
+                                ui.label(
+                                    RichText::from("Overtime!")
+                                        .size(40.)
+                                        .strong()
+                                        .color(Color32::RED),
+                                );
+
+                                for client in client_stats
+                                    .iter()
+                                    .filter(|client| sudden_death_players.contains(&client.uuid))
+                                {
+                                    ui.label(
+                                        RichText::from(client.username.clone())
+                                            .color(player_slot_color(client.player_slot)),
+                                    );
+                                }
+                            });
+                        });
+                }
+
+                // The players joining mid-round may have to wait for the next round
+                let spectator_text = if is_eliminated {
+                    Some("You have been eliminated! Spectating until the next round.")
+                } else if app_ctx.queued_for_next_round {
+                    Some("You'll join next round. Spectating until then.")
+                } else {
+                    None
+                };
+
+                if let Some(spectator_text) = spectator_text {
+                    egui::Area::new("spectator_overlay".into())
+                        .anchor(Align2::CENTER_BOTTOM, vec2(0., -40.))
+                        .show(ctx, |ui| {
+                            ui.label(
+                                RichText::from(spectator_text)
+                                    .size(20.)
+                                    .color(Color32::WHITE),
+                            );
+                        });
+                }
+            }
+
+            // Count down until the local player's pawn is respawned
+            if let Some(respawn_at) = app_ctx.respawn_at {
+                let remaining = respawn_at.saturating_duration_since(Instant::now());
+
+                if remaining.is_zero() {
+                    app_ctx.respawn_at = None;
+                } else {
+                    egui::Area::new("respawn_countdown".into())
+                        .anchor(Align2::CENTER_CENTER, vec2(0., 0.))
+                        .show(ctx, |ui| {
+                            ui.label(
+                                RichText::from(format!(
+                                    "Respawning in {:.1}s",
+                                    remaining.as_secs_f32()
+                                ))
+                                .size(30.)
+                                .color(Color32::WHITE),
+                            );
+                        });
+                }
+            }
+
+            // Display the recent knockouts, the expired ones are dropped from the kill feed
+            app_ctx
+                .kill_feed
+                .retain(|entry| entry.received_at.elapsed() < KILL_FEED_DURATION);
+
+            if !app_ctx.kill_feed.is_empty() {
+                egui::Area::new("kill_feed".into())
+                    .anchor(Align2::RIGHT_TOP, vec2(-10., 10.))
+                    .show(ctx, |ui| {
+                        ui.with_layout(Layout::top_down(egui::Align::Max), |ui| {
+                            for entry in app_ctx.kill_feed.iter() {
+                                ui.label(RichText::from(entry.text.clone()).color(Color32::WHITE));
+                            }
+                        });
+                    });
+            }
+
             // Set the new value of the UiLayer's enum
             app_ctx.ui_layer = UiLayer::Game(ongoing_game_data.clone());
 
-            if keyboard_input.pressed(KeyCode::Tab) {
+            let scoreboard_key = app_ctx.settings.key_bindings.scoreboard;
+
+            let show_scoreboard = match app_ctx.settings.scoreboard_mode {
+                ScoreboardMode::Hold => keyboard_input.pressed(scoreboard_key),
+                ScoreboardMode::Toggle => {
+                    if keyboard_input.just_pressed(scoreboard_key) {
+                        app_ctx.ui_state.scoreboard_open = !app_ctx.ui_state.scoreboard_open;
+                    }
+
+                    app_ctx.ui_state.scoreboard_open
+                }
+            };
+
+            if show_scoreboard {
+                // Reborrow the resource so that its fields can be borrowed separately
+                let app_ctx = &mut *app_ctx;
+
                 let leaderboard_area = egui::Area::new("scoreboard".into())
                     .anchor(Align2::CENTER_CENTER, vec2(0., 0.))
                     .show(ctx, |ui| {
@@ -110,59 +361,127 @@ pub fn ui_system(
                             ui.group(|ui| {
                                 let table = TableBuilder::new(ui)
                                     .striped(true)
-                                    .columns(Column::auto(), 5)
+                                    .vscroll(true)
+                                    .max_scroll_height(ctx.screen_rect().height() * 0.6)
+                                    .columns(Column::auto(), 8)
                                     .cell_layout(Layout::left_to_right(egui::Align::Center));
 
                                 table
                                     .header(20., |mut header| {
+                                        // Clicking a header sorts the scoreboard by its column
+                                        for column in ScoreboardColumn::VARIANTS {
+                                            header.col(|ui| {
+                                                scoreboard_header(
+                                                    ui,
+                                                    *column,
+                                                    &mut app_ctx.ui_state.scoreboard_sort,
+                                                );
+                                            });
+                                        }
                                         header.col(|ui| {
-                                            ui.label("Username");
-                                        });
-                                        header.col(|ui| {
-                                            ui.label("Kills");
-                                        });
-                                        header.col(|ui| {
-                                            ui.label("Deaths");
+                                            ui.label("Lives");
                                         });
                                         header.col(|ui| {
-                                            ui.label("Score");
-                                        });
-                                        header.col(|ui| {
-                                            ui.label("K/D");
+                                            ui.label("Muted");
                                         });
                                     })
                                     .body(|body| {
-                                        let client_stats =
-                                            connection.connected_clients_stats.read().clone();
+                                        let mut client_stats = connection
+                                            .connected_clients_stats
+                                            .read()
+                                            .iter()
+                                            .cloned()
+                                            .collect::<Vec<_>>();
+
+                                        if let Some(scoreboard_sort) =
+                                            app_ctx.ui_state.scoreboard_sort
+                                        {
+                                            scoreboard_sort.sort(&mut client_stats);
+                                        }
+
+                                        let scoreboard_rows =
+                                            app_ctx.settings.scoreboard_rows.max(1);
+
+                                        // Only the top players are listed, the local player's row is pinned below them if they aren't among them
+                                        let pinned_local_player = client_stats
+                                            .iter()
+                                            .skip(scoreboard_rows)
+                                            .find(|client| {
+                                                client.uuid
+                                                    == connection.server_metadata.client_uuid
+                                            })
+                                            .cloned();
+
+                                        client_stats.truncate(scoreboard_rows);
+                                        client_stats.extend(pinned_local_player);
+
                                         let mut client_stats_iter = client_stats.iter();
 
-                                        body.rows(
-                                            20.,
-                                            connection.connected_clients_stats.read().len(),
-                                            |mut column| {
-                                                if let Some(client) = client_stats_iter.next() {
-                                                    column.col(|ui| {
-                                                        ui.label(client.username.clone());
-                                                    });
-                                                    column.col(|ui| {
-                                                        ui.label(format!("{}", client.kills));
-                                                    });
-                                                    column.col(|ui| {
-                                                        ui.label(format!("{}", client.deaths));
-                                                    });
-                                                    column.col(|ui| {
-                                                        ui.label(format!("{}", client.score));
-                                                    });
-                                                    column.col(|ui| {
-                                                        ui.label(format!(
-                                                            "{:.2}",
-                                                            client.kills as f32
-                                                                / client.deaths as f32
-                                                        ));
-                                                    });
-                                                }
-                                            },
-                                        );
+                                        body.rows(20., client_stats.len(), |mut column| {
+                                            if let Some(client) = client_stats_iter.next() {
+                                                column.col(|ui| {
+                                                    ui.label(
+                                                        RichText::from(client.username.clone())
+                                                            .color(player_slot_color(
+                                                                client.player_slot,
+                                                            )),
+                                                    );
+                                                });
+                                                column.col(|ui| {
+                                                    ui.label(format!("{}", client.kills));
+                                                });
+                                                column.col(|ui| {
+                                                    ui.label(format!("{}", client.deaths));
+                                                });
+                                                column.col(|ui| {
+                                                    ui.label(format!("{}", client.assists));
+                                                });
+                                                column.col(|ui| {
+                                                    ui.label(format!("{}", client.score));
+                                                });
+                                                column.col(|ui| {
+                                                    ui.label(format!(
+                                                        "{:.2}",
+                                                        client.kill_death_ratio()
+                                                    ));
+                                                });
+                                                column.col(|ui| {
+                                                    ui.label(
+                                                        client
+                                                            .lives
+                                                            .map(display_lives)
+                                                            .unwrap_or_else(|| String::from("-")),
+                                                    );
+                                                });
+                                                column.col(|ui| {
+                                                    let muted_players =
+                                                        &mut app_ctx.ui_state.muted_players;
+
+                                                    let mut is_muted =
+                                                        muted_players.contains(&client.uuid);
+
+                                                    // The local player can't be muted
+                                                    let is_local_player = client.uuid
+                                                        == connection.server_metadata.client_uuid;
+
+                                                    if ui
+                                                        .add_enabled(
+                                                            !is_local_player,
+                                                            egui::Checkbox::without_text(
+                                                                &mut is_muted,
+                                                            ),
+                                                        )
+                                                        .changed()
+                                                    {
+                                                        if is_muted {
+                                                            muted_players.insert(client.uuid);
+                                                        } else {
+                                                            muted_players.remove(&client.uuid);
+                                                        }
+                                                    }
+                                                });
+                                            }
+                                        });
                                     });
                             });
                         }
@@ -178,55 +497,138 @@ pub fn ui_system(
 
                     ui.label(format!(
                         "Time left: {}s",
-                        intermission_data.intermission_end_date.time().signed_duration_since(local_utc_time.time()).num_seconds()
+                        intermission_data.intermission_end_date.time().signed_duration_since(server_utc_time.time()).num_seconds()
                     ));
+
+                    if app_ctx.queued_for_next_round {
+                        ui.label(RichText::from("You'll join next round.").strong());
+                    }
+
+                    // Display the order the players have been eliminated in during the last round
+                    if let Some(elimination_summary) = &app_ctx.elimination_summary {
+                        ui.label(RichText::from("Eliminations").strong());
+
+                        for (idx, client) in elimination_summary.iter().enumerate() {
+                            ui.label(
+                                RichText::from(format!("{}. {}", idx + 1, client.username))
+                                    .color(player_slot_color(client.player_slot)),
+                            );
+                        }
+
+                        // The players who still had lives left at the end of the round
+                        if let Some(client_connection) = &app_ctx.client_connection {
+                            for client in client_connection.connected_clients_stats.read().iter() {
+                                if client.lives.is_some_and(|lives| lives > 0) {
+                                    ui.label(RichText::from(format!("Survived: {}", client.username)).strong());
+                                }
+                            }
+                        }
+                    }
                 });
 
-                Grid::new("map_grid").show(ui, |ui| {
-                    // Iter over all the available maps
-                    for (map_idx, (map, vote_count)) in intermission_data.selectable_maps.iter().enumerate() {
-                        // Display the group
-                            ui.group(|ui| {
-                                // Allocate ui
-                                ui.allocate_ui(vec2(100., 100.), |ui| {
-                                    ui.vertical_centered(|ui| {
-                                        // Display the map's name
-                                        ui.horizontal(|ui| {
-                                            ui.label(RichText::from(map.to_string()).strong());
-                                            ui.label(RichText::from(vote_count.to_string()).strong());
-                                        });
-    
-                                        // Display an image of the map
-                                        ui.image(egui::include_image!(
-                                            "../../../assets/map_imgs/test.png"
-                                        ));
-    
-                                        // Show the vote button as available if the user hasnt voted yet.
-                                        ui.add_enabled_ui(!app_ctx.has_voted, |ui| {
-                                            // Show the button to vote
-                                            if ui.button("Vote").clicked() {
-                                                if let Some(client_connection) = &app_ctx.client_connection {
-                                                    client_connection.remote_server_sender.try_send(RemoteClientRequest {
-                                                        uuid: client_connection.server_metadata.client_uuid,
-                                                        request: punchafriend::networking::ClientRequest::Vote(*map),
-                                                    }).unwrap();
-                                                }
-    
-                                                // Prevent the user for voting multiple times
-                                                app_ctx.has_voted = true;
-                                            };
-                                        });
-                                    });
+                let selectable_maps = &intermission_data.selectable_maps;
+
+                // There is always at least one map displayed on a page
+                let maps_per_page = app_ctx.settings.intermission_maps_per_page.max(1);
+
+                let page_count = selectable_maps.len().div_ceil(maps_per_page).max(1);
+
+                // Keep the page in bounds if the amount of maps per page has changed
+                app_ctx.ui_state.intermission_page =
+                    app_ctx.ui_state.intermission_page.min(page_count - 1);
+
+                let current_page = app_ctx.ui_state.intermission_page;
+
+                if selectable_maps.is_empty() {
+                    ui.label("There are no maps to vote on.");
+                }
+
+                ui.horizontal(|ui| {
+                    // The paging buttons are only displayed if the maps don't fit on one page
+                    if page_count > 1
+                        && ui
+                            .add_enabled(
+                                current_page > 0,
+                                egui::Button::new(RichText::from("<").size(30.)),
+                            )
+                            .clicked()
+                    {
+                        app_ctx.ui_state.intermission_page -= 1;
+                    }
+
+                    // Iter over the maps on the current page
+                    for (map, vote_count) in selectable_maps
+                        .iter()
+                        .skip(current_page * maps_per_page)
+                        .take(maps_per_page)
+                    {
+                        let is_voted_map = app_ctx.voted_map == Some(*map);
+
+                        // Highlight the map the player has voted for
+                        let stroke = if is_voted_map {
+                            Stroke::new(3., ui.visuals().selection.bg_fill)
+                        } else {
+                            ui.visuals().widgets.noninteractive.bg_stroke
+                        };
+
+                        egui::Frame::group(ui.style()).stroke(stroke).show(ui, |ui| {
+                            ui.vertical(|ui| {
+                                ui.set_width(MAP_PREVIEW_SIZE.x);
+
+                                // Display the map's name and its vote count
+                                ui.horizontal(|ui| {
+                                    ui.label(RichText::from(map.to_string()).size(18.).strong());
+                                    ui.label(RichText::from(format!("Votes: {vote_count}")).strong());
+                                });
+
+                                // Display an image of the map
+                                let map_name = map.to_string();
+
+                                let thumbnail = app_ctx.custom_map_thumbnails.get(&map_name);
+
+                                map_preview(ui, &map_name, thumbnail.map(PathBuf::as_path));
+
+                                if is_voted_map {
+                                    ui.label(RichText::from("Your vote").strong());
+                                }
+
+                                // Show the vote button as available if the user hasnt voted yet.
+                                ui.add_enabled_ui(app_ctx.voted_map.is_none(), |ui| {
+                                    // Show the button to vote
+                                    if ui.button("Vote").clicked() {
+                                        if let Some(client_connection) = &app_ctx.client_connection {
+                                            client_connection.remote_server_sender.try_send(RemoteClientRequest {
+                                                uuid: client_connection.server_metadata.client_uuid,
+                                                request: punchafriend::networking::ClientRequest::Vote(*map),
+                                            }).unwrap();
+                                        }
+
+                                        // Prevent the user for voting multiple times
+                                        app_ctx.voted_map = Some(*map);
+                                    };
                                 });
                             });
+                        });
+                    }
 
-                        // End the row every 5 maps
-                        if map_idx % 5 == 0 {
-                            ui.end_row();
-                        }
+                    if page_count > 1
+                        && ui
+                            .add_enabled(
+                                current_page + 1 < page_count,
+                                egui::Button::new(RichText::from(">").size(30.)),
+                            )
+                            .clicked()
+                    {
+                        app_ctx.ui_state.intermission_page += 1;
                     }
                 });
 
+                if page_count > 1 {
+                    ui.label(format!("Page {}/{}", current_page + 1, page_count));
+                } else if selectable_maps.len() == 1 {
+                    ui.label("This is the only map available for the next round.");
+                }
+
                 ui.separator();
 
                 ui.label("Select a character:");
@@ -262,6 +664,16 @@ pub fn ui_system(
             egui::CentralPanel::default().show(ctx, |ui| {
                 ui.vertical_centered(|ui| {
                     ui.label(RichText::from("Punch A Friend!").size(50.));
+
+                    // Display the personal bests under the title
+                    ui.label(format!(
+                        "Highest combo: {}",
+                        app_ctx.personal_bests.highest_combo
+                    ));
+                    ui.label(format!(
+                        "Longest survival: {:.1}s",
+                        app_ctx.personal_bests.longest_survival.as_secs_f32()
+                    ));
                 });
             });
 
@@ -270,7 +682,19 @@ pub fn ui_system(
                 .show_separator_line(false)
                 .show(ctx, |ui| {
                     ui.with_layout(Layout::top_down(egui::Align::Min), |ui| {
-                        ui.add(egui::Button::new(RichText::from("Mods").size(25.)).frame(false));
+                        if ui
+                            .add(egui::Button::new(RichText::from("Mods").size(25.)).frame(false))
+                            .clicked()
+                        {
+                            // Look for the resource packs every time the browser is opened
+                            app_ctx.ui_state.resource_packs =
+                                scan_resource_packs(Path::new(MODS_DIRECTORY));
+
+                            app_ctx.ui_layer = UiLayer::PauseWindow((
+                                PauseWindowState::Mods,
+                                Box::new(app_ctx.ui_layer.clone()),
+                            ));
+                        };
 
                         if ui
                             .add(
@@ -284,6 +708,29 @@ pub fn ui_system(
                             ));
                         };
 
+                        // The last server can be reconnected to if the username is still set
+                        let last_server_address = app_ctx
+                            .ui_state
+                            .last_server_address
+                            .clone()
+                            .filter(|_| !app_ctx.ui_state.username_buffer.is_empty());
+
+                        if ui
+                            .add_enabled(
+                                last_server_address.is_some(),
+                                egui::Button::new(
+                                    RichText::from("Reconnect to last server").size(25.),
+                                )
+                                .frame(false),
+                            )
+                            .clicked()
+                            && app_ctx.client_connection.is_none()
+                        {
+                            if let Some(last_server_address) = last_server_address {
+                                connect_to_server(&app_ctx, &runtime, last_server_address);
+                            }
+                        };
+
                         if ui
                             .add(
                                 egui::Button::new(RichText::from("Play").size(40.))
@@ -315,32 +762,20 @@ pub fn ui_system(
                     // Username buffer setter
                     ui.text_edit_singleline(&mut app_ctx.ui_state.username_buffer);
 
-                    ui.add_enabled_ui(!app_ctx.ui_state.username_buffer.is_empty(), |ui| {
-                        if ui.button("Connect").clicked() && app_ctx.client_connection.is_none() {
-                            // Clone the address so it can be moved.
-                            let address = app_ctx.ui_state.connect_to_address.clone();
-
-                            // Move the sender
-                            let sender = app_ctx.connection_sender.clone();
-
-                            // Set the channel
-                            let cancellation_token = app_ctx.cancellation_token.clone();
+                    ui.label("Password (leave empty if the server has none)");
 
-                            let username = app_ctx.ui_state.username_buffer.clone();
-
-                            // Create the connecting thread
-                            runtime.spawn_background_task(|_ctx| async move {
-                                // Attempt to make a connection to the remote address.
-                                let client_connection = ClientConnection::connect_to_address(
-                                    address,
-                                    username.clone(),
-                                    cancellation_token,
-                                )
-                                .await;
+                    ui.add(
+                        egui::TextEdit::singleline(&mut app_ctx.ui_state.password_buffer)
+                            .password(true),
+                    );
 
-                                // Send it to the front end no matter the end result.
-                                sender.send(client_connection).await.unwrap();
-                            });
+                    ui.add_enabled_ui(!app_ctx.ui_state.username_buffer.is_empty(), |ui| {
+                        if ui.button("Connect").clicked() && app_ctx.client_connection.is_none() {
+                            connect_to_server(
+                                &app_ctx,
+                                &runtime,
+                                app_ctx.ui_state.connect_to_address.clone(),
+                            );
                         };
                     });
                 });
@@ -348,17 +783,19 @@ pub fn ui_system(
         }
         UiLayer::PauseWindow((inner_state, state_before)) => {
             // Paint the pause menu's backgound
-            egui::Area::new("pause_window_background".into()).show(ctx, |ui| {
-                ui.painter()
-                    .rect_filled(ctx.screen_rect(), 0., Color32::from_black_alpha(200));
-
-                // Consume all interactions
-                ui.interact(
-                    ctx.screen_rect(),
-                    "consume_input".into(),
-                    Sense::click_and_drag(),
-                );
-            });
+            // The background is kept below the pause menu's windows, so that clicking it can't bring it in front of them
+            // The toasts are displayed in the foreground, so they stay interactable too
+            egui::Area::new("pause_window_background".into())
+                .order(Order::Background)
+                .fixed_pos(Pos2::ZERO)
+                .movable(false)
+                .show(ctx, |ui| {
+                    ui.painter()
+                        .rect_filled(ctx.screen_rect(), 0., Color32::from_black_alpha(200));
+
+                    // Consume the interactions which haven't landed on the pause menu
+                    ui.allocate_rect(ctx.screen_rect(), Sense::click_and_drag());
+                });
 
             let window_state = match inner_state {
                 punchafriend::PauseWindowState::Main => {
@@ -382,11 +819,50 @@ pub fn ui_system(
                                     ));
                                 }
 
+                                // The match info makes the bug reports reproducible
+                                if let (UiLayer::Game(ongoing_game_data), Some(client_connection)) =
+                                    (&*state_before, &app_ctx.client_connection)
+                                {
+                                    ui.label(
+                                        RichText::from(format!(
+                                            "Seed: {}",
+                                            client_connection.server_metadata.seed
+                                        ))
+                                        .weak(),
+                                    );
+
+                                    if ui
+                                        .add(egui::Button::new("Copy match info").frame(false))
+                                        .clicked()
+                                    {
+                                        ui.ctx().copy_text(format!(
+                                            "Seed: {}\nMap: {}\nServer: {}\nTick: {}",
+                                            client_connection.server_metadata.seed,
+                                            ongoing_game_data.current_map.name,
+                                            client_connection.address,
+                                            pawn_update_rate.last_tick
+                                        ));
+                                    }
+                                }
+
                                 if ui
-                                    .add(egui::Button::new("Quit Server").frame(false))
+                                    .add(egui::Button::new("Leave match").frame(false))
                                     .clicked()
                                 {
-                                    reset_connection_and_ui(&mut app_ctx);
+                                    app_ctx.ui_layer = UiLayer::PauseWindow((
+                                        PauseWindowState::LeaveConfirmation,
+                                        Box::new(app_ctx.ui_layer.clone()),
+                                    ));
+                                }
+
+                                if ui
+                                    .add(egui::Button::new("Quit game").frame(false))
+                                    .clicked()
+                                {
+                                    app_ctx.ui_layer = UiLayer::PauseWindow((
+                                        PauseWindowState::QuitConfirmation,
+                                        Box::new(app_ctx.ui_layer.clone()),
+                                    ));
                                 }
                             });
                         })
@@ -400,41 +876,454 @@ pub fn ui_system(
                     .show(ctx, |ui| {
                         ui.label(RichText::from("Video").size(20.).strong());
 
+                        ui.horizontal(|ui| {
+                            ui.label("Present mode");
+
+                            egui::ComboBox::from_id_salt("present_mode")
+                                .selected_text(app_ctx.settings.present_mode.to_string())
+                                .show_ui(ui, |ui| {
+                                    for present_mode in PresentModeSetting::VARIANTS {
+                                        ui.selectable_value(
+                                            &mut app_ctx.settings.present_mode,
+                                            *present_mode,
+                                            present_mode.to_string(),
+                                        );
+                                    }
+                                });
+                        });
+
                         ui.horizontal(|ui| {
                             ui.label("Framerate");
 
-                            let fps_slider =
-                                ui.add(Slider::new(&mut app_ctx.settings.fps, 30.0..=600.0));
+                            // VSync limits the framerate to the monitor's refresh rate
+                            let fps_slider = ui
+                                .add_enabled(
+                                    !app_ctx.settings.present_mode.limits_framerate(),
+                                    Slider::new(&mut app_ctx.settings.fps, 30.0..=600.0),
+                                )
+                                .on_disabled_hover_text("The framerate is limited by VSync.");
 
                             if fps_slider.changed() {
                                 framepace.limiter = Limiter::from_framerate(app_ctx.settings.fps);
                             }
                         });
 
+                        ui.horizontal(|ui| {
+                            ui.label("Afterimages");
+
+                            ui.checkbox(&mut app_ctx.settings.afterimages, "");
+
+                            ui.add_enabled(
+                                app_ctx.settings.afterimages,
+                                Slider::new(&mut app_ctx.settings.afterimage_intensity, 0.0..=1.0)
+                                    .text("Intensity"),
+                            );
+                        });
+
+                        ui.horizontal(|ui| {
+                            ui.label("Hit sparks");
+
+                            ui.checkbox(&mut app_ctx.settings.hit_sparks, "");
+                        });
+
+                        ui.horizontal(|ui| {
+                            ui.label("Knockback arrows");
+
+                            ui.checkbox(&mut app_ctx.settings.knockback_arrows, "")
+                                .on_hover_text("Display the knockback of the hits on the training dummies.");
+                        });
+
+                        // The colliders and the server positions are only outlined in debug builds
+                        if cfg!(debug_assertions) {
+                            ui.horizontal(|ui| {
+                                ui.label("Hitboxes");
+
+                                egui::ComboBox::from_id_salt("hitbox_display")
+                                    .selected_text(app_ctx.settings.hitbox_display.to_string())
+                                    .show_ui(ui, |ui| {
+                                        for hitbox_display in HitboxDisplay::VARIANTS {
+                                            ui.selectable_value(
+                                                &mut app_ctx.settings.hitbox_display,
+                                                *hitbox_display,
+                                                hitbox_display.to_string(),
+                                            );
+                                        }
+                                    });
+                            });
+
+                            ui.horizontal(|ui| {
+                                ui.label("Server positions");
+
+                                ui.checkbox(&mut app_ctx.settings.server_position_ghosts, "")
+                                    .on_hover_text("Draw a ghost of the pawns where the server has last placed them.");
+                            });
+                        }
+
+                        ui.horizontal(|ui| {
+                            ui.label("Letterboxing");
+
+                            ui.checkbox(&mut app_ctx.settings.letterboxing, "")
+                                .on_hover_text("Display the game in 16:9 with black bars, so that wider monitors don't show more of the arena.");
+                        });
+
                         ui.horizontal(|ui| {
                             ui.label("Textures");
 
                             if ui.button("Reload all Textures").clicked() {
-                                for material in materials
-                                    .iter()
-                                    .map(|(mat, _la)| mat)
-                                    .collect::<Vec<AssetId<TextureAtlasLayout>>>()
-                                    .into_iter()
-                                {
-                                    materials.remove(material);
+                                reload_texture_atlas(
+                                    &mut materials,
+                                    &mut app_ctx,
+                                    &mut pawn_sprites,
+                                );
+                            }
+                        });
+
+                        ui.checkbox(
+                            &mut app_ctx.settings.hot_reload_resource_pack,
+                            "Reload the resource pack when its files change",
+                        );
+
+                        ui.horizontal(|ui| {
+                            ui.label("Maps per intermission page");
+
+                            ui.add(Slider::new(
+                                &mut app_ctx.settings.intermission_maps_per_page,
+                                1..=8,
+                            ));
+                        });
+
+                        ui.label(RichText::from("Audio").size(20.).strong());
+
+                        ui.horizontal(|ui| {
+                            ui.label("Music volume");
+
+                            ui.add(Slider::new(&mut app_ctx.settings.music_volume, 0.0..=1.0));
+                        });
+
+                        ui.label(RichText::from("Appearance").size(20.).strong());
+
+                        ui.horizontal(|ui| {
+                            ui.label("Pawn color");
+
+                            let mut custom_tint = app_ctx.settings.pawn_tint.is_some();
+
+                            if ui
+                                .checkbox(&mut custom_tint, "")
+                                .on_hover_text("The color is sent to the server when joining.")
+                                .changed()
+                            {
+                                app_ctx.settings.pawn_tint = custom_tint.then_some([255, 255, 255]);
+                            }
+
+                            if let Some(tint) = &mut app_ctx.settings.pawn_tint {
+                                ui.color_edit_button_srgb(tint);
+                            }
+                        });
+
+                        // The server nudges the colors which are too similar to another player's
+                        let assigned_tint = app_ctx.client_connection.as_ref().and_then(|client_connection| {
+                            client_connection
+                                .connected_clients_stats
+                                .read()
+                                .iter()
+                                .find(|client_stats| client_stats.uuid == client_connection.server_metadata.client_uuid)
+                                .and_then(|client_stats| client_stats.tint)
+                        });
+
+                        if let (Some(tint), Some(assigned_tint)) = (app_ctx.settings.pawn_tint, assigned_tint) {
+                            if tint != assigned_tint {
+                                ui.label(
+                                    RichText::from("Your color was too similar to another player's, so the server has adjusted it.")
+                                        .color(Color32::YELLOW),
+                                );
+                            }
+                        }
+
+                        ui.label(RichText::from("Controls").size(20.).strong());
+
+                        ui.horizontal(|ui| {
+                            ui.label("Scoreboard");
+
+                            egui::ComboBox::from_id_salt("scoreboard_mode")
+                                .selected_text(app_ctx.settings.scoreboard_mode.to_string())
+                                .show_ui(ui, |ui| {
+                                    for scoreboard_mode in ScoreboardMode::VARIANTS {
+                                        ui.selectable_value(
+                                            &mut app_ctx.settings.scoreboard_mode,
+                                            *scoreboard_mode,
+                                            scoreboard_mode.to_string(),
+                                        );
+                                    }
+                                });
+                        });
+
+                        ui.horizontal(|ui| {
+                            ui.label("Scoreboard rows");
+
+                            ui.add(
+                                egui::DragValue::new(&mut app_ctx.settings.scoreboard_rows)
+                                    .range(1..=64),
+                            );
+                        });
+
+                        for (action, name) in KeyBindings::BINDABLE_ACTIONS {
+                            ui.horizontal(|ui| {
+                                ui.label(name);
+
+                                if app_ctx.ui_state.rebinding_action == Some(action) {
+                                    ui.add_enabled(false, egui::Button::new("Press a key..."));
+
+                                    // Escape cancels the rebinding
+                                    if let Some(key) = keyboard_input.get_just_pressed().next() {
+                                        if *key != KeyCode::Escape {
+                                            if let Err(err) =
+                                                app_ctx.settings.key_bindings.bind(action, *key)
+                                            {
+                                                app_ctx.egui_toasts.add(
+                                                    Toast::new()
+                                                        .kind(egui_toast::ToastKind::Error)
+                                                        .text(format!("{err}."))
+                                                        .options(
+                                                            ToastOptions::default()
+                                                                .duration(Some(Duration::from_secs(3)))
+                                                                .show_progress(true),
+                                                        ),
+                                                );
+                                            }
+                                        }
+
+                                        app_ctx.ui_state.rebinding_action = None;
+                                    }
+                                } else if let Some(key) = app_ctx.settings.key_bindings.key(action) {
+                                    if ui.button(format!("{key:?}")).clicked() {
+                                        app_ctx.ui_state.rebinding_action = Some(action);
+                                    }
                                 }
+                            });
+                        }
 
-                                app_ctx.texture_atlas_layouts =
-                                    materials.add(TextureAtlasLayout::from_grid(
-                                        UVec2::new(50, 64),
-                                        7,
-                                        1,
-                                        Some(UVec2::new(20, 0)),
-                                        None,
-                                    ));
+                        if ui.button("Reset controls").clicked() {
+                            app_ctx.settings.key_bindings = KeyBindings::default();
+                        }
+
+                        ui.label(RichText::from("Network").size(20.).strong());
+
+                        ui.horizontal(|ui| {
+                            ui.label("Network statistics");
+
+                            ui.checkbox(&mut app_ctx.settings.network_stats, "")
+                                .on_hover_text("Display the server's tick rate and the received update rate.");
+                        });
+
+                        let thresholds = &mut app_ctx.settings.connection_quality_thresholds;
+
+                        ui.horizontal(|ui| {
+                            ui.label("Fair connection above");
+
+                            ui.add(
+                                egui::DragValue::new(&mut thresholds.fair_rtt_ms)
+                                    .range(1..=thresholds.poor_rtt_ms)
+                                    .suffix("ms"),
+                            );
+
+                            ui.add(
+                                egui::DragValue::new(&mut thresholds.fair_loss_percent)
+                                    .range(0.0..=thresholds.poor_loss_percent)
+                                    .suffix("% loss"),
+                            );
+                        });
+
+                        ui.horizontal(|ui| {
+                            ui.label("Poor connection above");
+
+                            ui.add(
+                                egui::DragValue::new(&mut thresholds.poor_rtt_ms)
+                                    .range(thresholds.fair_rtt_ms..=1000)
+                                    .suffix("ms"),
+                            );
+
+                            ui.add(
+                                egui::DragValue::new(&mut thresholds.poor_loss_percent)
+                                    .range(thresholds.fair_loss_percent..=100.)
+                                    .suffix("% loss"),
+                            );
+                        });
+
+                        ui.label(RichText::from("Statistics").size(20.).strong());
+
+                        ui.horizontal(|ui| {
+                            ui.label("Personal bests");
+
+                            if ui.button("Reset").clicked() {
+                                app_ctx.personal_bests = PersonalBests::default();
+                            }
+                        });
+
+                        ui.horizontal(|ui| {
+                            ui.label("Identity");
+
+                            // A new identity takes effect from the next connection, the servers won't recognize the player afterwards
+                            if ui
+                                .button("Reset")
+                                .on_hover_text("The servers will no longer recognize you, your standings won't be restored when you reconnect.")
+                                .clicked()
+                            {
+                                app_ctx.identity = Uuid::new_v4();
+                            }
+                        });
+                    }),
+                punchafriend::PauseWindowState::Mods => egui::Window::new("Mods")
+                    .resizable(false)
+                    .collapsible(false)
+                    .anchor(Align2::CENTER_CENTER, egui::vec2(0., 0.))
+                    .fixed_size(ctx.screen_rect().size() / 2.)
+                    .vscroll(true)
+                    .show(ctx, |ui| {
+                        ui.horizontal(|ui| {
+                            if ui.button("Refresh").clicked() {
+                                app_ctx.ui_state.resource_packs =
+                                    scan_resource_packs(Path::new(MODS_DIRECTORY));
+                            }
+
+                            if ui
+                                .add_enabled(
+                                    app_ctx.custom_textures.is_some(),
+                                    egui::Button::new("Use default textures"),
+                                )
+                                .clicked()
+                            {
+                                app_ctx.ui_state.current_resource_pack = None;
+                                app_ctx.custom_textures = None;
+                                app_ctx.custom_music.clear();
+                                app_ctx.custom_map_thumbnails.clear();
+
+                                reload_texture_atlas(
+                                    &mut materials,
+                                    &mut app_ctx,
+                                    &mut pawn_sprites,
+                                );
                             }
                         });
+
+                        ui.separator();
+
+                        if app_ctx.ui_state.resource_packs.is_empty() {
+                            ui.label(format!(
+                                "No resource packs were found in the `{MODS_DIRECTORY}` folder."
+                            ));
+                        }
+
+                        for (path, resource_pack) in app_ctx.ui_state.resource_packs.clone() {
+                            let pack_name = path
+                                .file_name()
+                                .map(|file_name| file_name.to_string_lossy().to_string())
+                                .unwrap_or_default();
+
+                            ui.group(|ui| {
+                                ui.horizontal(|ui| match resource_pack {
+                                    Ok(resource_pack) => {
+                                        // Display a preview of the pack's textures
+                                        ui.add(
+                                            egui::Image::new(format!(
+                                                "file://{}",
+                                                resource_pack.textures.idle.display()
+                                            ))
+                                            .max_size(vec2(64., 64.)),
+                                        );
+
+                                        ui.vertical(|ui| {
+                                            ui.label(RichText::from(&resource_pack.name).strong());
+
+                                            ui.label(format!(
+                                                "{} by {}",
+                                                resource_pack.version, resource_pack.author
+                                            ));
+                                        });
+
+                                        let is_active = app_ctx.ui_state.current_resource_pack
+                                            == Some(path.clone());
+
+                                        if ui
+                                            .add_enabled(
+                                                !is_active,
+                                                egui::Button::new(if is_active {
+                                                    "Active"
+                                                } else {
+                                                    "Activate"
+                                                }),
+                                            )
+                                            .clicked()
+                                        {
+                                            app_ctx.ui_state.current_resource_pack = Some(path);
+                                            app_ctx.custom_textures = Some(resource_pack.textures);
+                                            app_ctx.custom_music = resource_pack.music;
+                                            app_ctx.custom_map_thumbnails =
+                                                resource_pack.map_thumbnails;
+
+                                            reload_texture_atlas(
+                                                &mut materials,
+                                                &mut app_ctx,
+                                                &mut pawn_sprites,
+                                            );
+                                        }
+                                    }
+                                    // Display why the pack couldn't be loaded
+                                    Err(err) => {
+                                        ui.label(RichText::from(pack_name).strong());
+
+                                        ui.label(RichText::from(err).color(Color32::RED));
+                                    }
+                                });
+                            });
+                        }
                     }),
+                punchafriend::PauseWindowState::QuitConfirmation => {
+                    egui::Window::new("quit_confirmation")
+                        .title_bar(false)
+                        .resizable(false)
+                        .collapsible(false)
+                        .anchor(Align2::CENTER_CENTER, egui::vec2(0., 0.))
+                        .show(ctx, |ui| {
+                            ui.with_layout(Layout::top_down(egui::Align::Center), |ui| {
+                                ui.label(
+                                    RichText::from("Are you sure you want to quit?").size(20.),
+                                );
+
+                                ui.horizontal(|ui| {
+                                    // The application's state is saved by the exit handler
+                                    if ui.button("Quit").clicked() {
+                                        app_exit.send(AppExit::Success);
+                                    }
+
+                                    if ui.button("Cancel").clicked() {
+                                        app_ctx.ui_layer = *state_before.clone();
+                                    }
+                                });
+                            });
+                        })
+                }
+                punchafriend::PauseWindowState::LeaveConfirmation => {
+                    egui::Window::new("leave_confirmation")
+                        .title_bar(false)
+                        .resizable(false)
+                        .collapsible(false)
+                        .anchor(Align2::CENTER_CENTER, egui::vec2(0., 0.))
+                        .show(ctx, |ui| {
+                            ui.with_layout(Layout::top_down(egui::Align::Center), |ui| {
+                                ui.label(RichText::from("Leave the match?").size(20.));
+
+                                ui.horizontal(|ui| {
+                                    if ui.button("Leave").clicked() {
+                                        reset_connection_and_ui(&mut app_ctx, &runtime);
+                                    }
+
+                                    if ui.button("Cancel").clicked() {
+                                        app_ctx.ui_layer = *state_before.clone();
+                                    }
+                                });
+                            });
+                        })
+                }
             };
 
             let window_pos_rect = window_state.unwrap().response.rect;
@@ -453,3 +1342,227 @@ pub fn ui_system(
         }
     }
 }
+
+/// Displays the ping wheel while [`PING_WHEEL_KEY`] is held, and sends the selected ping to the server when the key is released.
+/// The ping is selected by the direction the cursor has been moved in, relative to where the wheel was opened.
+pub fn ping_wheel_system(
+    mut context: EguiContexts,
+    mut app_ctx: ResMut<ApplicationCtx>,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    cameras: Query<(&Camera, &GlobalTransform), With<GameCamera>>,
+) {
+    // The ping wheel can only be used while in a game
+    if !matches!(app_ctx.ui_layer, UiLayer::Game(_)) {
+        app_ctx.ping_wheel_origin = None;
+
+        return;
+    }
+
+    let Ok(window) = windows.get_single() else {
+        return;
+    };
+
+    let Some(cursor_position) = window.cursor_position() else {
+        return;
+    };
+
+    // Open the ping wheel where the cursor is
+    if keyboard_input.just_pressed(PING_WHEEL_KEY) {
+        app_ctx.ping_wheel_origin = Some(cursor_position);
+    }
+
+    let Some(wheel_origin) = app_ctx.ping_wheel_origin else {
+        return;
+    };
+
+    let selected_kind = ping_kind_from_direction(cursor_position - wheel_origin);
+
+    // Get context
+    let ctx = context.ctx_mut();
+
+    let painter = ctx.layer_painter(LayerId::new(Order::Foreground, "ping_wheel".into()));
+
+    let wheel_center = Pos2::new(wheel_origin.x, wheel_origin.y);
+
+    painter.circle_filled(wheel_center, PING_WHEEL_RADIUS, Color32::from_black_alpha(150));
+
+    // Display all of the ping kinds around the wheel
+    for (idx, kind) in PingKind::VARIANTS.iter().enumerate() {
+        let angle = idx as f32 * TAU / PingKind::VARIANTS.len() as f32;
+
+        // The y axis is flipped, as it grows downwards on the screen
+        let label_pos = wheel_center
+            + egui::vec2(angle.cos(), -angle.sin()) * PING_WHEEL_RADIUS * 0.65;
+
+        painter.text(
+            label_pos,
+            Align2::CENTER_CENTER,
+            kind.to_string(),
+            FontId::proportional(16.),
+            if Some(*kind) == selected_kind {
+                Color32::YELLOW
+            } else {
+                Color32::WHITE
+            },
+        );
+    }
+
+    if !keyboard_input.just_released(PING_WHEEL_KEY) {
+        return;
+    }
+
+    // Close the ping wheel
+    app_ctx.ping_wheel_origin = None;
+
+    let Some(kind) = selected_kind else {
+        return;
+    };
+
+    // Convert the wheel's origin into world coordinates
+    // The position has to be relative to the camera's viewport, as it may be letterboxed
+    let Some(position) = cameras.iter().find_map(|(camera, camera_transform)| {
+        let viewport_origin = camera
+            .logical_viewport_rect()
+            .map(|viewport| viewport.min)
+            .unwrap_or_default();
+
+        camera
+            .viewport_to_world_2d(camera_transform, wheel_origin - viewport_origin)
+            .ok()
+    }) else {
+        return;
+    };
+
+    if let Some(client_connection) = &app_ctx.client_connection {
+        let _ = client_connection
+            .remote_server_sender
+            .try_send(RemoteClientRequest {
+                uuid: client_connection.server_metadata.client_uuid,
+                request: ClientRequest::Ping { kind, position },
+            });
+    }
+}
+
+/// Starts connecting to the server in the background with the username and password entered by the user, and with the player's pawn tint.
+/// The result of the connection is received by the [`ApplicationCtx`]'s `connection_receiver`.
+fn connect_to_server(app_ctx: &ApplicationCtx, runtime: &TokioTasksRuntime, address: String) {
+    // Move the sender
+    let sender = app_ctx.connection_sender.clone();
+
+    // Set the channel
+    let cancellation_token = app_ctx.cancellation_token.clone();
+
+    let username = app_ctx.ui_state.username_buffer.clone();
+
+    let password = app_ctx.ui_state.password_buffer.clone();
+
+    let tint = app_ctx.settings.pawn_tint;
+
+    let identity = app_ctx.identity;
+
+    // Create the connecting thread
+    runtime.spawn_background_task(|_ctx| async move {
+        // Attempt to make a connection to the remote address.
+        let client_connection = ClientConnection::connect_to_address(
+            address,
+            username,
+            password,
+            tint,
+            identity,
+            cancellation_token,
+        )
+        .await;
+
+        // Send it to the front end no matter the end result.
+        sender.send(client_connection).await.unwrap();
+    });
+}
+
+/// Returns the [`PingKind`] the direction is pointing at on the ping wheel.
+/// Returns [`None`] if the direction is inside the wheel's deadzone.
+fn ping_kind_from_direction(direction: Vec2) -> Option<PingKind> {
+    if direction.length() < PING_WHEEL_DEADZONE {
+        return None;
+    }
+
+    let segment_angle = TAU / PingKind::VARIANTS.len() as f32;
+
+    // The y axis is flipped, as the cursor's position grows downwards
+    let angle = (-direction.y).atan2(direction.x).rem_euclid(TAU);
+
+    let idx = (angle / segment_angle).round() as usize % PingKind::VARIANTS.len();
+
+    Some(PingKind::VARIANTS[idx])
+}
+
+/// Displays the amount of lives as hearts.
+fn display_lives(lives: u32) -> String {
+    if lives == 0 {
+        String::from("Eliminated")
+    } else {
+        "♥".repeat(lives as usize)
+    }
+}
+
+/// Displays the header of the scoreboard's column, with an arrow if the scoreboard is sorted by the column.
+/// Clicking the header sorts the scoreboard by the column, see [`ScoreboardSort::clicked`].
+fn scoreboard_header(
+    ui: &mut egui::Ui,
+    column: ScoreboardColumn,
+    scoreboard_sort: &mut Option<ScoreboardSort>,
+) {
+    let arrow = match scoreboard_sort {
+        Some(sort) if sort.column == column && sort.descending => " ⬇",
+        Some(sort) if sort.column == column => " ⬆",
+        _ => "",
+    };
+
+    if ui
+        .add(egui::Button::new(format!("{column}{arrow}")).frame(false))
+        .clicked()
+    {
+        *scoreboard_sort = Some(ScoreboardSort::clicked(*scoreboard_sort, column));
+    }
+}
+
+/// Displays the map's preview, the active resource pack's thumbnail is used if it has one for the map.
+/// A placeholder with the map's name is displayed if the thumbnail can't be loaded, ie. its file has been removed since the pack was activated.
+fn map_preview(ui: &mut egui::Ui, map_name: &str, thumbnail: Option<&Path>) {
+    let Some(thumbnail) = thumbnail else {
+        ui.add(
+            egui::Image::new(egui::include_image!("../../../assets/map_imgs/test.png"))
+                .fit_to_exact_size(MAP_PREVIEW_SIZE),
+        );
+
+        return;
+    };
+
+    let uri = format!("file://{}", thumbnail.display());
+
+    // The image is loaded in the background, the image displays a spinner until it has been loaded
+    if ui
+        .ctx()
+        .try_load_image(&uri, egui::load::SizeHint::default())
+        .is_ok()
+    {
+        ui.add(egui::Image::new(uri).fit_to_exact_size(MAP_PREVIEW_SIZE));
+
+        return;
+    }
+
+    let (rect, _) = ui.allocate_exact_size(MAP_PREVIEW_SIZE, Sense::hover());
+
+    let visuals = ui.visuals();
+
+    ui.painter().rect_filled(rect, 3., visuals.extreme_bg_color);
+    ui.painter()
+        .rect_stroke(rect, 3., visuals.widgets.noninteractive.bg_stroke);
+    ui.painter().text(
+        rect.center(),
+        Align2::CENTER_CENTER,
+        map_name,
+        FontId::proportional(18.),
+        visuals.weak_text_color(),
+    );
+}
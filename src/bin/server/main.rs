@@ -1,19 +1,37 @@
 mod systems;
 mod ui;
 
-use bevy::{log::LogPlugin, prelude::*};
+use bevy::{
+    diagnostic::{Diagnostic, RegisterDiagnostic},
+    log::LogPlugin,
+    prelude::*,
+};
 use bevy_egui::EguiPlugin;
 use bevy_rapier2d::{
     plugin::{NoUserData, RapierPhysicsPlugin},
     render::RapierDebugRenderPlugin,
 };
 use punchafriend::{
-    game::collision::{
-        check_for_collision_with_attack_object, check_players_out_of_bounds, CollisionGroupSet,
+    game::{
+        collision::{
+            check_for_collision_with_attack_object, check_players_out_of_bounds,
+            respawn_pending_pawns, separate_overlapping_pawns, CollisionGroupSet,
+        },
+        combat::{
+            despawn_expired_attack_objects, expire_projectiles_on_impact, spawn_pending_attacks,
+            tick_attack_cooldowns,
+        },
+        dummy::reset_training_dummies,
+        hill::handle_hill_control,
+        modifiers::scale_pawns,
+    },
+    networking::SERVER_TICK_RATE,
+    server::{
+        ApplicationCtx, MatchSnapshot, ServerConfig, MATCH_SNAPSHOT_FILE, SERVER_CONFIG_FILE,
     },
-    server::ApplicationCtx,
     RandomEngine,
 };
+use std::path::Path;
 
 fn main() {
     let mut app = App::new();
@@ -26,22 +44,70 @@ fn main() {
     app.add_plugins(EguiPlugin);
     app.add_plugins(bevy_framepace::FramepacePlugin);
     app.add_plugins(bevy_tokio_tasks::TokioTasksPlugin::default());
-    app.add_plugins(RapierPhysicsPlugin::<NoUserData>::pixels_per_meter(100.0));
+    // The physics is stepped in `FixedPostUpdate`, so that it is part of the tick measured by the tick watchdog
+    app.add_plugins(RapierPhysicsPlugin::<NoUserData>::pixels_per_meter(100.0).in_fixed_schedule());
     app.add_plugins(RapierDebugRenderPlugin::default());
 
-    app.insert_resource(ApplicationCtx::default());
+    let mut app_ctx = ApplicationCtx::default();
+
+    // The operator's configuration from the last launch
+    match ServerConfig::load(Path::new(SERVER_CONFIG_FILE)) {
+        Ok(config) => app_ctx.apply_config(config),
+        Err(err) => eprintln!("Failed to load the server's config, using the defaults: {err:#}"),
+    }
+
+    // The match interrupted by the last shutdown, the stale matches are ignored
+    if app_ctx.settings.persist_match {
+        match MatchSnapshot::load(Path::new(MATCH_SNAPSHOT_FILE)) {
+            Ok(snapshot) => app_ctx.restored_match = snapshot,
+            Err(err) => eprintln!("Failed to load the match snapshot: {err:#}"),
+        }
+    }
+
+    app.insert_resource(app_ctx);
     app.insert_resource(CollisionGroupSet::new());
     app.insert_resource(RandomEngine::new());
+    app.insert_resource(Time::<Fixed>::from_hz(SERVER_TICK_RATE));
+    app.register_diagnostic(
+        Diagnostic::new(systems::TICK_DURATION)
+            .with_suffix("ms")
+            .with_max_history_length(systems::TICK_DURATION_HISTORY_LENGTH),
+    );
 
     app.add_systems(Startup, systems::setup_window);
     app.add_systems(Update, ui::ui_system);
     app.add_systems(Update, systems::frame);
+    app.add_systems(Update, systems::sample_metrics);
+    app.add_systems(Update, systems::kick_high_ping_clients);
+    // The window's close request is turned into an `AppExit` in `PostUpdate`, the app exits before the next `Update`
+    app.add_systems(Last, systems::save_server_config);
+    app.add_systems(Update, systems::save_match_snapshot);
+    app.add_systems(Update, systems::handle_map_change.after(ui::ui_system));
+    app.add_systems(FixedFirst, systems::start_tick_watchdog);
+    app.add_systems(FixedLast, systems::finish_tick_watchdog);
     app.add_systems(FixedUpdate, systems::recv_tick);
+    app.add_systems(FixedUpdate, systems::move_pawns.after(systems::recv_tick));
+    app.add_systems(FixedUpdate, systems::apply_arena_gravity);
     app.add_systems(FixedUpdate, systems::send_tick);
     app.add_systems(FixedUpdate, systems::reset_jump_remaining_for_player);
+    app.add_systems(FixedUpdate, spawn_pending_attacks);
+    app.add_systems(FixedUpdate, tick_attack_cooldowns);
     app.add_systems(FixedUpdate, check_for_collision_with_attack_object);
+    app.add_systems(
+        FixedUpdate,
+        expire_projectiles_on_impact.after(check_for_collision_with_attack_object),
+    );
+    app.add_systems(
+        FixedUpdate,
+        despawn_expired_attack_objects.after(expire_projectiles_on_impact),
+    );
     app.add_systems(FixedUpdate, check_players_out_of_bounds);
+    app.add_systems(FixedUpdate, respawn_pending_pawns);
+    app.add_systems(FixedUpdate, reset_training_dummies);
+    app.add_systems(FixedUpdate, separate_overlapping_pawns);
     app.add_systems(FixedUpdate, systems::tick);
+    app.add_systems(FixedUpdate, scale_pawns);
+    app.add_systems(FixedUpdate, handle_hill_control);
 
     app.run();
 }
@@ -1,20 +1,44 @@
 pub const MINUTE_SECS: u64 = 60;
 
+/// The amount of time a client has to wait between two pings.
+pub const PING_COOLDOWN_MILLIS: i64 = 1500;
+
+/// The amount of time a client has to wait between two pawn type changes.
+pub const PAWN_TYPE_CHANGE_COOLDOWN_MILLIS: i64 = 1000;
+
+/// The length of the sudden death, the round ends without a winner if no one has fallen off by then.
+pub const SUDDEN_DEATH_SECS: u64 = 60;
+
 use chrono::{Local, TimeDelta};
 use punchafriend::{
-    game::map::{load_map_from_mapinstance, MapObjectUpdate, MovementState},
+    game::map::{
+        load_map_from_mapinstance, push_out_of_map_objects, MapObjectUpdate, MovementState,
+        BASE_GRAVITY, DEFAULT_SPAWN_POSITION,
+    },
     networking::{
-        server::{send_request_to_all_clients, ServerInstance},
-        OngoingGameData, PawnUpdate,
+        server::{
+            clients_in_arena, forget_client, kick_client, send_request_to_all_clients,
+            ArenaAssignments, ServerInstance,
+        },
+        ClientStatistics, OngoingGameData, PawnUpdate, ProjectileUpdate,
         ServerGameState::{self, Intermission},
-        ServerRequest,
+        ServerRequest, TickUpdateBatch,
     },
 };
-use std::{f32::consts::PI, sync::Arc, time::Duration};
+use std::{
+    collections::HashMap,
+    f32::consts::PI,
+    net::SocketAddr,
+    path::Path,
+    sync::{atomic::Ordering, Arc},
+    time::{Duration, Instant},
+};
 
 use bevy::{
+    app::AppExit,
     asset::Assets,
     core_pipeline::core_2d::Camera2d,
+    diagnostic::{DiagnosticPath, Diagnostics, DiagnosticsStore},
     ecs::{
         entity::Entity,
         event::EventReader,
@@ -22,10 +46,10 @@ use bevy::{
         system::{Commands, Query, Res, ResMut},
         world::Mut,
     },
-    math::Vec3,
+    math::{Vec2, Vec3},
     render::mesh::Mesh,
     sprite::ColorMaterial,
-    time::{Real, Time, Timer},
+    time::{Fixed, Real, Time, Timer, TimerMode},
     transform::components::Transform,
     winit::{UpdateMode, WinitSettings},
 };
@@ -36,23 +60,88 @@ use parking_lot::Mutex;
 use punchafriend::{
     game::{
         collision::{check_for_collision_with_map_and_player, CollisionGroupSet},
+        combat::Projectile,
         map::MapElement,
-        pawns::{handle_game_input, Pawn},
+        modifiers::MatchModifiers,
+        pawns::{
+            apply_held_movement, handle_game_input, spawn_pawn, Pawn, PawnType, MAX_JUMPS,
+            PAWN_HALF_EXTENTS,
+        },
     },
     networking::{
+        metrics::{MetricsReport, SERVER_METRICS},
         server::{notify_client_about_player_disconnect, send_request_to_client},
         GameInput, RemoteServerRequest, ServerTickUpdate,
     },
-    server::ApplicationCtx,
+    server::{
+        ApplicationCtx, ArenaSnapshot, MatchSnapshot, ServerConfig, SuddenDeath,
+        HIGH_PING_KICK_GRACE_PERIOD, MATCH_SNAPSHOT_FILE, MATCH_SNAPSHOT_INTERVAL_SECS,
+        SERVER_CONFIG_FILE,
+    },
     RandomEngine,
 };
+use rand::rngs::SmallRng;
 use tokio::net::tcp::OwnedWriteHalf;
 
 use crate::ui::{
-    create_intermission_data_all, notify_valid_clients_intermission,
-    notify_valid_clients_map_change,
+    create_intermission_data, notify_valid_clients_intermission, notify_valid_clients_map_change,
 };
 
+/// Returns the gravity of the arena's current map, the pawns' jumps are scaled with it.
+fn arena_gravity(game_states: &[ServerGameState], arena: usize) -> Vec2 {
+    match game_states.get(arena) {
+        Some(ServerGameState::OngoingGame(ongoing_game_data)) => ongoing_game_data
+            .current_map
+            .gravity(&ongoing_game_data.modifiers),
+        _ => BASE_GRAVITY,
+    }
+}
+
+/// Pulls the pawns with the gravity of their arena's map, see [`arena_gravity`].
+/// The pawns ignore the physics world's gravity, so that every arena can play on a map with its own gravity.
+pub fn apply_arena_gravity(
+    app_ctx: Res<ApplicationCtx>,
+    mut pawns: Query<(&Pawn, &mut Velocity)>,
+    game_time: Res<Time>,
+) {
+    let Some(server_instance) = &app_ctx.server_instance else {
+        return;
+    };
+
+    let game_states = server_instance.game_states.read();
+
+    for (pawn, mut velocity) in pawns.iter_mut() {
+        velocity.linvel += arena_gravity(&game_states, pawn.arena) * game_time.delta_secs();
+    }
+}
+
+/// Moves the pawns according to the movement inputs their players are holding, see [`punchafriend::game::pawns::HeldMovement`].
+/// The clients only send the changes of their movement inputs, so the pawns move every tick regardless of the rate of the clients' packets.
+pub fn move_pawns(
+    mut commands: Commands,
+    app_ctx: Res<ApplicationCtx>,
+    mut players_query: Query<(
+        Entity,
+        Mut<Pawn>,
+        Mut<KinematicCharacterController>,
+        &Transform,
+        &Velocity,
+    )>,
+    game_time: Res<Time>,
+) {
+    let Some(server_instance) = &app_ctx.server_instance else {
+        return;
+    };
+
+    let game_states = server_instance.game_states.read();
+
+    for mut query_item in players_query.iter_mut() {
+        let gravity = arena_gravity(&game_states, query_item.1.arena);
+
+        apply_held_movement(&mut query_item, &mut commands, &game_time, gravity);
+    }
+}
+
 pub fn recv_tick(
     mut commands: Commands,
     mut app_ctx: ResMut<ApplicationCtx>,
@@ -68,14 +157,24 @@ pub fn recv_tick(
     collision_groups: Res<CollisionGroupSet>,
     game_time: Res<Time>,
 ) {
+    let tick_start = Instant::now();
+
+    // Reborrow the resource so that its fields can be borrowed separately
+    let app_ctx = &mut *app_ctx;
+
     // Increment global tick counter
     let current_tick_count = app_ctx.tick_count.wrapping_add(1);
 
     // Set the global tick count
     app_ctx.tick_count = current_tick_count;
 
+    let super_armor = app_ctx.settings.super_armor;
+    let jump_cut = app_ctx.settings.jump_cut;
+
     // Handle an existing connection
     if let Some(server_instance) = &mut app_ctx.server_instance {
+        let game_states = server_instance.game_states.clone();
+
         if let Some(remote_receiver) = &mut server_instance.client_udp_receiver {
             // Clone the connected clients list's handle
             let connected_clients_clone = server_instance.connected_client_tcp_handles.clone();
@@ -89,8 +188,11 @@ pub fn recv_tick(
                         continue;
                     }
 
+                    // The jumps are scaled with the gravity of the pawn's arena
+                    let gravity = arena_gravity(&game_states.read(), query_item.1.arena);
+
                     // Iter over all the inputs from the packet
-                    for action in &client_req.inputs {
+                    for action in &client_req.game_inputs() {
                         // Handle game input
                         handle_game_input(
                             &mut query_item,
@@ -99,6 +201,9 @@ pub fn recv_tick(
                             &collision_groups,
                             &mut rand.inner,
                             &game_time,
+                            gravity,
+                            super_armor,
+                            jump_cut,
                         );
 
                         // If the client requested to disconnect we should broadcast the message to all of the clients
@@ -112,9 +217,24 @@ pub fn recv_tick(
                             // Move the DashMap's handle
                             let connected_clients_clone = connected_clients_clone.clone();
 
-                            // The uuid of the client who has disconnected
-                            let removed_uuid =
-                                connected_clients_clone.remove(&address).unwrap().1 .0;
+                            // The uuid of the client who has disconnected, the client has already been removed if its connection has ended
+                            let Some((_, (removed_uuid, _))) =
+                                connected_clients_clone.remove(&address)
+                            else {
+                                break 'query_loop;
+                            };
+
+                            // Free the player slot and the arena of the disconnected client, and drop it from the leaderboard
+                            forget_client(
+                                &server_instance.player_slots,
+                                &server_instance.arena_assignments,
+                                &server_instance.connected_clients_stats,
+                                &server_instance.client_identities,
+                                &removed_uuid,
+                            );
+
+                            // Forget the state kept about the disconnected client
+                            app_ctx.forget_player_state(&removed_uuid);
 
                             // Spawn an async task to broadcast the disconnection message to the clients
                             notify_players_player_disconnect(
@@ -131,10 +251,15 @@ pub fn recv_tick(
             }
         }
     }
+
+    SERVER_METRICS
+        .recv_tick_micros
+        .store(tick_start.elapsed().as_micros() as u64, Ordering::Relaxed);
 }
 
+/// Starts the round in the arena and notifies the arena's players about it, `connected_client_list` holds the arena's players.
 fn notify_players_game_start(
-    runtime: &ResMut<'_, TokioTasksRuntime>,
+    runtime: &TokioTasksRuntime,
     connected_client_list: Arc<
         dashmap::DashMap<
             std::net::SocketAddr,
@@ -145,16 +270,22 @@ fn notify_players_game_start(
         >,
     >,
     map_instance: punchafriend::game::map::MapInstance,
+    modifiers: MatchModifiers,
+    round_duration: Duration,
     server_instance: &ServerInstance,
+    arena: usize,
 ) {
-    let round_end_date = Local::now()
-        .to_utc()
-        .checked_add_signed(TimeDelta::from_std(Duration::from_secs(8 * MINUTE_SECS)).unwrap())
+    let round_start_date = Local::now().to_utc();
+
+    let round_end_date = round_start_date
+        .checked_add_signed(TimeDelta::from_std(round_duration).unwrap())
         .unwrap();
 
-    *server_instance.game_state.write() = ServerGameState::OngoingGame(OngoingGameData {
+    server_instance.game_states.write()[arena] = ServerGameState::OngoingGame(OngoingGameData {
         current_map: map_instance.clone(),
+        round_start_date,
         round_end_date,
+        modifiers,
     });
 
     runtime.spawn_background_task(async move |_task| {
@@ -168,7 +299,12 @@ fn notify_players_game_start(
                 RemoteServerRequest {
                     request: punchafriend::networking::ServerRequest::ServerGameStateControl(
                         punchafriend::networking::ServerGameState::OngoingGame(
-                            OngoingGameData::new(map_instance.clone(), round_end_date),
+                            OngoingGameData::new(
+                                map_instance.clone(),
+                                round_start_date,
+                                round_end_date,
+                                modifiers,
+                            ),
                         ),
                     ),
                 },
@@ -179,6 +315,569 @@ fn notify_players_game_start(
     });
 }
 
+/// Starts the arena's round if enough players have joined it while it was waiting for players.
+/// Otherwise notifies the arena's players about the amount of players needed, if the amount of players in the arena has changed.
+fn handle_waiting_for_players(
+    app_ctx: &mut ApplicationCtx,
+    commands: &mut Commands,
+    collision_groups: &CollisionGroupSet,
+    runtime: &TokioTasksRuntime,
+    existing_pawns: &[uuid::Uuid],
+    arena: usize,
+) {
+    if !app_ctx.arenas[arena].waiting_for_players {
+        return;
+    }
+
+    let Some(server_instance) = &app_ctx.server_instance else {
+        return;
+    };
+
+    // The arena can only wait for players in an ongoing game, an intermission is handled by its own timer
+    let ServerGameState::OngoingGame(ongoing_game_data) =
+        server_instance.game_states.read()[arena].clone()
+    else {
+        return;
+    };
+
+    let connected_clients = clients_in_arena(
+        &server_instance.connected_client_tcp_handles,
+        &server_instance.arena_assignments.lock(),
+        arena,
+    );
+
+    let current = connected_clients.len();
+    let needed = app_ctx.settings.min_players_to_start;
+
+    if current >= needed {
+        // A resumed round continues with the time it had left
+        let round_duration = app_ctx
+            .restored_match
+            .as_mut()
+            .and_then(|restored_match| restored_match.arena_mut(arena)?.round_time_left.take())
+            .unwrap_or(Duration::from_secs(MINUTE_SECS * 8));
+
+        // Notify the clients about the round's start, this also sends them the new round end date
+        // The round keeps the modifiers its map has been loaded with
+        notify_players_game_start(
+            runtime,
+            connected_clients,
+            ongoing_game_data.current_map,
+            ongoing_game_data.modifiers,
+            round_duration,
+            server_instance,
+            arena,
+        );
+
+        let arena_round = &mut app_ctx.arenas[arena];
+
+        arena_round.waiting_for_players = false;
+        arena_round.announced_player_count = None;
+
+        // Start the round timer
+        arena_round.game_round_timer =
+            Some(Timer::new(round_duration, bevy::time::TimerMode::Once));
+
+        // Give every player a fresh set of lives
+        reset_player_lives(
+            app_ctx,
+            commands,
+            collision_groups,
+            runtime,
+            existing_pawns,
+            arena,
+        );
+    } else if app_ctx.arenas[arena].announced_player_count != Some(current) {
+        app_ctx.arenas[arena].announced_player_count = Some(current);
+
+        // Notify the arena's players about the amount of players needed
+        runtime.spawn_background_task(async move |_ctx| {
+            send_request_to_all_clients(
+                RemoteServerRequest {
+                    request: ServerRequest::WaitingForPlayers { current, needed },
+                },
+                connected_clients,
+            )
+            .await;
+        });
+    }
+}
+
+/// Resets the lives of the arena's players to the server's stock lives, and respawns the arena's players who don't have a pawn.
+fn reset_player_lives(
+    app_ctx: &mut ApplicationCtx,
+    commands: &mut Commands,
+    collision_groups: &CollisionGroupSet,
+    runtime: &TokioTasksRuntime,
+    existing_pawns: &[uuid::Uuid],
+    arena: usize,
+) {
+    // The eliminated players are respawned with the rest of the pawn-less players
+    app_ctx.arenas[arena].elimination_order.clear();
+
+    ensure_pawns_for_connected_clients(app_ctx, commands, collision_groups, existing_pawns, arena);
+
+    let Some(server_instance) = &app_ctx.server_instance else {
+        return;
+    };
+
+    let arena_assignments = server_instance.arena_assignments.lock();
+
+    let mut client_stats_handle = server_instance.connected_clients_stats.write();
+
+    let arena_players = client_stats_handle
+        .iter()
+        .filter(|client_stats| arena_assignments.arena_of(&client_stats.uuid) == Some(arena))
+        .cloned()
+        .collect::<Vec<ClientStatistics>>();
+
+    drop(arena_assignments);
+
+    let mut modified_client_stats = Vec::new();
+
+    // Set the lives of the arena's players
+    for mut client_stats in arena_players {
+        client_stats_handle.remove(&client_stats);
+
+        client_stats.lives = app_ctx.settings.stock_lives;
+
+        client_stats_handle.insert(client_stats.clone());
+
+        modified_client_stats.push(client_stats);
+    }
+
+    drop(client_stats_handle);
+
+    let connected_clients = server_instance.connected_client_tcp_handles.clone();
+
+    // Notify all the clients about the new entries, the leaderboard is shared by the arenas
+    runtime.spawn_background_task(async move |_ctx| {
+        send_request_to_all_clients(
+            RemoteServerRequest {
+                request: ServerRequest::PlayersStatisticsChange(modified_client_stats),
+            },
+            connected_clients,
+        )
+        .await;
+    });
+}
+
+/// Spawns a pawn for every player of the arena who doesn't have one, so that no player is left without a pawn when the arena's round starts.
+/// The pawns are spawned with the client's player slot and pawn type. The clients in `existing_pawns` are skipped, so calling this repeatedly doesn't spawn duplicates.
+fn ensure_pawns_for_connected_clients(
+    app_ctx: &ApplicationCtx,
+    commands: &mut Commands,
+    collision_groups: &CollisionGroupSet,
+    existing_pawns: &[uuid::Uuid],
+    arena: usize,
+) {
+    let Some(server_instance) = &app_ctx.server_instance else {
+        return;
+    };
+
+    let arena_assignments = server_instance.arena_assignments.lock();
+
+    let arena_players = server_instance
+        .connected_clients_stats
+        .read()
+        .iter()
+        .filter(|client_stats| arena_assignments.arena_of(&client_stats.uuid) == Some(arena))
+        .cloned()
+        .collect::<Vec<ClientStatistics>>();
+
+    drop(arena_assignments);
+
+    spawn_missing_pawns(
+        commands,
+        &arena_players,
+        &app_ctx.pawn_types,
+        app_ctx.settings.pawn_scale,
+        collision_groups,
+        existing_pawns,
+        arena,
+    );
+}
+
+/// Spawns a pawn in the arena for every player who isn't in `existing_pawns`, with the player's slot and pawn type.
+fn spawn_missing_pawns(
+    commands: &mut Commands,
+    players: &[ClientStatistics],
+    pawn_types: &HashMap<uuid::Uuid, PawnType>,
+    pawn_scale: f32,
+    collision_groups: &CollisionGroupSet,
+    existing_pawns: &[uuid::Uuid],
+    arena: usize,
+) {
+    for client_stats in players
+        .iter()
+        .filter(|client_stats| !existing_pawns.contains(&client_stats.uuid))
+    {
+        spawn_pawn(
+            commands,
+            client_stats.uuid,
+            client_stats.player_slot,
+            client_stats.tint,
+            pawn_types
+                .get(&client_stats.uuid)
+                .copied()
+                .unwrap_or_default(),
+            arena,
+            DEFAULT_SPAWN_POSITION,
+            pawn_scale,
+            collision_groups,
+        );
+    }
+}
+
+/// Gives the stock lives to the players who have joined during the round, as they are created without any lives.
+fn assign_stock_lives_to_new_players(app_ctx: &ApplicationCtx, runtime: &TokioTasksRuntime) {
+    let (Some(server_instance), Some(stock_lives)) =
+        (&app_ctx.server_instance, app_ctx.settings.stock_lives)
+    else {
+        return;
+    };
+
+    let mut client_stats_handle = server_instance.connected_clients_stats.write();
+
+    let new_players = client_stats_handle
+        .iter()
+        .filter(|client_stats| client_stats.lives.is_none())
+        .cloned()
+        .collect::<Vec<ClientStatistics>>();
+
+    if new_players.is_empty() {
+        return;
+    }
+
+    let mut modified_client_stats = Vec::new();
+
+    for mut client_stats in new_players {
+        client_stats_handle.remove(&client_stats);
+
+        client_stats.lives = Some(stock_lives);
+
+        client_stats_handle.insert(client_stats.clone());
+
+        modified_client_stats.push(client_stats);
+    }
+
+    let connected_clients = server_instance.connected_client_tcp_handles.clone();
+
+    // Notify all the clients about the new entries
+    runtime.spawn_background_task(async move |_ctx| {
+        send_request_to_all_clients(
+            RemoteServerRequest {
+                request: ServerRequest::PlayersStatisticsChange(modified_client_stats),
+            },
+            connected_clients,
+        )
+        .await;
+    });
+}
+
+/// Ends the arena's round, lets the arena's players know who have been eliminated during the round and starts the arena's intermission.
+fn end_round(
+    app_ctx: &mut ApplicationCtx,
+    runtime: &ResMut<'_, TokioTasksRuntime>,
+    rand: &mut SmallRng,
+    arena: usize,
+) {
+    // Let the clients know who have been eliminated during the round
+    notify_players_elimination_summary(app_ctx, runtime, arena);
+
+    if let Some(instance) = &app_ctx.server_instance {
+        let client_list = clients_in_arena(
+            &instance.connected_client_tcp_handles,
+            &instance.arena_assignments.lock(),
+            arena,
+        );
+
+        let arena_round = &mut app_ctx.arenas[arena];
+
+        let intermission_data =
+            create_intermission_data(&app_ctx.settings, arena_round.last_played_map, rand);
+
+        instance.game_states.write()[arena] =
+            ServerGameState::Intermission(intermission_data.clone());
+
+        notify_valid_clients_intermission(runtime, client_list, intermission_data);
+
+        arena_round.game_round_timer = None;
+        arena_round.intermission_timer =
+            Some(Timer::from_seconds(30., bevy::time::TimerMode::Once));
+    }
+
+    // The arena's resumed round has ended
+    if let Some(restored_match) = &mut app_ctx.restored_match {
+        if let Some(arena_snapshot) = restored_match.arenas.get_mut(arena) {
+            *arena_snapshot = None;
+        }
+    }
+
+    // The standings of the players who haven't reconnected belonged to the ended rounds
+    if app_ctx
+        .restored_match
+        .as_ref()
+        .is_some_and(|restored_match| restored_match.arenas.iter().all(Option::is_none))
+    {
+        app_ctx.restored_match = None;
+    }
+
+    // A restart during the intermission mustn't resume the finished round, the other arenas' rounds are kept
+    let result = match match_snapshot(app_ctx).filter(|_| app_ctx.settings.persist_match) {
+        Some(snapshot) => snapshot.save(Path::new(MATCH_SNAPSHOT_FILE)),
+        None => MatchSnapshot::delete(Path::new(MATCH_SNAPSHOT_FILE)),
+    };
+
+    if let Err(err) = result {
+        eprintln!("Failed to update the match snapshot: {err:#}");
+    }
+}
+
+/// Returns the players of the arena who have tied for first by their kills, this is empty if there is only one player in first.
+/// The players who have been eliminated can't take part in the sudden death.
+fn players_tied_for_first(app_ctx: &ApplicationCtx, arena: usize) -> Vec<uuid::Uuid> {
+    let Some(server_instance) = &app_ctx.server_instance else {
+        return Vec::new();
+    };
+
+    let arena_assignments = server_instance.arena_assignments.lock();
+
+    let client_stats = server_instance.connected_clients_stats.read();
+
+    let contenders = client_stats
+        .iter()
+        .filter(|client_stats| {
+            client_stats.lives != Some(0)
+                && arena_assignments.arena_of(&client_stats.uuid) == Some(arena)
+        })
+        .collect::<Vec<&ClientStatistics>>();
+
+    let Some(most_kills) = contenders
+        .iter()
+        .map(|client_stats| client_stats.kills)
+        .max()
+    else {
+        return Vec::new();
+    };
+
+    let tied_players = contenders
+        .iter()
+        .filter(|client_stats| client_stats.kills == most_kills)
+        .map(|client_stats| client_stats.uuid)
+        .collect::<Vec<uuid::Uuid>>();
+
+    if tied_players.len() > 1 {
+        tied_players
+    } else {
+        Vec::new()
+    }
+}
+
+/// Starts a sudden death in the arena between the tied players, only their pawns are respawned and the rest of the arena's players spectate.
+/// `pawns` holds the pawns of the arena.
+fn start_sudden_death(
+    app_ctx: &mut ApplicationCtx,
+    commands: &mut Commands,
+    collision_groups: &CollisionGroupSet,
+    runtime: &TokioTasksRuntime,
+    pawns: Vec<(Entity, uuid::Uuid, usize, Option<[u8; 3]>)>,
+    tied_players: Vec<uuid::Uuid>,
+    arena: usize,
+) {
+    let mut spectators = Vec::new();
+
+    for (entity, uuid, player_slot, tint) in pawns {
+        commands.entity(entity).despawn();
+
+        // Respawn the tied players so that they start the sudden death from the same position
+        if tied_players.contains(&uuid) {
+            let pawn_type = app_ctx.pawn_types.get(&uuid).copied().unwrap_or_default();
+
+            spawn_pawn(
+                commands,
+                uuid,
+                player_slot,
+                tint,
+                pawn_type,
+                arena,
+                DEFAULT_SPAWN_POSITION,
+                app_ctx.settings.pawn_scale,
+                collision_groups,
+            );
+        } else {
+            spectators.push(uuid);
+        }
+    }
+
+    let arena_round = &mut app_ctx.arenas[arena];
+
+    arena_round.game_round_timer = None;
+    arena_round.sudden_death = Some(SuddenDeath::new(
+        tied_players.clone(),
+        spectators,
+        Duration::from_secs(SUDDEN_DEATH_SECS),
+    ));
+
+    let Some(server_instance) = &app_ctx.server_instance else {
+        return;
+    };
+
+    let connected_clients = clients_in_arena(
+        &server_instance.connected_client_tcp_handles,
+        &server_instance.arena_assignments.lock(),
+        arena,
+    );
+
+    // Notify the arena's players about the sudden death
+    runtime.spawn_background_task(async move |_ctx| {
+        send_request_to_all_clients(
+            RemoteServerRequest {
+                request: ServerRequest::SuddenDeath(tied_players),
+            },
+            connected_clients,
+        )
+        .await;
+    });
+}
+
+/// Ends the arena's sudden death and respawns the pawns of the players who have spectated it.
+fn end_sudden_death(
+    app_ctx: &mut ApplicationCtx,
+    commands: &mut Commands,
+    collision_groups: &CollisionGroupSet,
+    arena: usize,
+) {
+    let (Some(sudden_death), Some(server_instance)) = (
+        app_ctx.arenas[arena].sudden_death.take(),
+        &app_ctx.server_instance,
+    ) else {
+        return;
+    };
+
+    let client_stats = server_instance.connected_clients_stats.read();
+
+    // Respawn the spectators who are still connected
+    for uuid in sudden_death.spectators {
+        if let Some(client_stats) = client_stats
+            .iter()
+            .find(|client_stats| client_stats.uuid == uuid)
+        {
+            spawn_pawn(
+                commands,
+                uuid,
+                client_stats.player_slot,
+                client_stats.tint,
+                app_ctx.pawn_types.get(&uuid).copied().unwrap_or_default(),
+                arena,
+                DEFAULT_SPAWN_POSITION,
+                app_ctx.settings.pawn_scale,
+                collision_groups,
+            );
+        }
+    }
+}
+
+/// Returns whether there is only one player left in the arena with lives remaining, this can only happen if the players' lives are limited.
+fn is_last_player_standing(app_ctx: &ApplicationCtx, arena: usize) -> bool {
+    if app_ctx.settings.stock_lives.is_none() {
+        return false;
+    }
+
+    app_ctx
+        .server_instance
+        .as_ref()
+        .is_some_and(|server_instance| {
+            let arena_assignments = server_instance.arena_assignments.lock();
+
+            let client_stats = server_instance.connected_clients_stats.read();
+
+            let arena_players = client_stats
+                .iter()
+                .filter(|client_stats| {
+                    arena_assignments.arena_of(&client_stats.uuid) == Some(arena)
+                })
+                .collect::<Vec<&ClientStatistics>>();
+
+            arena_players.len() > 1
+                && arena_players
+                    .iter()
+                    .filter(|client_stats| client_stats.lives != Some(0))
+                    .count()
+                    <= 1
+        })
+}
+
+/// Sends the order the arena's players have been eliminated in to the arena's players.
+fn notify_players_elimination_summary(
+    app_ctx: &ApplicationCtx,
+    runtime: &TokioTasksRuntime,
+    arena: usize,
+) {
+    let Some(server_instance) = &app_ctx.server_instance else {
+        return;
+    };
+
+    // Lives are unlimited, so there is nothing to summarize
+    if app_ctx.settings.stock_lives.is_none() {
+        return;
+    }
+
+    let client_stats = server_instance.connected_clients_stats.read();
+
+    // Look up the statistics of the eliminated players, disconnected players are left out
+    let elimination_summary = app_ctx.arenas[arena]
+        .elimination_order
+        .iter()
+        .filter_map(|uuid| {
+            client_stats
+                .iter()
+                .find(|client_stats| client_stats.uuid == *uuid)
+                .cloned()
+        })
+        .collect::<Vec<ClientStatistics>>();
+
+    let connected_clients = clients_in_arena(
+        &server_instance.connected_client_tcp_handles,
+        &server_instance.arena_assignments.lock(),
+        arena,
+    );
+
+    runtime.spawn_background_task(async move |_ctx| {
+        send_request_to_all_clients(
+            RemoteServerRequest {
+                request: ServerRequest::EliminationSummary(elimination_summary),
+            },
+            connected_clients,
+        )
+        .await;
+    });
+}
+
+/// Notifies the client that its request has been rejected, the client displays the reason to the user.
+fn notify_client_request_rejected(
+    runtime: &TokioTasksRuntime,
+    connected_clients: Arc<
+        dashmap::DashMap<std::net::SocketAddr, (uuid::Uuid, Arc<Mutex<OwnedWriteHalf>>)>,
+    >,
+    socket_addr: std::net::SocketAddr,
+    reason: String,
+) {
+    runtime.spawn_background_task(move |_ctx| async move {
+        if let Some(handle) = connected_clients.get(&socket_addr) {
+            let (_, tcp_write) = handle.value();
+
+            let _ = send_request_to_client(
+                &mut tcp_write.lock(),
+                RemoteServerRequest {
+                    request: ServerRequest::RequestRejected(reason),
+                },
+            )
+            .await;
+        }
+    });
+}
+
 fn notify_players_player_disconnect(
     runtime: &ResMut<'_, TokioTasksRuntime>,
     connected_clients_clone: std::sync::Arc<
@@ -196,6 +895,16 @@ fn notify_players_player_disconnect(
             notify_client_about_player_disconnect(&mut tcp_stream.lock(), removed_uuid)
                 .await
                 .unwrap();
+
+            // Let the client drop the player's row from the leaderboard
+            send_request_to_client(
+                &mut tcp_stream.lock(),
+                RemoteServerRequest {
+                    request: ServerRequest::PlayerStatsRemoved(removed_uuid),
+                },
+            )
+            .await
+            .unwrap();
         }
     });
 }
@@ -212,8 +921,11 @@ pub fn send_tick(
         ),
         Changed<Transform>,
     >,
+    projectiles_query: Query<(Entity, &Projectile, &Transform), Changed<Transform>>,
     runtime: Res<TokioTasksRuntime>,
 ) {
+    let tick_start = Instant::now();
+
     // Increment global tick counter
     let current_tick_count = app_ctx.tick_count.wrapping_add(1);
 
@@ -221,6 +933,9 @@ pub fn send_tick(
     app_ctx.tick_count = current_tick_count;
 
     if let Some(server_instance) = &mut app_ctx.server_instance {
+        // The updates of the tick are collected per arena, so that every update is only serialized once
+        let mut arena_batches: HashMap<usize, TickUpdateBatch> = HashMap::new();
+
         // The tick function is only called if an entity changes its position, so we dont need to check for any kind of input from the clients
         // Iter over all the entities
         for (_entity, player, _, position, velocity) in players_query.iter() {
@@ -230,39 +945,505 @@ pub fn send_tick(
                     PawnUpdate::new(*position, *velocity, player.clone(), current_tick_count),
                 ));
 
-            // Serialize the packet into bytes so it can be sent later
-            let message_bytes = rmp_serde::to_vec(&server_tick_update).unwrap();
+            // The pawn is only sent to the players of its own arena
+            arena_batches
+                .entry(player.arena)
+                .or_default()
+                .push(&server_tick_update);
+        }
+
+        // The projectiles are simulated by the server only, the clients just display them at the received positions
+        for (entity, projectile, transform) in projectiles_query.iter() {
+            let server_tick_update = ServerTickUpdate::new(
+                punchafriend::networking::TickUpdateType::Projectile(ProjectileUpdate {
+                    id: entity.to_bits(),
+                    position: transform.translation.truncate(),
+                }),
+            );
+
+            arena_batches
+                .entry(projectile.arena)
+                .or_default()
+                .push(&server_tick_update);
+        }
+
+        let arena_assignments = server_instance.arena_assignments.lock();
+
+        for (arena, tick_update_batch) in arena_batches {
+            send_tick_update_batch_to_arena(
+                server_instance,
+                &arena_assignments,
+                &runtime,
+                tick_update_batch,
+                arena,
+            );
+        }
+    }
+
+    SERVER_METRICS
+        .send_tick_micros
+        .store(tick_start.elapsed().as_micros() as u64, Ordering::Relaxed);
+}
+
+/// Sends the tick's updates to the clients playing in the arena over UDP.
+/// A single task sends the batch's datagrams to every player of the arena, instead of a task being spawned for every update and player.
+fn send_tick_update_batch_to_arena(
+    server_instance: &ServerInstance,
+    arena_assignments: &ArenaAssignments,
+    runtime: &TokioTasksRuntime,
+    tick_update_batch: TickUpdateBatch,
+    arena: usize,
+) {
+    // Fetch the socket addresses of the arena's players
+    let addresses: Vec<SocketAddr> = server_instance
+        .connected_client_tcp_handles
+        .iter()
+        .filter(|client| arena_assignments.arena_of(&client.value().0) == Some(arena))
+        .map(|client| *client.key())
+        .collect();
+
+    if addresses.is_empty() {
+        return;
+    }
+
+    // Every update is serialized once, the players are sent the same buffer
+    let datagrams = tick_update_batch.into_shared();
+
+    // Clone the UdpSocket's handle
+    let udp_socket = server_instance.udp_socket.clone();
+
+    runtime.spawn_background_task(move |_ctx| async move {
+        for addr in addresses {
+            for datagram in datagrams.iter() {
+                // Send the message to the client, a failed send mustn't hold up the rest of the arena's players
+                if let Err(err) = udp_socket.send_to(datagram, addr).await {
+                    eprintln!("Failed to send the tick update to {addr}: {err}");
+
+                    continue;
+                }
+
+                SERVER_METRICS.record_udp_sent(datagram.len());
+            }
+        }
+    });
+}
+
+/// Samples the server's metrics once every sampling period and updates the report shown in the UI and served by the metrics endpoint.
+pub fn sample_metrics(
+    mut app_ctx: ResMut<ApplicationCtx>,
+    time: Res<Time<Real>>,
+    fixed_time: Res<Time<Fixed>>,
+    diagnostics: Res<DiagnosticsStore>,
+) {
+    app_ctx.metrics_sample_timer.tick(time.delta());
+
+    if !app_ctx.metrics_sample_timer.just_finished() {
+        return;
+    }
+
+    let elapsed = app_ctx.metrics_sample_timer.duration();
+
+    let current_snapshot = SERVER_METRICS.snapshot();
+    let previous_snapshot = std::mem::replace(&mut app_ctx.last_metrics_snapshot, current_snapshot);
+
+    let Some(server_instance) = &app_ctx.server_instance else {
+        return;
+    };
+
+    let pending_udp_messages = server_instance
+        .client_udp_receiver
+        .as_ref()
+        .map(|receiver| receiver.len())
+        .unwrap_or_default();
+
+    let pending_tcp_messages = server_instance
+        .client_tcp_receiver
+        .as_ref()
+        .map(|receiver| receiver.len())
+        .unwrap_or_default();
+
+    let mut metrics_report = MetricsReport::new(
+        &previous_snapshot,
+        &current_snapshot,
+        elapsed,
+        server_instance.connected_client_tcp_handles.len(),
+        pending_udp_messages,
+        pending_tcp_messages,
+    );
+
+    metrics_report.average_tick_duration = diagnostics
+        .get(&TICK_DURATION)
+        .and_then(|diagnostic| diagnostic.average())
+        .map(|average_millis| Duration::from_secs_f64(average_millis / 1000.))
+        .unwrap_or_default();
+    metrics_report.tick_budget = fixed_time.timestep();
+
+    *server_instance.metrics_report.write() = metrics_report;
+}
+
+/// The diagnostic of the fixed ticks' wall-clock duration in milliseconds.
+pub const TICK_DURATION: DiagnosticPath = DiagnosticPath::const_new("server/tick_duration");
+
+/// The amount of ticks the average tick duration is calculated from, this is about two seconds worth of ticks.
+pub const TICK_DURATION_HISTORY_LENGTH: usize = 128;
 
-            // Get the lenght of the message and turn it into bytes
-            let message_length_bytes = (message_bytes.len() as u32).to_be_bytes();
+/// The minimum amount of time between two logged tick overruns.
+pub const TICK_OVERRUN_WARNING_INTERVAL: Duration = Duration::from_secs(5);
 
-            // Iter over all of the connected clients
-            for client in server_instance.connected_client_tcp_handles.iter() {
-                // Fetch client socket address
-                let addr = *client.key();
+/// Marks the start of the fixed tick, this runs before every other fixed system including the physics step.
+pub fn start_tick_watchdog(mut app_ctx: ResMut<ApplicationCtx>) {
+    app_ctx.tick_started_at = Some(Instant::now());
+}
+
+/// Measures the duration of the fixed tick and warns the operator if it has taken longer than the fixed timestep.
+/// The simulation falls behind the clients if the ticks keep overrunning their budget.
+pub fn finish_tick_watchdog(
+    mut app_ctx: ResMut<ApplicationCtx>,
+    mut diagnostics: Diagnostics,
+    time: Res<Time<Fixed>>,
+) {
+    let Some(tick_started_at) = app_ctx.tick_started_at.take() else {
+        return;
+    };
 
-                // Clone the UdpSocket's handle
-                let udp_socket = server_instance.udp_socket.clone();
+    let tick_duration = tick_started_at.elapsed();
 
-                // Clone the messages' bytes
-                let message_bytes = message_bytes.clone();
+    diagnostics.add_measurement(&TICK_DURATION, || tick_duration.as_secs_f64() * 1000.);
+
+    let tick_budget = time.timestep();
+
+    if tick_duration <= tick_budget {
+        return;
+    }
 
-                // Turn the message length into bytes
-                let mut message_length_bytes = message_length_bytes.to_vec();
+    SERVER_METRICS.tick_overruns.fetch_add(1, Ordering::Relaxed);
 
-                // Spawn an async task to send the information to all of the other clients
-                runtime.spawn_background_task(move |_ctx| async move {
-                    // Create the messaage buffer which is going to be sent
-                    message_length_bytes.extend(message_bytes);
+    let now = Instant::now();
 
-                    // Send the message to the client
-                    udp_socket
-                        .send_to(&message_length_bytes, addr)
-                        .await
-                        .unwrap();
-                });
+    if app_ctx
+        .last_tick_overrun_warning
+        .is_some_and(|last_warning| {
+            now.duration_since(last_warning) < TICK_OVERRUN_WARNING_INTERVAL
+        })
+    {
+        return;
+    }
+
+    app_ctx.last_tick_overrun_warning = Some(now);
+
+    eprintln!(
+        "A server tick took {:.2}ms, which is over the budget of {:.2}ms. The server can't keep up with the players and objects.",
+        tick_duration.as_secs_f64() * 1000.,
+        tick_budget.as_secs_f64() * 1000.
+    );
+}
+
+/// Kicks the players whose ping has stayed above the [`punchafriend::server::Settings::high_ping_kick_threshold`] for [`HIGH_PING_KICK_GRACE_PERIOD`].
+pub fn kick_high_ping_clients(
+    mut app_ctx: ResMut<ApplicationCtx>,
+    runtime: ResMut<TokioTasksRuntime>,
+) {
+    // Reborrow the resource so that its fields can be borrowed separately
+    let app_ctx = &mut *app_ctx;
+
+    let Some(server_instance) = &app_ctx.server_instance else {
+        return;
+    };
+
+    let Some(high_ping_kick_threshold) = app_ctx.settings.high_ping_kick_threshold else {
+        app_ctx.high_ping_since.clear();
+
+        return;
+    };
+
+    let threshold = Duration::from_millis(high_ping_kick_threshold as u64);
+
+    let now = Instant::now();
+
+    let round_trip_times = &server_instance.client_round_trip_times;
+
+    // Forget the players whose ping has come down or who have disconnected
+    app_ctx.high_ping_since.retain(|uuid, _| {
+        round_trip_times
+            .get(uuid)
+            .is_some_and(|round_trip_time| *round_trip_time > threshold)
+    });
+
+    for round_trip_time in round_trip_times.iter() {
+        if *round_trip_time.value() > threshold {
+            app_ctx
+                .high_ping_since
+                .entry(*round_trip_time.key())
+                .or_insert(now);
+        }
+    }
+
+    let kicked_clients = app_ctx
+        .high_ping_since
+        .iter()
+        .filter(|(_, high_ping_since)| {
+            now.duration_since(**high_ping_since) >= HIGH_PING_KICK_GRACE_PERIOD
+        })
+        .map(|(uuid, _)| *uuid)
+        .collect::<Vec<_>>();
+
+    for uuid in kicked_clients {
+        app_ctx.high_ping_since.remove(&uuid);
+        round_trip_times.remove(&uuid);
+
+        let Some(game_socket_address) = server_instance
+            .connected_client_tcp_handles
+            .iter()
+            .find(|handle| handle.value().0 == uuid)
+            .map(|handle| *handle.key())
+        else {
+            continue;
+        };
+
+        if let Some(client_stats) = server_instance
+            .connected_clients_stats
+            .read()
+            .iter()
+            .find(|client_stats| client_stats.uuid == uuid)
+        {
+            eprintln!("Kicking {} for their high ping.", client_stats.username);
+        }
+
+        forget_client(
+            &server_instance.player_slots,
+            &server_instance.arena_assignments,
+            &server_instance.connected_clients_stats,
+            &server_instance.client_identities,
+            &uuid,
+        );
+
+        let connected_client_tcp_handles = server_instance.connected_client_tcp_handles.clone();
+
+        let reason = format!(
+            "Your ping has been over {high_ping_kick_threshold}ms for {} seconds.",
+            HIGH_PING_KICK_GRACE_PERIOD.as_secs()
+        );
+
+        runtime.spawn_background_task(async move |mut ctx| {
+            kick_client(
+                &mut ctx,
+                &connected_client_tcp_handles,
+                game_socket_address,
+                reason,
+            )
+            .await;
+        });
+    }
+}
+
+/// The amount of time the configuration has to stay untouched before it is saved.
+pub const CONFIG_SAVE_DEBOUNCE_SECS: f32 = 1.;
+
+/// Saves the [`ServerConfig`] to the [`SERVER_CONFIG_FILE`] when the operator changes it.
+/// The changes are saved after they have settled so that dragging a value doesn't write to the disk every frame, a pending change is saved immediately when the server is closed.
+/// This runs in `Last`, so that the exit sent in `PostUpdate` when the window is closed is read too.
+pub fn save_server_config(
+    app_ctx: Res<ApplicationCtx>,
+    time: Res<Time<Real>>,
+    mut exit_events: EventReader<AppExit>,
+    mut observed_config: bevy::ecs::system::Local<Option<ServerConfig>>,
+    mut debounce: bevy::ecs::system::Local<Option<Timer>>,
+) {
+    let config = app_ctx.config();
+
+    // The config loaded at startup is only observed, so that it isn't written back unchanged
+    let Some(observed) = &*observed_config else {
+        *observed_config = Some(config);
+
+        return;
+    };
+
+    // Restart the debounce timer every time the config is modified
+    if *observed != config {
+        *observed_config = Some(config.clone());
+
+        *debounce = Some(Timer::from_seconds(
+            CONFIG_SAVE_DEBOUNCE_SECS,
+            TimerMode::Once,
+        ));
+    }
+
+    let Some(timer) = &mut *debounce else {
+        return;
+    };
+
+    timer.tick(time.delta());
+
+    if !(timer.finished() || exit_events.read().next().is_some()) {
+        return;
+    }
+
+    *debounce = None;
+
+    if let Err(err) = config.save(Path::new(SERVER_CONFIG_FILE)) {
+        eprintln!("Failed to save the server's config: {err}");
+    }
+}
+
+/// Saves the ongoing match to the [`MATCH_SNAPSHOT_FILE`] every [`MATCH_SNAPSHOT_INTERVAL_SECS`], if the operator has enabled [`punchafriend::server::Settings::persist_match`].
+/// The standings of the players who haven't reconnected since the last restart are kept in the snapshot, so that they aren't lost by another restart.
+pub fn save_match_snapshot(
+    app_ctx: Res<ApplicationCtx>,
+    time: Res<Time<Real>>,
+    mut save_timer: bevy::ecs::system::Local<Option<Timer>>,
+) {
+    if !app_ctx.settings.persist_match || app_ctx.server_instance.is_none() {
+        return;
+    }
+
+    let save_timer = save_timer.get_or_insert_with(|| {
+        Timer::from_seconds(MATCH_SNAPSHOT_INTERVAL_SECS, TimerMode::Repeating)
+    });
+
+    if !save_timer.tick(time.delta()).just_finished() {
+        return;
+    }
+
+    let Some(snapshot) = match_snapshot(&app_ctx) else {
+        return;
+    };
+
+    if let Err(err) = snapshot.save(Path::new(MATCH_SNAPSHOT_FILE)) {
+        eprintln!("Failed to save the match snapshot: {err}");
+    }
+}
+
+/// Returns the snapshot of the ongoing match, this is [`None`] if none of the arenas are playing a round.
+/// Only the rounds being played are saved, an intermission starts a new round anyway.
+fn match_snapshot(app_ctx: &ApplicationCtx) -> Option<MatchSnapshot> {
+    let server_instance = app_ctx.server_instance.as_ref()?;
+
+    let restored_match = app_ctx.restored_match.as_ref();
+
+    let arenas = server_instance
+        .game_states
+        .read()
+        .iter()
+        .enumerate()
+        .map(|(arena, game_state)| {
+            let ServerGameState::OngoingGame(ongoing_game_data) = game_state else {
+                return None;
+            };
+
+            // A resumed round which is still waiting for its players keeps the time it had left
+            let restored_time_left = restored_match
+                .and_then(|restored_match| restored_match.arenas.get(arena)?.as_ref())
+                .and_then(|arena_snapshot| arena_snapshot.round_time_left);
+
+            Some(ArenaSnapshot {
+                current_map: ongoing_game_data.current_map.clone(),
+                modifiers: ongoing_game_data.modifiers,
+                round_time_left: app_ctx
+                    .arenas
+                    .get(arena)
+                    .and_then(|arena_round| arena_round.game_round_timer.as_ref())
+                    .map(Timer::remaining)
+                    .or(restored_time_left),
+            })
+        })
+        .collect::<Vec<Option<ArenaSnapshot>>>();
+
+    if arenas.iter().all(Option::is_none) {
+        return None;
+    }
+
+    let mut client_stats = server_instance
+        .connected_clients_stats
+        .read()
+        .iter()
+        .filter_map(|client_stats| {
+            let identity = *server_instance.client_identities.get(&client_stats.uuid)?;
+
+            Some((identity, client_stats.clone()))
+        })
+        .collect::<Vec<(uuid::Uuid, ClientStatistics)>>();
+
+    client_stats.extend(
+        restored_match
+            .iter()
+            .flat_map(|restored_match| restored_match.client_stats.iter().cloned()),
+    );
+
+    Some(MatchSnapshot {
+        saved_at: Local::now().to_utc(),
+        arenas,
+        client_stats,
+    })
+}
+
+/// Swaps the current map of every arena playing a round to the pending map change without resetting the rounds' state.
+/// The pawns overlapping with the new map's objects are pushed out of them, so that they don't get stuck.
+pub fn handle_map_change(
+    mut app_ctx: ResMut<ApplicationCtx>,
+    mut commands: Commands,
+    current_map_objects: Query<(Entity, &MapElement, &mut Transform), Without<Pawn>>,
+    mut pawns: Query<(&Pawn, &mut Transform), Without<MapElement>>,
+    collision_groups: Res<CollisionGroupSet>,
+    runtime: Res<TokioTasksRuntime>,
+) {
+    let Some(map_instance) = app_ctx.pending_map_change.take() else {
+        return;
+    };
+
+    let Some(server_instance) = &app_ctx.server_instance else {
+        return;
+    };
+
+    let arena_count = server_instance.game_states.read().len();
+
+    for arena in 0..arena_count {
+        // The map can only be changed during a round
+        if let ServerGameState::OngoingGame(ongoing_game_data) =
+            &mut server_instance.game_states.write()[arena]
+        {
+            ongoing_game_data.current_map = map_instance.clone();
+        } else {
+            continue;
+        }
+
+        // Push out the pawns standing where the new geometry appears
+        for (_, mut transform) in pawns.iter_mut().filter(|(pawn, _)| pawn.arena == arena) {
+            if let Some(new_position) = push_out_of_map_objects(
+                &map_instance,
+                transform.translation.truncate(),
+                PAWN_HALF_EXTENTS * transform.scale.truncate(),
+            ) {
+                transform.translation.x = new_position.x;
+                transform.translation.y = new_position.y;
             }
         }
+
+        load_map_from_mapinstance(
+            map_instance.clone(),
+            &mut commands,
+            collision_groups.clone(),
+            arena..arena + 1,
+            &current_map_objects,
+        );
+
+        let connected_clients = clients_in_arena(
+            &server_instance.connected_client_tcp_handles,
+            &server_instance.arena_assignments.lock(),
+            arena,
+        );
+
+        let map_instance = map_instance.clone();
+
+        // Notify the arena's players about the new map
+        runtime.spawn_background_task(async move |_ctx| {
+            send_request_to_all_clients(
+                RemoteServerRequest {
+                    request: ServerRequest::MapChange(map_instance),
+                },
+                connected_clients,
+            )
+            .await;
+        });
     }
 }
 
@@ -278,7 +1459,8 @@ pub fn reset_jump_remaining_for_player(
         character_entity_query,
     ) {
         if let Ok(mut local_player) = local_player_query.get_mut(colliding_entity) {
-            local_player.jumps_remaining = 2;
+            local_player.jumps_remaining = MAX_JUMPS;
+            local_player.jump_rising = false;
         }
     }
 }
@@ -305,12 +1487,30 @@ pub fn tick(
     app_ctx: Res<ApplicationCtx>,
 ) {
     if let Some(server_instance) = &app_ctx.server_instance {
-        let connected_clients = server_instance.connected_client_tcp_handles.clone();
+        let arena_assignments = server_instance.arena_assignments.lock();
+
+        // The map objects' updates are only sent to the players of the objects' arena
+        let arena_clients = (0..arena_assignments.arena_count())
+            .map(|arena| {
+                clients_in_arena(
+                    &server_instance.connected_client_tcp_handles,
+                    &arena_assignments,
+                    arena,
+                )
+            })
+            .collect::<Vec<_>>();
+
+        drop(arena_assignments);
+
         let udp_socket = server_instance.udp_socket.clone();
 
         for (_element, mut map_element, mut transform) in map_element_query.iter_mut() {
             let map_element_init_pos = map_element.initial_position;
 
+            let Some(connected_clients) = arena_clients.get(map_element.arena) else {
+                continue;
+            };
+
             match &mut map_element.object_type {
                 // If the map element is static we dont need to send the updated coordinates to the client
                 punchafriend::game::map::ObjectType::Static => (),
@@ -433,245 +1633,535 @@ pub fn frame(
         Without<MapElement>,
     >,
     collision_groups: Res<CollisionGroupSet>,
+    mut rand: ResMut<RandomEngine>,
 ) {
-    // Increment the round timer, to know when does this round finish
-    if let Some(round_timer) = &mut app_ctx.game_round_timer {
-        round_timer.tick(real_time.delta());
+    // The clients who already have a pawn, these are skipped when the pawns are respawned at the start of a round
+    let existing_pawns = players_query
+        .iter()
+        .map(|(_, pawn, ..)| pawn.uuid)
+        .collect::<Vec<_>>();
+
+    // Every arena plays its own rounds, with its own timers and vote
+    for arena in 0..app_ctx.arenas.len() {
+        // Start the round if the arena has been waiting for enough players to join
+        handle_waiting_for_players(
+            &mut app_ctx,
+            &mut commands,
+            &collision_groups,
+            &runtime,
+            &existing_pawns,
+            arena,
+        );
     }
 
-    // If there is any existing intermission timer increment it
-    if let Some(intermission_timer) = &mut app_ctx.intermission_timer {
-        intermission_timer.tick(real_time.delta());
-    }
-
-    // If there is a round timer check the state of it
-    if let Some(round_timer) = app_ctx.game_round_timer.clone() {
-        if round_timer.finished() {
-            if let Some(instance) = &mut app_ctx.server_instance {
-                let client_list = instance.connected_client_tcp_handles.clone();
+    // Players joining mid-round should not have unlimited lives
+    assign_stock_lives_to_new_players(&app_ctx, &runtime);
 
-                let intermission_data = create_intermission_data_all();
+    for arena in 0..app_ctx.arenas.len() {
+        let arena_round = &mut app_ctx.arenas[arena];
 
-                *instance.game_state.write() =
-                    ServerGameState::Intermission(intermission_data.clone());
+        // Increment the round timer, to know when does this round finish
+        if let Some(round_timer) = &mut arena_round.game_round_timer {
+            round_timer.tick(real_time.delta());
+        }
 
-                notify_valid_clients_intermission(&runtime, client_list, intermission_data);
+        // If there is any existing intermission timer increment it
+        if let Some(intermission_timer) = &mut arena_round.intermission_timer {
+            intermission_timer.tick(real_time.delta());
+        }
 
-                app_ctx.game_round_timer = None;
-                app_ctx.intermission_timer =
-                    Some(Timer::from_seconds(30., bevy::time::TimerMode::Once));
+        // If there is a round timer check the state of it
+        if let Some(round_timer) = app_ctx.arenas[arena].game_round_timer.clone() {
+            // The round also ends if there is only one player left with lives remaining
+            if round_timer.finished() || is_last_player_standing(&app_ctx, arena) {
+                // A tie for first is decided by a sudden death if the round's time is up
+                let tied_players = if round_timer.finished() && app_ctx.settings.sudden_death {
+                    players_tied_for_first(&app_ctx, arena)
+                } else {
+                    Vec::new()
+                };
+
+                if tied_players.is_empty() {
+                    end_round(&mut app_ctx, &runtime, &mut rand.inner, arena);
+                } else {
+                    let pawns = players_query
+                        .iter()
+                        .filter(|(_, pawn, ..)| pawn.arena == arena)
+                        .map(|(entity, pawn, ..)| (entity, pawn.uuid, pawn.player_slot, pawn.tint))
+                        .collect::<Vec<_>>();
+
+                    start_sudden_death(
+                        &mut app_ctx,
+                        &mut commands,
+                        &collision_groups,
+                        &runtime,
+                        pawns,
+                        tied_players,
+                        arena,
+                    );
+                }
             }
         }
-    }
 
-    // If there is any existing intermission timer get the immutable state of it
-    if let Some(timer) = app_ctx.intermission_timer.clone() {
-        if let Some(server_instance) = &app_ctx.server_instance {
-            // If the countdown has ended or all of the votes have been casted notify all the clients about the intermission end, and send the new map.
-            if timer.finished()
-                || (app_ctx.intermission_total_votes
-                    == server_instance.connected_client_tcp_handles.len())
-                    && !server_instance.connected_client_tcp_handles.is_empty()
-            {
-                let game_state = server_instance.game_state.read().clone();
+        // Check the state of the sudden death if there is one
+        if let Some(sudden_death) = &mut app_ctx.arenas[arena].sudden_death {
+            sudden_death.timer.tick(real_time.delta());
 
-                if let Intermission(intermission_data) = game_state.clone() {
-                    let most_voted_entry =
-                        intermission_data.selectable_maps.iter().max_by_key(|e| e.1);
+            if sudden_death.has_ended || sudden_death.timer.finished() {
+                end_sudden_death(&mut app_ctx, &mut commands, &collision_groups, arena);
 
-                    if let Some((voted_map_name, _vote_count)) = most_voted_entry {
-                        let connected_client_list =
-                            server_instance.connected_client_tcp_handles.clone();
+                end_round(&mut app_ctx, &runtime, &mut rand.inner, arena);
+            }
+        }
+
+        // If there is any existing intermission timer get the immutable state of it
+        if let Some(timer) = app_ctx.arenas[arena].intermission_timer.clone() {
+            if let Some(server_instance) = &app_ctx.server_instance {
+                let arena_clients = clients_in_arena(
+                    &server_instance.connected_client_tcp_handles,
+                    &server_instance.arena_assignments.lock(),
+                    arena,
+                );
+
+                // If the countdown has ended or all of the arena's players have voted notify them about the intermission end, and send the new map.
+                if timer.finished()
+                    || (app_ctx.arenas[arena].intermission_total_votes == arena_clients.len())
+                        && !arena_clients.is_empty()
+                {
+                    let game_state = server_instance.game_states.read()[arena].clone();
+
+                    // The map won by the vote
+                    let mut played_map = None;
+
+                    if let Intermission(intermission_data) = game_state.clone() {
+                        let voted_map_name = intermission_data.voted_map();
+
+                        if intermission_data.selectable_maps.is_empty() {
+                            eprintln!(
+                                "There were no maps to vote for, falling back to {voted_map_name}."
+                            );
+                        }
 
                         let map_instance = voted_map_name.into_map_instance();
 
+                        played_map = Some(voted_map_name);
+
                         let map_instance_clone = map_instance.clone();
 
+                        // The operator's modifiers are applied from the start of the next round
+                        let modifiers = app_ctx.settings.modifiers;
+
                         notify_players_game_start(
                             &runtime,
-                            connected_client_list,
+                            arena_clients.clone(),
                             map_instance,
+                            modifiers,
+                            Duration::from_secs(MINUTE_SECS * 8),
                             server_instance,
+                            arena,
                         );
 
                         load_map_from_mapinstance(
                             map_instance_clone.clone(),
                             &mut commands,
                             collision_groups.clone(),
-                            current_game_objects,
+                            arena..arena + 1,
+                            &current_game_objects,
                         );
-
-                        *(server_instance.game_state.write()) =
-                            ServerGameState::OngoingGame(OngoingGameData {
-                                current_map: map_instance_clone.clone(),
-                                round_end_date: Local::now()
-                                    .to_utc()
-                                    .checked_add_signed(TimeDelta::seconds(60 * 8))
-                                    .unwrap(),
-                            });
                     }
-                }
 
-                // Reset the timer's state
-                app_ctx.intermission_timer = None;
+                    let connected_player_count = arena_clients.len();
 
-                // Reset the round timer's state
-                app_ctx.game_round_timer = Some(Timer::new(
-                    Duration::from_secs(60 * 8),
-                    bevy::time::TimerMode::Once,
-                ));
+                    let arena_round = &mut app_ctx.arenas[arena];
+
+                    // Reset the timer's state
+                    arena_round.intermission_timer = None;
 
-                app_ctx.intermission_total_votes = 0;
+                    // Remember the map, so that it can be left out of the next vote
+                    if played_map.is_some() {
+                        arena_round.last_played_map = played_map;
+                    }
+
+                    // Reset the round timer's state
+                    arena_round.game_round_timer = Some(Timer::new(
+                        Duration::from_secs(60 * 8),
+                        bevy::time::TimerMode::Once,
+                    ));
+
+                    arena_round.intermission_total_votes = 0;
+
+                    // Give every player a fresh set of lives
+                    reset_player_lives(
+                        &mut app_ctx,
+                        &mut commands,
+                        &collision_groups,
+                        &runtime,
+                        &existing_pawns,
+                        arena,
+                    );
+
+                    // Freeze the round timer if there aren't enough players to start the round
+                    if connected_player_count < app_ctx.settings.min_players_to_start {
+                        let arena_round = &mut app_ctx.arenas[arena];
+
+                        arena_round.game_round_timer = None;
+                        arena_round.waiting_for_players = true;
+                    }
+                }
             }
         }
+    }
 
-        if let Some(server_instance) = &mut app_ctx.server_instance {
-                                        let connected_clients_clone = server_instance.connected_client_tcp_handles.clone();
-                                        // If there is a tcp_listener try receiving the messages sent by the sender thread
-            if let Some(tcp_receiver) = &mut server_instance.client_tcp_receiver {
-                // Try receiving the message
-                if let Ok((message, socket_addr)) = tcp_receiver.try_recv() {
-                    //  Match the message type
-                    match message.request {
-                        punchafriend::networking::ClientRequest::Vote(
-                            voted_map_name_discriminant,
-                        ) => {
-                            // If the client has sent a message check the state of the server.
-                            match &mut *server_instance.game_state.clone().write() {
-                                punchafriend::networking::ServerGameState::Pause => {}
-                                punchafriend::networking::ServerGameState::Intermission(
-                                    server_intermission_data,
-                                ) => {
-                                    if let Some(idx) = server_intermission_data
-                                        .selectable_maps
-                                        .iter()
-                                        .position(|(map, _)| *map == voted_map_name_discriminant)
-                                    {
-                                        // Increment the voted map's vote count
-                                        server_intermission_data.selectable_maps[idx].1 += 1;
-
-                                        // Increment total round count, to check if all the clients have voted
-                                        app_ctx.intermission_total_votes += 1;
-                                        
-                                        runtime.spawn_background_task(async move |_ctx| {
-                                            send_request_to_all_clients(RemoteServerRequest { request: ServerRequest::PlayerVote((message.uuid.clone(), voted_map_name_discriminant)) }, connected_clients_clone).await;
-                                        });
+    // Reborrow the resource so that its fields can be borrowed separately
+    let app_ctx = &mut *app_ctx;
+
+    if let Some(server_instance) = &mut app_ctx.server_instance {
+        let connected_clients_clone = server_instance.connected_client_tcp_handles.clone();
+
+        // The requests are received every frame, not only while there is an intermission timer, so that the pings are answered during the rounds too
+        // If there is a tcp_listener try receiving the messages sent by the sender thread
+        if let Some(tcp_receiver) = &mut server_instance.client_tcp_receiver {
+            // Try receiving the message
+            if let Ok((message, socket_addr)) = tcp_receiver.try_recv() {
+                //  Match the message type
+                match message.request {
+                    punchafriend::networking::ClientRequest::Vote(
+                        voted_map_name_discriminant,
+                    ) => {
+                        let arena = server_instance
+                            .arena_assignments
+                            .lock()
+                            .arena_of(&message.uuid)
+                            .unwrap_or_default();
+
+                        // If the client has sent a message check the state of the client's arena.
+                        match &mut server_instance.game_states.clone().write()[arena] {
+                            punchafriend::networking::ServerGameState::Pause => {}
+                            punchafriend::networking::ServerGameState::Intermission(
+                                server_intermission_data,
+                            ) => {
+                                if let Some(idx) = server_intermission_data
+                                    .selectable_maps
+                                    .iter()
+                                    .position(|(map, _)| *map == voted_map_name_discriminant)
+                                {
+                                    // Increment the voted map's vote count
+                                    server_intermission_data.selectable_maps[idx].1 += 1;
+
+                                    // Increment total round count, to check if all the arena's players have voted
+                                    if let Some(arena_round) = app_ctx.arenas.get_mut(arena) {
+                                        arena_round.intermission_total_votes += 1;
                                     }
-                                }
-                                punchafriend::networking::ServerGameState::OngoingGame(
-                                    ongoing_game_data,
-                                ) => {
-                                    let connected_client_tcp_handles =
-                                        server_instance.connected_client_tcp_handles.clone();
+                                    
+                                    let vote_tally = server_intermission_data.selectable_maps.clone();
 
-                                    let socket_addr = socket_addr;
-                                    let ongoing_game_data = ongoing_game_data.clone();
+                                    let arena_clients = clients_in_arena(&connected_clients_clone, &server_instance.arena_assignments.lock(), arena);
 
+                                    // Send the updated vote counts to the arena's players
                                     runtime.spawn_background_task(async move |_ctx| {
-                                        if let Some(handle) = connected_client_tcp_handles
-                                            .get(&socket_addr)
-                                        {
-                                            let (_, tcp_write) = handle.value();
-
-                                            send_request_to_client(
-                                                &mut tcp_write.lock(), 
-                                                RemoteServerRequest {
-                                                    request: punchafriend::networking::ServerRequest::ServerGameStateControl(
-                                                        punchafriend::networking::ServerGameState::OngoingGame(
-                                                            OngoingGameData::new(ongoing_game_data.current_map.clone(), ongoing_game_data.round_end_date)
-                                                        )
-                                                    )
-                                                }
-                                            ).await.unwrap();
-                                        }
+                                        send_request_to_all_clients(RemoteServerRequest { request: ServerRequest::VoteTally(vote_tally) }, arena_clients).await;
                                     });
                                 }
-                            };
-                        }
-                        punchafriend::networking::ClientRequest::RTTMeasurement(timestamp) => {
-                            let connected_client_tcp_handles =
-                                server_instance.connected_client_tcp_handles.clone();
-
-                            runtime.spawn_background_task(async move |_ctx| {
-                                if let Some(handle) = connected_client_tcp_handles
-                                    .get(&socket_addr)
-                                {
-                                    let (_, tcp_write) = handle.value();
-
-                                    send_request_to_client(
-                                        &mut tcp_write.lock(), 
-                                        RemoteServerRequest {
-                                            request: punchafriend::networking::ServerRequest::RTTMeasurement(timestamp)
-                                        }
-                                    ).await.unwrap();
-                                }
-                            });
-                        }
-                        punchafriend::networking::ClientRequest::PawnTypeChange(
-                            desired_pawn_type,
-                        ) => {
-                            if let Some((_entity, mut pawn, ..)) = players_query
-                                .iter_mut()
-                                .find(|(_e, pawn, ..)| pawn.uuid == message.uuid)
-                            {
-                                pawn.pawn_type = desired_pawn_type;
-
-                                let connected_clients_clone =
+                            }
+                            punchafriend::networking::ServerGameState::OngoingGame(
+                                ongoing_game_data,
+                            ) => {
+                                let connected_client_tcp_handles =
                                     server_instance.connected_client_tcp_handles.clone();
 
+                                let socket_addr = socket_addr;
+                                let ongoing_game_data = ongoing_game_data.clone();
+
                                 runtime.spawn_background_task(async move |_ctx| {
-                                    send_request_to_all_clients(
-                                        RemoteServerRequest {
-                                            request: ServerRequest::PawnTypeChange((
-                                                message.uuid,
-                                                desired_pawn_type,
-                                            )),
-                                        },
-                                        connected_clients_clone,
-                                    )
-                                    .await;
+                                    if let Some(handle) = connected_client_tcp_handles
+                                        .get(&socket_addr)
+                                    {
+                                        let (_, tcp_write) = handle.value();
+
+                                        send_request_to_client(
+                                            &mut tcp_write.lock(), 
+                                            RemoteServerRequest {
+                                                request: punchafriend::networking::ServerRequest::ServerGameStateControl(
+                                                    punchafriend::networking::ServerGameState::OngoingGame(
+                                                        OngoingGameData::new(ongoing_game_data.current_map.clone(), ongoing_game_data.round_start_date, ongoing_game_data.round_end_date, ongoing_game_data.modifiers)
+                                                    )
+                                                )
+                                            }
+                                        ).await.unwrap();
+                                    }
                                 });
-                            } else {
-                                eprintln!(
-                                    "`PawnType` change requested, but client not found at uuid."
-                                )
                             }
-                        }
-                        punchafriend::networking::ClientRequest::ClientPawnSync => {
-                            let mut pawn_updates: Vec<PawnUpdate> = vec![];
+                        };
+                    }
+                    punchafriend::networking::ClientRequest::RTTMeasurement(timestamp) => {
+                        let connected_client_tcp_handles =
+                            server_instance.connected_client_tcp_handles.clone();
 
-                            for (_entity, pawn, _controller, transform, velocity) in
-                                players_query.iter()
+                        runtime.spawn_background_task(async move |_ctx| {
+                            if let Some(handle) = connected_client_tcp_handles
+                                .get(&socket_addr)
                             {
-                                pawn_updates.push(PawnUpdate::new(
-                                    *transform,
-                                    *velocity,
-                                    pawn.clone(),
-                                    1,
-                                ));
+                                let (_, tcp_write) = handle.value();
+
+                                send_request_to_client(
+                                    &mut tcp_write.lock(), 
+                                    RemoteServerRequest {
+                                        request: punchafriend::networking::ServerRequest::RTTMeasurement { client_timestamp: timestamp, server_timestamp: Local::now().to_utc() }
+                                    }
+                                ).await.unwrap();
                             }
+                        });
+                    }
+                    // The client only answers the keep-alive so that its connection isn't considered dead, which the connection's listener has already noted
+                    punchafriend::networking::ClientRequest::KeepAlive => {}
+                    punchafriend::networking::ClientRequest::PawnTypeChange(
+                        desired_pawn_type,
+                    ) => {
+                        let now = Local::now().to_utc();
+
+                        // The pawn type can only be changed between rounds, so that the players can't swap their stats mid-fight
+                        let arena = server_instance
+                            .arena_assignments
+                            .lock()
+                            .arena_of(&message.uuid)
+                            .unwrap_or_default();
+
+                        let is_between_rounds = app_ctx
+                            .arenas
+                            .get(arena)
+                            .is_some_and(|arena_round| arena_round.waiting_for_players)
+                            || matches!(
+                                server_instance.game_states.read()[arena],
+                                ServerGameState::Intermission(_)
+                            );
+
+                        // Check if the client has changed its pawn type too recently
+                        let is_rate_limited = app_ctx
+                            .last_pawn_type_change_dates
+                            .get(&message.uuid)
+                            .is_some_and(|last_change_date| {
+                                now.signed_duration_since(last_change_date)
+                                    < TimeDelta::milliseconds(PAWN_TYPE_CHANGE_COOLDOWN_MILLIS)
+                            });
 
-                            let connected_client_tcp_handles =
+                        let rejection_reason = if !app_ctx
+                            .settings
+                            .allowed_pawn_types
+                            .contains(&desired_pawn_type)
+                        {
+                            Some(format!("{desired_pawn_type} is disabled on this server."))
+                        } else if !is_between_rounds {
+                            Some(String::from(
+                                "The character can only be changed between rounds.",
+                            ))
+                        } else if is_rate_limited {
+                            Some(String::from("You are changing characters too quickly."))
+                        } else {
+                            None
+                        };
+
+                        if let Some(rejection_reason) = rejection_reason {
+                            notify_client_request_rejected(
+                                &runtime,
+                                server_instance.connected_client_tcp_handles.clone(),
+                                socket_addr,
+                                rejection_reason,
+                            );
+                        } else if let Some((_entity, mut pawn, ..)) = players_query
+                            .iter_mut()
+                            .find(|(_e, pawn, ..)| pawn.uuid == message.uuid)
+                        {
+                            pawn.pawn_type = desired_pawn_type;
+
+                            // Store the pawn type so that the client's pawn is respawned with it
+                            app_ctx.pawn_types.insert(message.uuid, desired_pawn_type);
+
+                            // Store the date of the change so that the client can be rate-limited
+                            app_ctx.last_pawn_type_change_dates.insert(message.uuid, now);
+
+                            let connected_clients_clone =
                                 server_instance.connected_client_tcp_handles.clone();
 
                             runtime.spawn_background_task(async move |_ctx| {
-                                if let Some(handle) = connected_client_tcp_handles
-                                    .get(&socket_addr)
-                                {
-                                    let (_, tcp_write) = handle.value();
+                                send_request_to_all_clients(
+                                    RemoteServerRequest {
+                                        request: ServerRequest::PawnTypeChange((
+                                            message.uuid,
+                                            desired_pawn_type,
+                                        )),
+                                    },
+                                    connected_clients_clone,
+                                )
+                                .await;
+                            });
+                        } else {
+                            eprintln!(
+                                "`PawnType` change requested, but client not found at uuid."
+                            )
+                        }
+                    }
+                    punchafriend::networking::ClientRequest::Ping { kind, position } => {
+                        let now = Local::now().to_utc();
+
+                        // Check if the client has sent a ping too recently
+                        let is_rate_limited =
+                            app_ctx
+                                .last_ping_dates
+                                .get(&message.uuid)
+                                .is_some_and(|last_ping_date| {
+                                    now.signed_duration_since(last_ping_date)
+                                        < TimeDelta::milliseconds(PING_COOLDOWN_MILLIS)
+                                });
 
-                                    send_request_to_client(
-                                        &mut tcp_write.lock(), 
-                                        RemoteServerRequest {
-                                            request: punchafriend::networking::ServerRequest::ClientPawnSync(pawn_updates)
-                                        }
-                                    ).await.unwrap();
-                                }
+                        if !is_rate_limited {
+                            // Store the date of the ping so that the client can be rate-limited
+                            app_ctx.last_ping_dates.insert(message.uuid, now);
+
+                            let arena_assignments = server_instance.arena_assignments.lock();
+
+                            // The ping is only displayed to the players of the sender's arena
+                            let connected_clients_clone = clients_in_arena(
+                                &connected_clients_clone,
+                                &arena_assignments,
+                                arena_assignments
+                                    .arena_of(&message.uuid)
+                                    .unwrap_or_default(),
+                            );
+
+                            drop(arena_assignments);
+
+                            // Broadcast the ping to all of the clients
+                            runtime.spawn_background_task(async move |_ctx| {
+                                send_request_to_all_clients(
+                                    RemoteServerRequest {
+                                        request: ServerRequest::Ping {
+                                            uuid: message.uuid,
+                                            kind,
+                                            position,
+                                        },
+                                    },
+                                    connected_clients_clone,
+                                )
+                                .await;
                             });
                         }
                     }
+                    punchafriend::networking::ClientRequest::ClientPawnSync => {
+                        let mut pawn_updates: Vec<PawnUpdate> = vec![];
+
+                        let arena = server_instance
+                            .arena_assignments
+                            .lock()
+                            .arena_of(&message.uuid)
+                            .unwrap_or_default();
+
+                        // Only the pawns of the client's arena are synced
+                        for (_entity, pawn, _controller, transform, velocity) in players_query
+                            .iter()
+                            .filter(|(_entity, pawn, ..)| pawn.arena == arena)
+                        {
+                            pawn_updates.push(PawnUpdate::new(
+                                *transform,
+                                *velocity,
+                                pawn.clone(),
+                                1,
+                            ));
+                        }
+
+                        let connected_client_tcp_handles =
+                            server_instance.connected_client_tcp_handles.clone();
+
+                        runtime.spawn_background_task(async move |_ctx| {
+                            if let Some(handle) = connected_client_tcp_handles
+                                .get(&socket_addr)
+                            {
+                                let (_, tcp_write) = handle.value();
+
+                                send_request_to_client(
+                                    &mut tcp_write.lock(), 
+                                    RemoteServerRequest {
+                                        request: punchafriend::networking::ServerRequest::ClientPawnSync(pawn_updates)
+                                    }
+                                ).await.unwrap();
+                            }
+                        });
+                    }
                 }
             }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::world::{CommandQueue, World};
+    use punchafriend::game::pawns::DEFAULT_PAWN_SCALE;
+
+    use super::*;
+
+    /// Returns the owners of the world's pawns, an owner is listed once for every pawn it has.
+    fn pawn_owners(world: &mut World) -> Vec<uuid::Uuid> {
+        world
+            .query::<&Pawn>()
+            .iter(world)
+            .map(|pawn| pawn.uuid)
+            .collect()
+    }
+
+    #[test]
+    fn round_transition_leaves_every_client_with_one_pawn() {
+        let mut world = World::new();
+
+        let collision_groups = CollisionGroupSet::new();
+
+        let players = (0..8)
+            .map(|player_slot| {
+                ClientStatistics::new(
+                    uuid::Uuid::new_v4(),
+                    format!("Player {player_slot}"),
+                    player_slot,
+                )
+            })
+            .collect::<Vec<ClientStatistics>>();
+
+        // Half of the players still have their pawn from the last round
+        for client_stats in players.iter().step_by(2) {
+            world.spawn(Pawn::new_from_id(client_stats.uuid));
+        }
+
+        // The pass is run twice to check that it doesn't spawn duplicates
+        for _ in 0..2 {
+            let existing_pawns = pawn_owners(&mut world);
+
+            let mut command_queue = CommandQueue::default();
+
+            let mut commands = Commands::new(&mut command_queue, &world);
+
+            spawn_missing_pawns(
+                &mut commands,
+                &players,
+                &HashMap::new(),
+                DEFAULT_PAWN_SCALE,
+                &collision_groups,
+                &existing_pawns,
+                0,
+            );
+
+            command_queue.apply(&mut world);
+        }
+
+        let pawn_owners = pawn_owners(&mut world);
+
+        assert_eq!(pawn_owners.len(), players.len());
+
+        for client_stats in &players {
+            assert_eq!(
+                pawn_owners
+                    .iter()
+                    .filter(|uuid| **uuid == client_stats.uuid)
+                    .count(),
+                1
+            );
+        }
+    }
+}
@@ -1,12 +1,15 @@
 use std::{net::SocketAddr, sync::Arc, time::Duration};
 
 use bevy::{
+    app::AppExit,
     asset::Assets,
     ecs::{
         entity::Entity,
-        query::Without,
+        event::EventWriter,
+        query::{With, Without},
         system::{Commands, Query, Res, ResMut},
     },
+    input::{keyboard::KeyCode, ButtonInput},
     render::mesh::Mesh,
     sprite::ColorMaterial,
     time::Timer,
@@ -22,17 +25,29 @@ use dashmap::DashMap;
 use parking_lot::Mutex;
 use punchafriend::{
     game::{
-        collision::CollisionGroupSet,
-        map::{load_map_from_mapinstance, MapElement, MapNameDiscriminants, MapObjectUpdate},
-        pawns::Pawn,
+        collision::{CollisionGroupSet, MAX_ARENAS},
+        dummy::{spawn_training_dummy, TrainingDummy},
+        hill::ContestedHillScoring,
+        map::{
+            load_map_from_mapinstance, MapElement, MapNameDiscriminants, MapObjectUpdate,
+            DEFAULT_SPAWN_POSITION,
+        },
+        modifiers::GRAVITY_SCALE_RANGE,
+        pawns::{Pawn, PawnType, DEFAULT_JUMP_CUT_MULTIPLIER, PAWN_SCALE_RANGE},
+        physics::{apply_physics_settings, SOLVER_ITERATIONS_RANGE, SUBSTEPS_RANGE},
     },
     networking::{
-        server::{send_request_to_client, setup_remote_client_handler, ServerInstance},
-        IntermissionData, RemoteServerRequest, ServerGameState, ServerTickUpdate,
+        error::NetError,
+        metrics::{setup_metrics_endpoint, SERVER_METRICS},
+        server::{
+            clients_in_arena, send_request_to_client, setup_remote_client_handler, ServerInstance,
+        },
+        IntermissionData, RemoteServerRequest, ServerGameState, ServerRequest, ServerTickUpdate,
     },
-    server::ApplicationCtx,
-    UiLayer,
+    server::{ApplicationCtx, ArenaRound, Settings},
+    PauseWindowState, RandomEngine, UiLayer,
 };
+use rand::{rngs::SmallRng, seq::IteratorRandom};
 use strum::VariantArray;
 use tokio::{
     net::{tcp::OwnedWriteHalf, UdpSocket},
@@ -40,8 +55,6 @@ use tokio::{
 };
 use uuid::Uuid;
 
-use crate::systems::MINUTE_SECS;
-
 pub fn ui_system(
     mut contexts: EguiContexts,
     mut app_ctx: ResMut<ApplicationCtx>,
@@ -51,57 +64,254 @@ pub fn ui_system(
     collision_groups: Res<CollisionGroupSet>,
     current_map_objects: Query<(Entity, &MapElement, &mut Transform), Without<Pawn>>,
     runtime: ResMut<TokioTasksRuntime>,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut app_exit: EventWriter<AppExit>,
+    pawns: Query<Entity, With<Pawn>>,
+    mut rand: ResMut<RandomEngine>,
+    training_dummies: Query<(Entity, &TrainingDummy)>,
 ) {
+    // Reborrow the resource so that its fields can be borrowed separately
+    let app_ctx = &mut *app_ctx;
+
     let ctx = contexts.ctx_mut();
 
+    // Check for pause key
+    if keyboard_input.just_pressed(KeyCode::Escape) && matches!(app_ctx.ui_mode, UiLayer::Game(_)) {
+        app_ctx.ui_mode =
+            UiLayer::PauseWindow((PauseWindowState::Main, Box::new(app_ctx.ui_mode.clone())));
+    }
+
     match app_ctx.ui_mode.clone() {
         // If there is a game currently playing we should display the HUD.
         punchafriend::UiLayer::Game(_ongoing_game_data) => {
             egui::SidePanel::left("server_panel").show(ctx, |ui| {
                 if let Some(inst) = &app_ctx.server_instance {
-                    if let Some(round_timer) = &app_ctx.game_round_timer {
-                        ui.label(format!(
-                            "Time left: {:.2}s",
-                            round_timer.duration().as_secs_f32() - round_timer.elapsed_secs()
-                        ));
+                    let arena_assignments = inst.arena_assignments.lock();
+
+                    for (arena, arena_round) in app_ctx.arenas.iter().enumerate() {
+                        // Only label the arenas if the server hosts more than one
+                        if app_ctx.arenas.len() > 1 {
+                            ui.label(format!(
+                                "Arena {}: {} players",
+                                arena + 1,
+                                arena_assignments.player_count(arena)
+                            ));
+                        }
+
+                        if arena_round.waiting_for_players {
+                            ui.label(format!(
+                                "Waiting for players ({}/{})",
+                                arena_assignments.player_count(arena),
+                                app_ctx.settings.min_players_to_start
+                            ));
+                        }
+
+                        if let Some(round_timer) = &arena_round.game_round_timer {
+                            ui.label(format!(
+                                "Time left: {:.2}s",
+                                round_timer.duration().as_secs_f32() - round_timer.elapsed_secs()
+                            ));
+                        }
+
+                        if let Some(sudden_death) = &arena_round.sudden_death {
+                            ui.label(format!(
+                                "Sudden death between {} players: {:.2}s",
+                                sudden_death.players.len(),
+                                sudden_death.timer.remaining_secs()
+                            ));
+                        }
                     }
 
+                    drop(arena_assignments);
+
                     ui.label(format!("Port: {}", inst.tcp_listener_port));
 
+                    // Every arena is sent to the intermission
                     if ui.button("Set intermission state").clicked() {
-                        let dash_map = inst.connected_client_tcp_handles.clone();
+                        for arena in 0..app_ctx.arenas.len() {
+                            let arena_clients = clients_in_arena(
+                                &inst.connected_client_tcp_handles,
+                                &inst.arena_assignments.lock(),
+                                arena,
+                            );
 
-                        let intermission_data = create_intermission_data_all();
+                            let intermission_data = create_intermission_data(
+                                &app_ctx.settings,
+                                app_ctx.arenas[arena].last_played_map,
+                                &mut rand.inner,
+                            );
 
-                        if let Some(server_instance) = &app_ctx.server_instance {
-                            *server_instance.game_state.write() =
+                            inst.game_states.write()[arena] =
                                 ServerGameState::Intermission(intermission_data.clone());
-                        }
 
-                        app_ctx.intermission_timer = Some(Timer::new(
-                            Duration::from_secs(30),
-                            bevy::time::TimerMode::Once,
-                        ));
+                            app_ctx.arenas[arena].intermission_timer = Some(Timer::new(
+                                Duration::from_secs(30),
+                                bevy::time::TimerMode::Once,
+                            ));
 
-                        notify_valid_clients_intermission(&runtime, dash_map, intermission_data);
+                            notify_valid_clients_intermission(
+                                &runtime,
+                                arena_clients,
+                                intermission_data,
+                            );
+                        }
                     }
 
+                    ui.horizontal(|ui| {
+                        egui::ComboBox::from_id_salt("force_map")
+                            .selected_text(
+                                app_ctx
+                                    .ui_state
+                                    .force_map
+                                    .map(|map| map.to_string())
+                                    .unwrap_or_else(|| String::from("Select a map")),
+                            )
+                            .show_ui(ui, |ui| {
+                                for map in MapNameDiscriminants::VARIANTS {
+                                    ui.selectable_value(
+                                        &mut app_ctx.ui_state.force_map,
+                                        Some(*map),
+                                        map.to_string(),
+                                    );
+                                }
+                            });
+
+                        // Swap the map without resetting the round
+                        if ui
+                            .add_enabled(
+                                app_ctx.ui_state.force_map.is_some(),
+                                egui::Button::new("Force map"),
+                            )
+                            .clicked()
+                        {
+                            if let Some(map) = app_ctx.ui_state.force_map {
+                                app_ctx.pending_map_change = Some(map.into_map_instance());
+                            }
+                        }
+                    });
+
                     ui.separator();
 
-                    ui.label("Game Timers:");
+                    ui.label("Training dummies:");
 
-                    if let Some(intermission_timer) = &app_ctx.intermission_timer {
+                    for (index, (_, training_dummy)) in training_dummies.iter().enumerate() {
                         ui.label(format!(
-                            "Intermission elapsed: {:.2}s",
-                            intermission_timer.elapsed_secs()
+                            "Dummy {}: {} hit combo (best {}), {:.0} damage",
+                            index + 1,
+                            training_dummy.combo,
+                            training_dummy.best_combo,
+                            training_dummy.damage
                         ));
                     }
 
-                    if let Some(game_round_timer) = &app_ctx.game_round_timer {
-                        ui.label(format!(
-                            "Game round elapsed: {:.2}s",
-                            game_round_timer.elapsed_secs()
-                        ));
+                    ui.horizontal(|ui| {
+                        // Every arena gets its own dummy, so that all of the players can practice on one
+                        if ui.button("Spawn dummies").clicked() {
+                            let arena_count = inst.arena_assignments.lock().arena_count();
+
+                            for arena in 0..arena_count.max(1) {
+                                spawn_training_dummy(
+                                    &mut commands,
+                                    arena,
+                                    DEFAULT_SPAWN_POSITION,
+                                    &collision_groups,
+                                );
+                            }
+                        }
+
+                        if ui.button("Remove dummies").clicked() {
+                            for (entity, _) in training_dummies.iter() {
+                                commands.entity(entity).despawn();
+                            }
+                        }
+                    });
+
+                    ui.horizontal(|ui| {
+                        let mut resets_dummies = app_ctx.settings.dummy_reset_delay.is_some();
+
+                        if ui.checkbox(&mut resets_dummies, "Reset dummies").changed() {
+                            app_ctx.settings.dummy_reset_delay = resets_dummies.then_some(2.);
+                        }
+
+                        if let Some(dummy_reset_delay) = &mut app_ctx.settings.dummy_reset_delay {
+                            ui.add(
+                                egui::DragValue::new(dummy_reset_delay)
+                                    .range(0.0..=10.0)
+                                    .suffix("s"),
+                            );
+                        }
+                    });
+
+                    ui.separator();
+
+                    ui.label("Metrics:");
+
+                    let metrics_report = inst.metrics_report.read();
+
+                    ui.label(format!(
+                        "Connected players: {}",
+                        metrics_report.connected_players
+                    ));
+                    ui.label(format!(
+                        "Tick duration: {:.2}ms",
+                        metrics_report.tick_duration.as_secs_f64() * 1000.
+                    ));
+                    ui.label(format!(
+                        "Average tick duration: {:.2}ms of {:.2}ms",
+                        metrics_report.average_tick_duration.as_secs_f64() * 1000.,
+                        metrics_report.tick_budget.as_secs_f64() * 1000.
+                    ));
+
+                    if metrics_report.is_over_capacity() {
+                        ui.colored_label(
+                            Color32::RED,
+                            format!(
+                                "{} ticks have overrun the budget, the server is over capacity!",
+                                metrics_report.tick_overruns
+                            ),
+                        );
+                    }
+                    ui.label(format!(
+                        "UDP packets/s: {:.0} in, {:.0} out",
+                        metrics_report.udp_packets_received_per_sec,
+                        metrics_report.udp_packets_sent_per_sec
+                    ));
+                    ui.label(format!(
+                        "TCP messages/s: {:.0} in, {:.0} out",
+                        metrics_report.tcp_messages_received_per_sec,
+                        metrics_report.tcp_messages_sent_per_sec
+                    ));
+                    ui.label(format!(
+                        "Bytes: {} received, {} sent",
+                        metrics_report.bytes_received, metrics_report.bytes_sent
+                    ));
+                    ui.label(format!(
+                        "Pending messages: {} UDP, {} TCP",
+                        metrics_report.pending_udp_messages, metrics_report.pending_tcp_messages
+                    ));
+
+                    drop(metrics_report);
+
+                    ui.separator();
+
+                    ui.label("Game Timers:");
+
+                    for (arena, arena_round) in app_ctx.arenas.iter().enumerate() {
+                        if let Some(intermission_timer) = &arena_round.intermission_timer {
+                            ui.label(format!(
+                                "Arena {} intermission elapsed: {:.2}s",
+                                arena + 1,
+                                intermission_timer.elapsed_secs()
+                            ));
+                        }
+
+                        if let Some(game_round_timer) = &arena_round.game_round_timer {
+                            ui.label(format!(
+                                "Arena {} game round elapsed: {:.2}s",
+                                arena + 1,
+                                game_round_timer.elapsed_secs()
+                            ));
+                        }
                     }
                 }
             });
@@ -120,6 +330,382 @@ pub fn ui_system(
                 .show_separator_line(false)
                 .show(ctx, |ui| {
                     ui.with_layout(Layout::top_down(egui::Align::Min), |ui| {
+                        ui.horizontal(|ui| {
+                            ui.label("Minimum players to start:");
+                            ui.add(
+                                egui::DragValue::new(&mut app_ctx.settings.min_players_to_start)
+                                    .range(1..=64),
+                            );
+                        });
+
+                        ui.horizontal(|ui| {
+                            let mut has_stock_lives = app_ctx.settings.stock_lives.is_some();
+
+                            if ui.checkbox(&mut has_stock_lives, "Limited lives").changed() {
+                                app_ctx.settings.stock_lives = has_stock_lives.then_some(3);
+                            }
+
+                            if let Some(stock_lives) = &mut app_ctx.settings.stock_lives {
+                                ui.add(egui::DragValue::new(stock_lives).range(1..=99));
+                            }
+                        });
+
+                        ui.horizontal(|ui| {
+                            let mut delays_respawns = app_ctx.settings.respawn_delay.is_some();
+
+                            if ui.checkbox(&mut delays_respawns, "Respawn delay").changed() {
+                                app_ctx.settings.respawn_delay = delays_respawns.then_some(2.);
+                            }
+
+                            if let Some(respawn_delay) = &mut app_ctx.settings.respawn_delay {
+                                ui.add(
+                                    egui::DragValue::new(respawn_delay)
+                                        .range(0.0..=10.0)
+                                        .suffix("s"),
+                                );
+                            }
+                        });
+
+                        ui.horizontal(|ui| {
+                            let mut kicks_high_ping =
+                                app_ctx.settings.high_ping_kick_threshold.is_some();
+
+                            if ui
+                                .checkbox(&mut kicks_high_ping, "Kick players with a high ping")
+                                .changed()
+                            {
+                                app_ctx.settings.high_ping_kick_threshold =
+                                    kicks_high_ping.then_some(500);
+                            }
+
+                            if let Some(high_ping_kick_threshold) =
+                                &mut app_ctx.settings.high_ping_kick_threshold
+                            {
+                                ui.add(
+                                    egui::DragValue::new(high_ping_kick_threshold)
+                                        .range(100..=2000)
+                                        .suffix("ms"),
+                                );
+                            }
+                        });
+
+                        ui.checkbox(
+                            &mut app_ctx.settings.sudden_death,
+                            "Sudden death when the round ends in a tie",
+                        );
+
+                        ui.checkbox(
+                            &mut app_ctx.settings.super_armor,
+                            "Super armor during attack startups",
+                        );
+
+                        ui.checkbox(
+                            &mut app_ctx.settings.hit_resets_combo,
+                            "Getting hit resets the combo",
+                        );
+
+                        ui.checkbox(
+                            &mut app_ctx.settings.late_joiners_spectate,
+                            "Players joining mid-round spectate",
+                        )
+                        .on_hover_text(
+                            "The players joining mid-round wait for the next round to play.",
+                        );
+
+                        ui.checkbox(
+                            &mut app_ctx.settings.persist_match,
+                            "Resume the match after a restart",
+                        )
+                        .on_hover_text("The players get their standings back when they reconnect.");
+
+                        ui.horizontal(|ui| {
+                            let mut has_jump_cut = app_ctx.settings.jump_cut.is_some();
+
+                            if ui
+                                .checkbox(&mut has_jump_cut, "Jump cut")
+                                .on_hover_text(
+                                    "Releasing the jump key early results in a smaller jump.",
+                                )
+                                .changed()
+                            {
+                                app_ctx.settings.jump_cut =
+                                    has_jump_cut.then_some(DEFAULT_JUMP_CUT_MULTIPLIER);
+                            }
+
+                            if let Some(jump_cut) = &mut app_ctx.settings.jump_cut {
+                                ui.add(egui::Slider::new(jump_cut, 0.0..=1.0).suffix("x"));
+                            }
+                        });
+
+                        ui.horizontal(|ui| {
+                            ui.label("Safe spawn distance:");
+                            ui.add(
+                                egui::DragValue::new(&mut app_ctx.settings.safe_spawn_distance)
+                                    .range(0.0..=1000.0),
+                            );
+                        });
+
+                        ui.horizontal(|ui| {
+                            ui.label("Pawn size:");
+                            ui.add(
+                                egui::Slider::new(
+                                    &mut app_ctx.settings.pawn_scale,
+                                    PAWN_SCALE_RANGE,
+                                )
+                                .suffix("x"),
+                            )
+                            .on_hover_text("Applies to the pawns spawned from now on.");
+                        });
+
+                        ui.collapsing("Modifiers", |ui| {
+                            let modifiers = &mut app_ctx.settings.modifiers;
+
+                            ui.checkbox(&mut modifiers.big_heads, "Big heads");
+
+                            ui.horizontal(|ui| {
+                                ui.label("Gravity:");
+                                ui.add(
+                                    egui::Slider::new(
+                                        &mut modifiers.gravity_scale,
+                                        GRAVITY_SCALE_RANGE,
+                                    )
+                                    .suffix("x"),
+                                );
+                            });
+
+                            ui.checkbox(&mut modifiers.one_hit_knockouts, "One-hit knockouts");
+                        })
+                        .header_response
+                        .on_hover_text(
+                            "The modifiers are applied from the start of the next round.",
+                        );
+
+                        ui.checkbox(&mut app_ctx.settings.hill_scoring, "Hill scoring")
+                            .on_hover_text(
+                                "Award score to the players standing on the map's hill.",
+                            );
+
+                        ui.add_enabled_ui(app_ctx.settings.hill_scoring, |ui| {
+                            ui.horizontal(|ui| {
+                                ui.label("Contested hill:");
+
+                                egui::ComboBox::from_id_salt("contested_hill_scoring")
+                                    .selected_text(
+                                        app_ctx.settings.contested_hill_scoring.to_string(),
+                                    )
+                                    .show_ui(ui, |ui| {
+                                        for scoring in ContestedHillScoring::VARIANTS {
+                                            ui.selectable_value(
+                                                &mut app_ctx.settings.contested_hill_scoring,
+                                                *scoring,
+                                                scoring.to_string(),
+                                            );
+                                        }
+                                    });
+                            });
+                        });
+
+                        ui.horizontal(|ui| {
+                            ui.label("Maps per vote:");
+                            ui.add(
+                                egui::DragValue::new(&mut app_ctx.settings.maps_per_vote)
+                                    .range(1..=MapNameDiscriminants::VARIANTS.len()),
+                            );
+
+                            ui.checkbox(
+                                &mut app_ctx.settings.exclude_last_played_map,
+                                "Leave out the last played map",
+                            );
+                        });
+
+                        ui.horizontal(|ui| {
+                            let stale_hit_falloff = &mut app_ctx.settings.stale_hit_falloff;
+
+                            ui.label("Repeated hit falloff:");
+                            ui.checkbox(&mut stale_hit_falloff.enabled, "")
+                                .on_hover_text(
+                                    "Weaker knockback when the same attacker hits repeatedly.",
+                                );
+
+                            ui.add_enabled_ui(stale_hit_falloff.enabled, |ui| {
+                                ui.add(
+                                    egui::Slider::new(&mut stale_hit_falloff.falloff, 0.5..=1.0)
+                                        .text("Per hit"),
+                                );
+                                ui.add(
+                                    egui::Slider::new(
+                                        &mut stale_hit_falloff.min_multiplier,
+                                        0.0..=1.0,
+                                    )
+                                    .text("Minimum"),
+                                );
+
+                                let mut recovery_secs = stale_hit_falloff.recovery.as_secs_f32();
+
+                                if ui
+                                    .add(
+                                        egui::DragValue::new(&mut recovery_secs)
+                                            .range(0.0..=10.0)
+                                            .speed(0.1)
+                                            .suffix("s"),
+                                    )
+                                    .changed()
+                                {
+                                    stale_hit_falloff.recovery =
+                                        Duration::from_secs_f32(recovery_secs);
+                                }
+                            });
+                        });
+
+                        ui.horizontal(|ui| {
+                            let crowd_knockback_scaling =
+                                &mut app_ctx.settings.crowd_knockback_scaling;
+
+                            ui.label("Crowd knockback scaling:");
+                            ui.checkbox(&mut crowd_knockback_scaling.enabled, "")
+                                .on_hover_text("Weaker knockback when many pawns fight nearby.");
+
+                            ui.add_enabled_ui(crowd_knockback_scaling.enabled, |ui| {
+                                ui.add(
+                                    egui::DragValue::new(&mut crowd_knockback_scaling.radius)
+                                        .range(50.0..=1000.0)
+                                        .suffix("px"),
+                                );
+                                ui.add(
+                                    egui::Slider::new(
+                                        &mut crowd_knockback_scaling.reduction_per_pawn,
+                                        0.0..=0.5,
+                                    )
+                                    .text("Per pawn"),
+                                );
+                                ui.add(
+                                    egui::Slider::new(
+                                        &mut crowd_knockback_scaling.min_multiplier,
+                                        0.0..=1.0,
+                                    )
+                                    .text("Minimum"),
+                                );
+                            });
+                        });
+
+                        ui.horizontal(|ui| {
+                            let mut has_password = app_ctx.password.is_some();
+
+                            if ui.checkbox(&mut has_password, "Password").changed() {
+                                app_ctx.password = has_password.then(String::new);
+                            }
+
+                            if let Some(password) = &mut app_ctx.password {
+                                ui.add(egui::TextEdit::singleline(password).password(true));
+                            }
+                        });
+
+                        let connection_limits = &mut app_ctx.settings.connection_limits;
+
+                        ui.horizontal(|ui| {
+                            ui.label("Pending connections:");
+                            ui.add(
+                                egui::DragValue::new(&mut connection_limits.max_pending_handshakes)
+                                    .range(1..=1024),
+                            )
+                            .on_hover_text("The connections exchanging the metadata at once.");
+
+                            ui.label("Connections per IP per minute:");
+                            ui.add(
+                                egui::DragValue::new(&mut connection_limits.connections_per_minute)
+                                    .range(1..=600),
+                            );
+                        });
+
+                        ui.horizontal(|ui| {
+                            let mut bans_flooders = connection_limits.flood_ban_secs.is_some();
+
+                            if ui
+                                .checkbox(&mut bans_flooders, "Ban flooding addresses")
+                                .changed()
+                            {
+                                connection_limits.flood_ban_secs = bans_flooders.then_some(60);
+                            }
+
+                            if let Some(flood_ban_secs) = &mut connection_limits.flood_ban_secs {
+                                ui.add(
+                                    egui::DragValue::new(flood_ban_secs)
+                                        .range(1..=86400)
+                                        .suffix("s"),
+                                );
+                            }
+                        });
+
+                        // The running server admits the connections within the changed limits right away
+                        if let Some(server_instance) = &app_ctx.server_instance {
+                            *server_instance.connection_limits.write() =
+                                app_ctx.settings.connection_limits;
+                        }
+
+                        ui.horizontal(|ui| {
+                            ui.label("Arenas:");
+                            ui.add(
+                                egui::DragValue::new(&mut app_ctx.settings.arena_count)
+                                    .range(1..=MAX_ARENAS),
+                            );
+                        });
+
+                        ui.horizontal(|ui| {
+                            ui.label("Physics solver iterations:");
+                            ui.add(
+                                egui::DragValue::new(
+                                    &mut app_ctx.settings.physics.solver_iterations,
+                                )
+                                .range(SOLVER_ITERATIONS_RANGE),
+                            )
+                            .on_hover_text("Reduces jittering under stacked knockbacks.");
+
+                            ui.label("Substeps:");
+                            ui.add(
+                                egui::DragValue::new(&mut app_ctx.settings.physics.substeps)
+                                    .range(SUBSTEPS_RANGE),
+                            )
+                            .on_hover_text("Prevents fast pawns from tunneling through the map.");
+                        });
+
+                        ui.horizontal(|ui| {
+                            let mut has_metrics_endpoint =
+                                app_ctx.settings.metrics_endpoint_port.is_some();
+
+                            if ui
+                                .checkbox(&mut has_metrics_endpoint, "Metrics endpoint")
+                                .changed()
+                            {
+                                app_ctx.settings.metrics_endpoint_port =
+                                    has_metrics_endpoint.then_some(9100);
+                            }
+
+                            if let Some(port) = &mut app_ctx.settings.metrics_endpoint_port {
+                                ui.label("Port:");
+                                ui.add(egui::DragValue::new(port).range(1..=u16::MAX));
+                            }
+                        });
+
+                        ui.horizontal(|ui| {
+                            ui.label("Allowed characters:");
+
+                            for pawn_type in PawnType::VARIANTS {
+                                let mut is_allowed =
+                                    app_ctx.settings.allowed_pawn_types.contains(pawn_type);
+
+                                if ui.checkbox(&mut is_allowed, pawn_type.to_string()).changed() {
+                                    if is_allowed {
+                                        app_ctx.settings.allowed_pawn_types.push(*pawn_type);
+                                    } else {
+                                        app_ctx
+                                            .settings
+                                            .allowed_pawn_types
+                                            .retain(|allowed| allowed != pawn_type);
+                                    }
+                                }
+                            }
+                        });
+
                         ui.add(egui::Button::new(RichText::from("Mods").size(25.)).frame(false));
                         ui.add(egui::Button::new(RichText::from("Options").size(25.)).frame(false));
                         ui.add(
@@ -134,15 +720,27 @@ pub fn ui_system(
                             .clicked()
                         {
                             // Create a new pair of channels
-                            let (sender, receiver) = channel::<anyhow::Result<ServerInstance>>(255);
+                            let (sender, receiver) =
+                                channel::<Result<ServerInstance, NetError>>(255);
 
                             // Set the receiver so that it will receive the new instnace from the async task
                             app_ctx.server_instance_receiver = receiver;
 
+                            let physics_settings = app_ctx.settings.physics;
+                            let arena_count = app_ctx.settings.arena_count;
+                            let modifiers = app_ctx.settings.modifiers;
+                            let seed = rand.seed;
+
                             // Spawn a new async task
-                            runtime.spawn_background_task(|_ctx| async move {
+                            runtime.spawn_background_task(move |_ctx| async move {
                                 // Create a new ServerInstance
-                                let connection_result = ServerInstance::create_server().await;
+                                let connection_result = ServerInstance::create_server(
+                                    physics_settings,
+                                    arena_count,
+                                    modifiers,
+                                    seed,
+                                )
+                                .await;
 
                                 // Send the new instance through the channel
                                 sender.send(connection_result).await.unwrap();
@@ -153,27 +751,92 @@ pub fn ui_system(
                     });
                 });
         }
-        punchafriend::UiLayer::PauseWindow(_) => {
+        punchafriend::UiLayer::PauseWindow((inner_state, state_before)) => {
             // Paint the pause menu's backgound
             egui::Area::new("pause_window_background".into()).show(ctx, |ui| {
                 ui.painter()
                     .rect_filled(ctx.screen_rect(), 0., Color32::from_black_alpha(200));
             });
 
-            // If the player pauses their game whilst in a game we should display the pause menu.
-            egui::Window::new("pause_window")
-                .title_bar(false)
-                .resizable(false)
-                .collapsible(false)
-                .anchor(Align2::CENTER_CENTER, egui::vec2(0., 0.))
-                .fixed_size(ctx.screen_rect().size() / 3.)
-                .show(ctx, |ui| {
-                    ui.with_layout(Layout::top_down(egui::Align::Center), |ui| {
-                        ui.add(egui::Button::new("Resume").frame(false));
-                        ui.add(egui::Button::new("Options").frame(false));
-                        ui.add(egui::Button::new("Quit").frame(false));
-                    });
-                });
+            match inner_state {
+                PauseWindowState::QuitConfirmation => {
+                    egui::Window::new("quit_confirmation")
+                        .title_bar(false)
+                        .resizable(false)
+                        .collapsible(false)
+                        .anchor(Align2::CENTER_CENTER, egui::vec2(0., 0.))
+                        .show(ctx, |ui| {
+                            ui.with_layout(Layout::top_down(egui::Align::Center), |ui| {
+                                ui.label(
+                                    RichText::from("Are you sure you want to quit?").size(20.),
+                                );
+
+                                ui.horizontal(|ui| {
+                                    if ui.button("Quit").clicked() {
+                                        app_exit.send(AppExit::Success);
+                                    }
+
+                                    if ui.button("Cancel").clicked() {
+                                        app_ctx.ui_mode = *state_before.clone();
+                                    }
+                                });
+                            });
+                        });
+                }
+                _ => {
+                    // If the player pauses their game whilst in a game we should display the pause menu.
+                    egui::Window::new("pause_window")
+                        .title_bar(false)
+                        .resizable(false)
+                        .collapsible(false)
+                        .anchor(Align2::CENTER_CENTER, egui::vec2(0., 0.))
+                        .fixed_size(ctx.screen_rect().size() / 3.)
+                        .show(ctx, |ui| {
+                            ui.with_layout(Layout::top_down(egui::Align::Center), |ui| {
+                                if ui.add(egui::Button::new("Resume").frame(false)).clicked() {
+                                    app_ctx.ui_mode = *state_before.clone();
+                                }
+
+                                ui.add(egui::Button::new("Options").frame(false));
+
+                                if app_ctx.ui_state.confirm_stop_server {
+                                    ui.label("Are you sure you want to stop the server?");
+
+                                    ui.horizontal(|ui| {
+                                        if ui.button("Stop").clicked() {
+                                            stop_server(
+                                                &mut app_ctx,
+                                                &mut commands,
+                                                &runtime,
+                                                &pawns,
+                                                &current_map_objects,
+                                            );
+                                        }
+
+                                        if ui.button("Cancel").clicked() {
+                                            app_ctx.ui_state.confirm_stop_server = false;
+                                        }
+                                    });
+                                } else if ui
+                                    .add(egui::Button::new("Stop server").frame(false))
+                                    .clicked()
+                                {
+                                    app_ctx.ui_state.confirm_stop_server = true;
+                                }
+
+                                if ui
+                                    .add(egui::Button::new("Quit game").frame(false))
+                                    .clicked()
+                                {
+                                    app_ctx.ui_mode = UiLayer::PauseWindow((
+                                        PauseWindowState::QuitConfirmation,
+                                        Box::new(app_ctx.ui_mode.clone()),
+                                    ));
+                                }
+                            });
+                        });
+                }
+            }
         }
         punchafriend::UiLayer::GameMenu => {}
         punchafriend::UiLayer::Intermission(_) => {
@@ -188,35 +851,71 @@ pub fn ui_system(
     if let Ok(server_instance) = app_ctx.server_instance_receiver.try_recv() {
         match server_instance {
             Ok(mut server_instance) => {
-                // Initalize game
-                let game_state = server_instance.game_state.read();
-
-                match game_state.clone() {
-                    punchafriend::networking::ServerGameState::Pause => {
-                        unimplemented!("The server should never reach this point.");
-                    }
-                    punchafriend::networking::ServerGameState::Intermission(_) => {
-                        unimplemented!("The server should never reach this point.");
+                // Resume the match interrupted by the last shutdown, every arena on the map of its own round
+                if let Some(restored_match) = &app_ctx.restored_match {
+                    for (game_state, arena_snapshot) in server_instance
+                        .game_states
+                        .write()
+                        .iter_mut()
+                        .zip(&restored_match.arenas)
+                    {
+                        if let (ServerGameState::OngoingGame(game_data), Some(arena_snapshot)) =
+                            (game_state, arena_snapshot)
+                        {
+                            game_data.current_map = arena_snapshot.current_map.clone();
+                            game_data.modifiers = arena_snapshot.modifiers;
+                        }
                     }
-                    punchafriend::networking::ServerGameState::OngoingGame(game_data) => {
-                        load_map_from_mapinstance(
-                            game_data.current_map.clone(),
-                            &mut commands,
-                            collision_groups.clone(),
-                            current_map_objects,
-                        );
+                }
+
+                // Initalize every arena's game
+                let game_states = server_instance.game_states.read();
 
-                        app_ctx.ui_mode = UiLayer::Game(game_data.clone());
+                for (arena, game_state) in game_states.iter().enumerate() {
+                    match game_state.clone() {
+                        punchafriend::networking::ServerGameState::Pause => {
+                            unimplemented!("The server should never reach this point.");
+                        }
+                        punchafriend::networking::ServerGameState::Intermission(_) => {
+                            unimplemented!("The server should never reach this point.");
+                        }
+                        punchafriend::networking::ServerGameState::OngoingGame(game_data) => {
+                            load_map_from_mapinstance(
+                                game_data.current_map.clone(),
+                                &mut commands,
+                                collision_groups.clone(),
+                                arena..arena + 1,
+                                &current_map_objects,
+                            );
+
+                            // The server's own view follows the first arena
+                            if arena == 0 {
+                                app_ctx.ui_mode = UiLayer::Game(game_data.clone());
+                            }
+                        }
                     }
                 }
 
-                // Reset the round timer's state
-                app_ctx.game_round_timer = Some(Timer::new(
-                    Duration::from_secs(MINUTE_SECS * 8),
-                    bevy::time::TimerMode::Once,
-                ));
+                // Configure the physics world with the operator's settings
+                apply_physics_settings(&mut commands, app_ctx.settings.physics);
+
+                // Freeze every arena's round timer until enough players have joined
+                app_ctx.arenas = (0..game_states.len())
+                    .map(|_| ArenaRound {
+                        waiting_for_players: true,
+                        ..Default::default()
+                    })
+                    .collect();
+
+                drop(game_states);
 
-                drop(game_state);
+                // Protect the server with the operator's password
+                server_instance.password = app_ctx
+                    .password
+                    .clone()
+                    .filter(|password| !password.is_empty());
+
+                *server_instance.connection_limits.write() = app_ctx.settings.connection_limits;
 
                 // Initalize server threads
                 setup_remote_client_handler(
@@ -226,6 +925,16 @@ pub fn ui_system(
                     collision_groups.clone(),
                 );
 
+                // Serve the server's health over HTTP if it has been enabled
+                if let Some(port) = app_ctx.settings.metrics_endpoint_port {
+                    setup_metrics_endpoint(
+                        &runtime,
+                        port,
+                        server_instance.metrics_report.clone(),
+                        app_ctx.cancellation_token.clone(),
+                    );
+                }
+
                 app_ctx.server_instance = Some(server_instance);
             }
             Err(err) => {}
@@ -233,10 +942,86 @@ pub fn ui_system(
     }
 }
 
-pub fn create_intermission_data_all() -> IntermissionData {
+/// Stops the hosted match without closing the application.
+/// The clients are notified about the shutdown, all of the server's tasks are cancelled and the game's entities are despawned.
+fn stop_server(
+    app_ctx: &mut ApplicationCtx,
+    commands: &mut Commands,
+    runtime: &TokioTasksRuntime,
+    pawns: &Query<Entity, With<Pawn>>,
+    current_map_objects: &Query<(Entity, &MapElement, &mut Transform), Without<Pawn>>,
+) {
+    // The tasks are cancelled with the old token, the next server gets a new one
+    let cancellation_token = app_ctx.cancellation_token.clone();
+
+    if let Some(server_instance) = &app_ctx.server_instance {
+        let connected_clients = server_instance.connected_client_tcp_handles.clone();
+
+        runtime.spawn_background_task(move |_ctx| async move {
+            // Notify the clients before closing the connections, the clients which have already disconnected are ignored
+            for connected_client in connected_clients.iter() {
+                let (_, write_half) = connected_client.value();
+
+                let _ = send_request_to_client(
+                    &mut write_half.lock(),
+                    RemoteServerRequest {
+                        request: ServerRequest::ServerShutdown,
+                    },
+                )
+                .await;
+            }
+
+            cancellation_token.cancel();
+        });
+    } else {
+        cancellation_token.cancel();
+    }
+
+    // Despawn the game's entities
+    for entity in pawns.iter() {
+        commands.entity(entity).despawn();
+    }
+
+    for (entity, _, _) in current_map_objects.iter() {
+        commands.entity(entity).despawn();
+    }
+
+    // Reset the server's state, the settings are kept for the next match
+    *app_ctx = ApplicationCtx {
+        settings: app_ctx.settings.clone(),
+        ..Default::default()
+    };
+}
+
+/// Creates the data of a new intermission, with [`Settings::maps_per_vote`] randomly chosen maps to vote for.
+/// The last played map is left out if [`Settings::exclude_last_played_map`] is enabled, unless it is the only map.
+pub fn create_intermission_data(
+    settings: &Settings,
+    last_played_map: Option<MapNameDiscriminants>,
+    rand: &mut SmallRng,
+) -> IntermissionData {
+    let mut candidate_maps = MapNameDiscriminants::VARIANTS
+        .iter()
+        .copied()
+        .filter(|map| !(settings.exclude_last_played_map && Some(*map) == last_played_map))
+        .collect::<Vec<MapNameDiscriminants>>();
+
+    // Keep the last played map if there would be nothing to vote for without it
+    if candidate_maps.is_empty() {
+        candidate_maps = MapNameDiscriminants::VARIANTS.to_vec();
+    }
+
+    // Every map is offered if there are no more maps than the amount offered
+    let selectable_maps = if settings.maps_per_vote >= candidate_maps.len() {
+        candidate_maps
+    } else {
+        candidate_maps
+            .into_iter()
+            .choose_multiple(rand, settings.maps_per_vote.max(1))
+    };
+
     let intermission_data = IntermissionData::new(
-        MapNameDiscriminants::VARIANTS
-            .to_vec()
+        selectable_maps
             .iter()
             .map(|map| (*map, 0))
             .collect::<Vec<(MapNameDiscriminants, usize)>>(),
@@ -276,6 +1061,8 @@ pub fn notify_valid_clients_map_change(
                 .send_to(&message_length_bytes, *socket_addr)
                 .await
                 .unwrap();
+
+            SERVER_METRICS.record_udp_sent(message_length_bytes.len());
         }
     });
 }
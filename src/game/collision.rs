@@ -5,10 +5,11 @@ use bevy::{
         component::Component,
         entity::Entity,
         event::EventReader,
-        query::{Changed, With},
-        system::{Commands, Query, Res, Resource},
+        query::{Changed, With, Without},
+        system::{Commands, Query, Res, ResMut, Resource},
     },
-    math::vec2,
+    math::{vec2, Vec2},
+    time::{Time, Timer},
     transform::components::Transform,
 };
 use bevy_rapier2d::prelude::{CollisionGroups, Group, Velocity};
@@ -16,15 +17,21 @@ use bevy_tokio_tasks::TokioTasksRuntime;
 use uuid::Uuid;
 
 use crate::{
-    networking::{server::send_request_to_all_clients, ClientStatistics, RemoteServerRequest},
+    networking::{
+        server::{
+            clients_in_arena, send_request_to_all_clients, send_request_to_client, ServerInstance,
+        },
+        ClientStatistics, RemoteServerRequest, ServerGameState, ServerRequest,
+    },
     server::ApplicationCtx,
-    Direction,
+    Direction, RandomEngine,
 };
 
 use super::{
-    combat::{AttackObject, AttackType, Combo},
-    map::MapElement,
-    pawns::{spawn_pawn, Pawn},
+    combat::{AttackObject, AttackType, Combo, RecentHits},
+    dummy::TrainingDummy,
+    map::{MapElement, DEFAULT_SPAWN_POSITION},
+    pawns::{spawn_pawn, Pawn, PawnType, PAWN_HALF_EXTENTS},
 };
 
 #[derive(Component, Debug, Clone, Default)]
@@ -40,6 +47,20 @@ impl LastInteractedPawn {
     }
 }
 
+/// A knocked out pawn waiting to be respawned, this is spawned as a separate entity as the pawn's entity is despawned.
+/// The pawn is spawned by [`respawn_pending_pawns`] when the timer finishes, see [`crate::server::Settings::respawn_delay`].
+#[derive(Component, Debug, Clone)]
+pub struct PendingRespawn {
+    pub uuid: Uuid,
+    pub player_slot: usize,
+    pub tint: Option<[u8; 3]>,
+    pub pawn_type: PawnType,
+    pub arena: usize,
+    pub scale: f32,
+    /// The time left until the pawn is respawned.
+    pub timer: Timer,
+}
+
 #[repr(u32)]
 pub enum CollisionGroup {
     MapObject = 0b0001,
@@ -47,6 +68,25 @@ pub enum CollisionGroup {
     AttackObj = 0b1000,
 }
 
+/// The maximum amount of arenas a server can host, every arena takes up 4 bits of the 32 collision group bits.
+pub const MAX_ARENAS: usize = 8;
+
+/// The pawns falling below this height are knocked out.
+pub const OUT_OF_BOUNDS_HEIGHT: f32 = -400.;
+
+/// The score awarded for knocking out a pawn.
+pub const KILL_SCORE: u32 = 100;
+
+/// The score awarded for an assist, see [`crate::game::pawns::ASSIST_WINDOW`].
+pub const ASSIST_SCORE: u32 = 50;
+
+/// The speed the overlapping pawns are pushed apart with in pixels per second.
+/// This is kept low, so that the pawns crowding a platform are separated gently instead of being shoved off.
+pub const PAWN_SEPARATION_SPEED: f32 = 120.;
+
+/// The overlap of the pawns which is ignored, so that the pawns touching each other aren't pushed apart.
+pub const PAWN_OVERLAP_TOLERANCE: f32 = 1.;
+
 #[derive(Resource, Clone)]
 pub struct CollisionGroupSet {
     /// Collides with all
@@ -84,6 +124,25 @@ impl CollisionGroupSet {
             ),
         }
     }
+
+    /// Returns the collision groups of the arena, the objects of different arenas never collide with each other.
+    /// The arena's groups are the default groups shifted by 4 bits per arena, so that every arena has its own set of bits.
+    pub fn for_arena(&self, arena: usize) -> Self {
+        let shift = 4 * (arena % MAX_ARENAS) as u32;
+
+        let shift_groups = |collision_groups: CollisionGroups| {
+            CollisionGroups::new(
+                Group::from_bits_truncate(collision_groups.memberships.bits() << shift),
+                Group::from_bits_truncate(collision_groups.filters.bits() << shift),
+            )
+        };
+
+        Self {
+            map_object: shift_groups(self.map_object),
+            pawn: shift_groups(self.pawn),
+            attack_obj: shift_groups(self.attack_obj),
+        }
+    }
 }
 
 pub fn check_for_collision_with_map_and_player(
@@ -137,6 +196,7 @@ pub fn check_for_collision_with_map_and_player(
     None
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn check_for_collision_with_attack_object(
     mut commands: Commands,
     mut collision_events: EventReader<bevy_rapier2d::prelude::CollisionEvent>,
@@ -148,7 +208,35 @@ pub fn check_for_collision_with_attack_object(
         &mut LastInteractedPawn,
     )>,
     attack_object_query: Query<(Entity, &AttackObject)>,
+    mut recent_hits_query: Query<&mut RecentHits>,
+    mut training_dummies: Query<&mut TrainingDummy>,
+    app_ctx: Res<ApplicationCtx>,
+    time: Res<Time>,
+    runtime: Res<TokioTasksRuntime>,
 ) {
+    // The hits registered in this update, with the arena they have happened in
+    let mut registered_hits: Vec<(usize, ServerRequest)> = Vec::new();
+
+    // Any hit knocks the attacked pawn out if its arena's round has the one-hit knockouts modifier
+    let one_hit_knockouts: Vec<bool> = app_ctx
+        .server_instance
+        .as_ref()
+        .map(|server_instance| {
+            server_instance
+                .game_states
+                .read()
+                .iter()
+                .map(|game_state| {
+                    matches!(
+                        game_state,
+                        ServerGameState::OngoingGame(ongoing_game_data)
+                            if ongoing_game_data.modifiers.one_hit_knockouts
+                    )
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
     for collision in collision_events.read() {
         match collision {
             bevy_rapier2d::prelude::CollisionEvent::Started(
@@ -198,6 +286,15 @@ pub fn check_for_collision_with_attack_object(
                         1.0
                     };
 
+                    // Directional attacks also push the enemy up or down
+                    let push_vertical = match attack_object.attack_type {
+                        AttackType::Directional(Direction::Up)
+                        | AttackType::Projectile(Direction::Up) => 1.,
+                        AttackType::Directional(Direction::Down)
+                        | AttackType::Projectile(Direction::Down) => -1.,
+                        _ => 0.,
+                    };
+
                     let attacker_result = character_query
                         .iter_mut()
                         .find(|(ent, _, _, _, _)| *ent == attack_object.attack_by);
@@ -215,30 +312,111 @@ pub fn check_for_collision_with_attack_object(
                         
                         let pawn_attribute = local_player.pawn_type.into_pawn_attribute();
 
-                        attacker_strength = pawn_attribute.attack_knockback;
+                        attacker_strength = pawn_attribute.attack_knockback
+                            * attack_object.attack_type.attack_profile().knockback;
 
                         attacker_uuid = Some(local_player.uuid)
                     }
 
-                    colliding_entity_commands.insert(Velocity {
-                        linvel: vec2(
-                            foreign_char_velocity.linvel.x + 400. * push_left * attacker_strength,
-                            foreign_char_velocity.linvel.y
-                                + if attack_object.attack_type
-                                    == AttackType::Directional(Direction::Up)
-                                {
-                                    500.
-                                } else if attack_object.attack_type
-                                    == AttackType::Directional(Direction::Down)
-                                {
-                                    -500.
-                                } else {
-                                    0.
-                                },
-                        ),
-                        // Angles are disabled
-                        angvel: 0.,
-                    });
+                    // The training dummies keep track of the hits they have taken
+                    if let Ok(mut training_dummy) = training_dummies.get_mut(*attacked_entity) {
+                        training_dummy.register_hit(attack_object.attack_strength);
+                    }
+
+                    // Getting hit cancels the attacked pawn's charge, and its combo if the server punishes getting hit
+                    if let Ok((_, mut attacked_pawn, _, _, _)) =
+                        character_query.get_mut(*attacked_entity)
+                    {
+                        attacked_pawn.take_hit(app_ctx.settings.hit_resets_combo);
+
+                        // The attacker is awarded an assist if the pawn is knocked out by someone else soon
+                        if let Some(attacker_uuid) = attacker_uuid {
+                            attacked_pawn.register_attacker(attacker_uuid, time.elapsed());
+                        }
+                    }
+
+                    // Repeated hits by the same attacker knock the pawn back less and less, if the server has enabled it
+                    if let (true, Some(attacker_uuid), Ok(mut recent_hits)) = (
+                        app_ctx.settings.stale_hit_falloff.enabled,
+                        attacker_uuid,
+                        recent_hits_query.get_mut(*attacked_entity),
+                    ) {
+                        attacker_strength *= recent_hits.register_hit(
+                            attacker_uuid,
+                            time.elapsed(),
+                            &app_ctx.settings.stale_hit_falloff,
+                        );
+                    }
+
+                    // Crowded fights knock the pawns back less, if the server has enabled it
+                    let crowd_knockback_scaling = &app_ctx.settings.crowd_knockback_scaling;
+
+                    if crowd_knockback_scaling.enabled {
+                        let nearby_pawns = character_query
+                            .iter()
+                            .filter(|(entity, pawn, transform, _, _)| {
+                                *entity != *attacked_entity
+                                    && *entity != attack_object.attack_by
+                                    && pawn.arena == attacked_pawn.arena
+                                    && transform.translation.distance(character_position)
+                                        <= crowd_knockback_scaling.radius
+                            })
+                            .count();
+
+                        attacker_strength *= crowd_knockback_scaling.multiplier(nearby_pawns);
+                    }
+
+                    // Freshly spawned pawns can't be knocked back or out
+                    let spawn_protected = attacked_pawn.spawn_protection.is_some();
+
+                    // Bigger pawns are heavier, so they are launched less far
+                    let pawn_knockback = attacked_pawn.knockback(vec2(
+                        400. * push_left * attacker_strength,
+                        500. * push_vertical,
+                    ));
+
+                    // The velocity added to the attacked pawn, if it is knocked back
+                    let mut knockback = Vec2::ZERO;
+
+                    let one_hit_knockout = one_hit_knockouts
+                        .get(attacked_pawn.arena)
+                        .copied()
+                        .unwrap_or_default();
+
+                    if one_hit_knockout && !spawn_protected {
+                        // Move the pawn below the map's bounds, so that it is knocked out like a fallen pawn and the attacker gets the kill
+                        colliding_entity_commands.insert(Transform {
+                            translation: foreign_char_transform
+                                .translation
+                                .with_y(OUT_OF_BOUNDS_HEIGHT - 1.),
+                            ..*foreign_char_transform
+                        });
+                    }
+                    // Pawns with super armor take the hit without being knocked back
+                    else if let Some(pawn_knockback) = pawn_knockback {
+                        knockback = pawn_knockback;
+
+                        colliding_entity_commands.insert(Velocity {
+                            linvel: foreign_char_velocity.linvel + knockback,
+                            // Angles are disabled
+                            angvel: 0.,
+                        });
+                    }
+
+                    // Let the players of the arena know about the hit, so that they can display it
+                    if let Some(attacker_uuid) = attacker_uuid {
+                        registered_hits.push((
+                            attacked_pawn.arena,
+                            ServerRequest::HitRegistered {
+                                attacker: attacker_uuid,
+                                victim: attacked_pawn.uuid,
+                                position: character_position.truncate(),
+                                direction: vec2(push_left, push_vertical).normalize_or_zero(),
+                                knockback,
+                                on_training_dummy: training_dummies.contains(*attacked_entity),
+                            },
+                        ));
+                    }
                 };
 
                 let character_query_result = character_query
@@ -262,28 +440,127 @@ pub fn check_for_collision_with_attack_object(
         };
     }
 
-    //Remove all the attacks objects after checking for collision
-    for (ent, _) in attack_object_query.iter() {
-        commands.entity(ent).despawn();
+    let Some(server_instance) = &app_ctx.server_instance else {
+        return;
+    };
+
+    for (arena, hit_registered) in registered_hits {
+        // The hits are only displayed to the players of the arena the hit has happened in
+        let connected_clients = clients_in_arena(
+            &server_instance.connected_client_tcp_handles,
+            &server_instance.arena_assignments.lock(),
+            arena,
+        );
+
+        runtime.spawn_background_task(async move |_ctx| {
+            send_request_to_all_clients(
+                RemoteServerRequest {
+                    request: hit_registered,
+                },
+                connected_clients,
+            )
+            .await;
+        });
     }
 }
 
+/// Nudges the pawns overlapping each other apart along the axis they overlap the least on, so that the pawns walking into each other don't get stuck.
+/// Both pawns are moved by at most [`PAWN_SEPARATION_SPEED`] per second, pawns of different arenas never collide so they are ignored.
+pub fn separate_overlapping_pawns(time: Res<Time>, mut pawns: Query<(&Pawn, &mut Transform)>) {
+    let max_push = PAWN_SEPARATION_SPEED * time.delta_secs();
+
+    let mut combinations = pawns.iter_combinations_mut();
+
+    while let Some([(pawn, mut transform), (pawn1, mut transform1)]) = combinations.fetch_next() {
+        if pawn.arena != pawn1.arena {
+            continue;
+        }
+
+        // The pawns' colliders are scaled with their transforms
+        let half_extents = PAWN_HALF_EXTENTS * transform.scale.truncate();
+        let half_extents1 = PAWN_HALF_EXTENTS * transform1.scale.truncate();
+
+        let delta = transform.translation.truncate() - transform1.translation.truncate();
+        let overlap = (half_extents + half_extents1) - delta.abs() - PAWN_OVERLAP_TOLERANCE;
+
+        if overlap.x <= 0. || overlap.y <= 0. {
+            continue;
+        }
+
+        // Push the pawns apart the shortest way, pawns exactly on top of each other are pushed horizontally
+        let push = if overlap.x < overlap.y || delta == Vec2::ZERO {
+            vec2(
+                (overlap.x / 2.).min(max_push) * if delta.x < 0. { -1. } else { 1. },
+                0.,
+            )
+        } else {
+            vec2(
+                0.,
+                (overlap.y / 2.).min(max_push) * if delta.y < 0. { -1. } else { 1. },
+            )
+        };
+
+        transform.translation += push.extend(0.);
+        transform1.translation -= push.extend(0.);
+    }
+}
+
+/// Returns the position a pawn of the arena is respawned at, the pawn is kept away from the living pawns of its arena if possible.
+fn respawn_position(
+    server_instance: &ServerInstance,
+    all_pawns: &Query<(&Pawn, &Transform)>,
+    uuid: Uuid,
+    arena: usize,
+    safe_spawn_distance: f32,
+    rand: &mut RandomEngine,
+) -> Vec2 {
+    let enemies = all_pawns
+        .iter()
+        .filter(|(enemy, _)| enemy.uuid != uuid && enemy.arena == arena)
+        .map(|(_, transform)| transform.translation.truncate())
+        .collect::<Vec<Vec2>>();
+
+    match server_instance.game_states.read().get(arena) {
+        Some(ServerGameState::OngoingGame(ongoing_game_data)) => ongoing_game_data
+            .current_map
+            .safe_spawn_point(&enemies, safe_spawn_distance, &mut rand.inner),
+        _ => DEFAULT_SPAWN_POSITION,
+    }
+}
+
+/// Knocks out the pawns which have fallen off the map, and respawns them at the map's safest spawn point.
+/// If the server respawns the pawns with a delay, a [`PendingRespawn`] is spawned instead and the player is notified of the delay.
 pub fn check_players_out_of_bounds(
     runtime: Res<TokioTasksRuntime>,
-    players: Query<(Entity, &Pawn, &Transform, &LastInteractedPawn), Changed<Transform>>,
-    app_ctx: Res<ApplicationCtx>,
+    players: Query<
+        (Entity, &Pawn, &Transform, &LastInteractedPawn),
+        (Changed<Transform>, Without<TrainingDummy>),
+    >,
+    all_pawns: Query<(&Pawn, &Transform)>,
+    mut app_ctx: ResMut<ApplicationCtx>,
     mut commands: Commands,
     collision_groups: Res<CollisionGroupSet>,
+    mut rand: ResMut<RandomEngine>,
+    time: Res<Time>,
 ) {
+    // Reborrow the resource so that its fields can be borrowed separately
+    let app_ctx = &mut *app_ctx;
+
     // Check if there is a server running currently
     if let Some(server_instance) = &app_ctx.server_instance {
         // Create a list of all the modified client statistics.
         let mut modified_client_stats: Vec<ClientStatistics> = Vec::new();
 
+        // The knockouts of this update and the arenas they have happened in, these are displayed in the kill feed of the arena's players
+        let mut knockouts: Vec<(usize, ServerRequest)> = Vec::new();
+
+        // The players whose pawns are respawned with a delay
+        let mut delayed_respawns: Vec<Uuid> = Vec::new();
+
         // Iter over the list of players
         for (e, pawn, position, last_interacted_pawn) in players.iter() {
             // Check if the player contained in the query is out of bounds
-            if position.translation.y < -400. {
+            if position.translation.y < OUT_OF_BOUNDS_HEIGHT {
                 let mut client_stats_list_handle = server_instance.connected_clients_stats.write();
 
                 let client_stats_list = client_stats_list_handle
@@ -300,6 +577,15 @@ pub fn check_players_out_of_bounds(
                         // Modify the entry
                         client.deaths += 1;
 
+                        // Take a life from the player if the lives are limited
+                        let is_eliminated = if let Some(lives) = &mut client.lives {
+                            *lives = lives.saturating_sub(1);
+
+                            *lives == 0
+                        } else {
+                            false
+                        };
+
                         // Re-insert the entry
                         client_stats_list_handle.insert(client.clone());
 
@@ -307,14 +593,16 @@ pub fn check_players_out_of_bounds(
                         modified_client_stats.push(client);
 
                         // Check who interacted last with the pawn
-                        if let Some(last_int_player_uuid) = last_interacted_pawn.get_inner() {
+                        let killer = *last_interacted_pawn.get_inner();
+
+                        if let Some(last_int_player_uuid) = killer {
                             for mut client_stats in client_stats_list.clone() {
-                                if client_stats.uuid == *last_int_player_uuid {
+                                if client_stats.uuid == last_int_player_uuid {
                                     client_stats_list_handle.remove(&client_stats);
 
                                     // Increment stats
                                     client_stats.kills += 1;
-                                    client_stats.score += 100;
+                                    client_stats.score += KILL_SCORE;
 
                                     // Update the BTreeSet on the serverside
                                     client_stats_list_handle.insert(client_stats.clone());
@@ -325,18 +613,115 @@ pub fn check_players_out_of_bounds(
                             }
                         }
 
+                        // The other players who have hit the pawn recently are awarded an assist
+                        let assists = pawn.assisting_attackers(killer, time.elapsed());
+
+                        for mut client_stats in client_stats_list
+                            .iter()
+                            .filter(|client_stats| assists.contains(&client_stats.uuid))
+                            .cloned()
+                        {
+                            client_stats_list_handle.remove(&client_stats);
+
+                            client_stats.assists += 1;
+                            client_stats.score += ASSIST_SCORE;
+
+                            client_stats_list_handle.insert(client_stats.clone());
+
+                            modified_client_stats.push(client_stats);
+                        }
+
+                        knockouts.push((
+                            pawn.arena,
+                            ServerRequest::PlayerKnockedOut {
+                                victim: pawn.uuid,
+                                killer,
+                                assists,
+                            },
+                        ));
+
                         // Despawn pawn which has fallen off
                         commands.entity(e).despawn();
 
-                        // Respawn the pawn
-                        spawn_pawn(&mut commands, pawn.uuid, collision_groups.pawn);
+                        let arena_round = &mut app_ctx.arenas[pawn.arena];
+
+                        let sudden_death = arena_round
+                            .sudden_death
+                            .as_mut()
+                            .filter(|sudden_death| sudden_death.players.contains(&pawn.uuid));
+
+                        if let Some(sudden_death) = sudden_death {
+                            // The first competing player to fall off ends the sudden death, their pawn is respawned with the spectators'
+                            sudden_death.spectators.push(pawn.uuid);
+                            sudden_death.has_ended = true;
+                        } else if is_eliminated {
+                            // Eliminated players spectate until the next round, so their pawn is not respawned
+                            arena_round.elimination_order.push(pawn.uuid);
+                        } else if let Some(respawn_delay) = app_ctx
+                            .settings
+                            .respawn_delay
+                            .filter(|respawn_delay| *respawn_delay > 0.)
+                        {
+                            // The pawn is respawned once the delay is over, the player spectates until then
+                            commands.spawn(PendingRespawn {
+                                uuid: pawn.uuid,
+                                player_slot: pawn.player_slot,
+                                tint: pawn.tint,
+                                pawn_type: pawn.pawn_type,
+                                arena: pawn.arena,
+                                scale: pawn.scale,
+                                timer: Timer::new(
+                                    Duration::from_secs_f32(respawn_delay),
+                                    bevy::time::TimerMode::Once,
+                                ),
+                            });
+
+                            delayed_respawns.push(pawn.uuid);
+                        } else {
+                            let spawn_position = respawn_position(
+                                server_instance,
+                                &all_pawns,
+                                pawn.uuid,
+                                pawn.arena,
+                                app_ctx.settings.safe_spawn_distance,
+                                &mut rand,
+                            );
+
+                            // Respawn the pawn
+                            spawn_pawn(
+                                &mut commands,
+                                pawn.uuid,
+                                pawn.player_slot,
+                                pawn.tint,
+                                pawn.pawn_type,
+                                pawn.arena,
+                                spawn_position,
+                                pawn.scale,
+                                &collision_groups,
+                            );
+                        }
                     }
                 }
             }
         }
         // Clone the list handle
         let connected_clients_clone = server_instance.connected_client_tcp_handles.clone();
-        if !modified_client_stats.is_empty() {
+        if !modified_client_stats.is_empty() || !knockouts.is_empty() {
+            let arena_assignments = server_instance.arena_assignments.lock();
+
+            // The knockouts are only sent to the players of the arena they have happened in
+            let knockouts = knockouts
+                .into_iter()
+                .map(|(arena, knockout)| {
+                    (
+                        knockout,
+                        clients_in_arena(&connected_clients_clone, &arena_assignments, arena),
+                    )
+                })
+                .collect::<Vec<_>>();
+
+            drop(arena_assignments);
+
             // Create an async task for sending the updates to the clients
             runtime.spawn_background_task(async move |_ctx| {
                 // Notify all the clients about the new entries
@@ -346,10 +731,136 @@ pub fn check_players_out_of_bounds(
                             modified_client_stats,
                         ),
                     },
-                    connected_clients_clone,
+                    connected_clients_clone.clone(),
                 )
                 .await;
+
+                // The knockouts are sent after the statistics, so that the clients' kill feed can show the updated names
+                for (knockout, arena_clients) in knockouts {
+                    send_request_to_all_clients(
+                        RemoteServerRequest { request: knockout },
+                        arena_clients,
+                    )
+                    .await;
+                }
             });
         }
+
+        if let Some(respawn_delay) = app_ctx.settings.respawn_delay {
+            let connected_clients = server_instance.connected_client_tcp_handles.clone();
+
+            for uuid in delayed_respawns {
+                let connected_clients = connected_clients.clone();
+
+                // Let the player display the countdown until its pawn is respawned
+                runtime.spawn_background_task(async move |_ctx| {
+                    let Some(handle) = connected_clients
+                        .iter()
+                        .find(|client| client.value().0 == uuid)
+                    else {
+                        return;
+                    };
+
+                    let (_, tcp_write) = handle.value();
+
+                    let _ = send_request_to_client(
+                        &mut tcp_write.lock(),
+                        RemoteServerRequest {
+                            request: ServerRequest::RespawnIn(respawn_delay),
+                        },
+                    )
+                    .await;
+                });
+            }
+        }
+    }
+}
+
+/// Ticks the [`PendingRespawn`]s, and respawns the pawns whose respawn delay is over.
+/// The respawns are dropped if the player has left or already has a pawn, or if the round has ended as the next round spawns every player's pawn.
+pub fn respawn_pending_pawns(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut pending_respawns: Query<(Entity, &mut PendingRespawn)>,
+    all_pawns: Query<(&Pawn, &Transform)>,
+    mut app_ctx: ResMut<ApplicationCtx>,
+    collision_groups: Res<CollisionGroupSet>,
+    mut rand: ResMut<RandomEngine>,
+) {
+    // Reborrow the resource so that its fields can be borrowed separately
+    let app_ctx = &mut *app_ctx;
+
+    let Some(server_instance) = &app_ctx.server_instance else {
+        return;
+    };
+
+    for (entity, mut pending_respawn) in pending_respawns.iter_mut() {
+        let is_round_ongoing = matches!(
+            server_instance
+                .game_states
+                .read()
+                .get(pending_respawn.arena),
+            Some(ServerGameState::OngoingGame(_))
+        );
+
+        if !is_round_ongoing {
+            commands.entity(entity).despawn();
+
+            continue;
+        }
+
+        pending_respawn.timer.tick(time.delta());
+
+        if !pending_respawn.timer.finished() {
+            continue;
+        }
+
+        commands.entity(entity).despawn();
+
+        let is_connected = server_instance
+            .connected_clients_stats
+            .read()
+            .iter()
+            .any(|client_stats| client_stats.uuid == pending_respawn.uuid);
+
+        let has_pawn = all_pawns
+            .iter()
+            .any(|(pawn, _)| pawn.uuid == pending_respawn.uuid);
+
+        if !is_connected || has_pawn {
+            continue;
+        }
+
+        // The players who weren't competing in the sudden death spectate it, they are respawned when it ends
+        if let Some(sudden_death) = &mut app_ctx.arenas[pending_respawn.arena].sudden_death {
+            if !sudden_death.players.contains(&pending_respawn.uuid) {
+                if !sudden_death.spectators.contains(&pending_respawn.uuid) {
+                    sudden_death.spectators.push(pending_respawn.uuid);
+                }
+
+                continue;
+            }
+        }
+
+        let spawn_position = respawn_position(
+            server_instance,
+            &all_pawns,
+            pending_respawn.uuid,
+            pending_respawn.arena,
+            app_ctx.settings.safe_spawn_distance,
+            &mut rand,
+        );
+
+        spawn_pawn(
+            &mut commands,
+            pending_respawn.uuid,
+            pending_respawn.player_slot,
+            pending_respawn.tint,
+            pending_respawn.pawn_type,
+            pending_respawn.arena,
+            spawn_position,
+            pending_respawn.scale,
+            &collision_groups,
+        );
     }
 }
@@ -1,16 +1,27 @@
 use bevy::{
-    ecs::{component::Component, entity::Entity, system::Commands},
-    time::Timer,
+    ecs::{
+        component::Component,
+        entity::Entity,
+        event::EventReader,
+        query::With,
+        system::{Commands, Query, Res},
+    },
+    math::{vec2, Vec2},
+    time::{Time, Timer},
     transform::components::Transform,
 };
-use bevy_rapier2d::prelude::{ActiveEvents, Collider, Sensor};
+use bevy_rapier2d::prelude::{
+    ActiveCollisionTypes, ActiveEvents, Collider, CollisionEvent, CollisionGroups, RigidBody,
+    Sensor, Velocity,
+};
 use rand::{rngs::SmallRng, Rng};
-use std::time::Duration;
+use std::{collections::HashMap, ops::Range, time::Duration};
 use strum::EnumDiscriminants;
+use uuid::Uuid;
 
 use crate::{game::collision::CollisionGroupSet, Direction};
 
-use super::pawns::Pawn;
+use super::{map::MapElement, pawns::Pawn};
 
 #[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
 pub struct Combo {
@@ -44,6 +55,8 @@ pub struct AttackObject {
     pub attack_type: AttackType,
     pub attack_strength: f32,
     pub attack_by: Entity,
+    /// The attack's collider is despawned when this timer finishes.
+    pub lifetime: Timer,
 }
 
 impl AttackObject {
@@ -58,15 +71,332 @@ impl AttackObject {
             attack_type,
             attack_strength,
             attack_by,
+            lifetime: Timer::new(
+                attack_type.attack_profile().lifetime,
+                bevy::time::TimerMode::Once,
+            ),
         }
     }
+
+    /// Finishes the attack's lifetime, so that it is despawned by [`despawn_expired_attack_objects`].
+    pub fn expire(&mut self) {
+        let lifetime = self.lifetime.duration();
+
+        self.lifetime.set_elapsed(lifetime);
+    }
 }
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
 pub enum AttackType {
+    /// The light attack performed while facing up or down, this pushes the attacked pawn vertically too.
     Directional(Direction),
+    /// The heavy attack, which is slow to start and to recover from but hits the hardest.
     Super,
+    /// The light attack performed while facing left or right.
     Quick,
+    /// The ranged attack, which spawns a [`Projectile`] travelling in the direction the pawn is facing.
+    Projectile(Direction),
+    /// The charged attack, which is performed when the charge attack input is released.
+    /// Contains the charge level in percent, the attack's size, strength and knockback grow with it.
+    Charged(u8),
+}
+
+impl AttackType {
+    /// Returns the parameters of the attack type.
+    pub fn attack_profile(&self) -> AttackProfile {
+        match self {
+            AttackType::Directional(_) => AttackProfile {
+                collider_size: vec2(50., 50.),
+                shape: AttackShape::Rectangle,
+                strength_range: 14.0..21.0,
+                startup: Duration::ZERO,
+                cooldown: Duration::from_secs_f32(0.25),
+                knockback: 1.,
+                lifetime: MELEE_ATTACK_LIFETIME,
+            },
+            AttackType::Quick => AttackProfile {
+                collider_size: vec2(50., 50.),
+                shape: AttackShape::Arc {
+                    radius: 70.,
+                    thickness: 25.,
+                    start_angle: 50_f32.to_radians(),
+                    end_angle: -35_f32.to_radians(),
+                    segments: 5,
+                },
+                strength_range: 14.0..21.0,
+                startup: Duration::ZERO,
+                cooldown: Duration::from_secs_f32(0.25),
+                knockback: 1.,
+                lifetime: MELEE_ATTACK_LIFETIME,
+            },
+            AttackType::Super => AttackProfile {
+                collider_size: vec2(80., 60.),
+                shape: AttackShape::Arc {
+                    radius: 110.,
+                    thickness: 35.,
+                    start_angle: 100_f32.to_radians(),
+                    end_angle: -45_f32.to_radians(),
+                    segments: 7,
+                },
+                strength_range: 28.0..38.0,
+                startup: Duration::from_secs_f32(0.3),
+                cooldown: Duration::from_secs_f32(1.2),
+                knockback: 2.2,
+                lifetime: MELEE_ATTACK_LIFETIME,
+            },
+            AttackType::Projectile(_) => AttackProfile {
+                collider_size: vec2(10., 10.),
+                shape: AttackShape::Rectangle,
+                strength_range: 10.0..14.0,
+                startup: Duration::ZERO,
+                cooldown: Duration::from_secs_f32(0.6),
+                knockback: 0.7,
+                lifetime: Duration::from_secs_f32(1.5),
+            },
+            AttackType::Charged(charge_level) => {
+                let charge = f32::from((*charge_level).min(100)) / 100.;
+
+                AttackProfile {
+                    collider_size: vec2(50., 50.) * (1. + charge * 0.6),
+                    shape: AttackShape::Rectangle,
+                    strength_range: 16.0 * (1. + charge)..22.0 * (1. + charge),
+                    startup: Duration::ZERO,
+                    cooldown: Duration::from_secs_f32(0.8),
+                    knockback: 1. + charge * 1.5,
+                    lifetime: MELEE_ATTACK_LIFETIME,
+                }
+            }
+        }
+    }
+}
+
+/// The parameters which decide how an [`AttackType`] behaves.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AttackProfile {
+    /// The half extents of the attack's collider, this is only used by [`AttackShape::Rectangle`].
+    pub collider_size: Vec2,
+    /// The shape of the attack's collider.
+    pub shape: AttackShape,
+    /// The range the attack's strength is rolled from.
+    pub strength_range: Range<f32>,
+    /// The time it takes for the attack to hit after it has been started.
+    pub startup: Duration,
+    /// The time the attacker has to wait after starting the attack before attacking again.
+    pub cooldown: Duration,
+    /// The multiplier of the knockback applied to the attacked pawn.
+    pub knockback: f32,
+    /// The time the attack's collider exists for, the attack can hit pawns during this time.
+    pub lifetime: Duration,
+}
+
+/// The shape of an attack's collider.
+/// The shapes are defined for an attacker facing right, they are mirrored for the attackers facing left and rotated for the attackers facing up or down.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum AttackShape {
+    /// A rectangle with the profile's collider size, placed in front of the attacker.
+    Rectangle,
+    /// A swing around the attacker, made up of circles placed along an arc.
+    /// The angles are in radians, they are measured from the facing direction and the positive angles are above it.
+    Arc {
+        /// The distance of the circles from the attacker.
+        radius: f32,
+        /// The radius of the circles.
+        thickness: f32,
+        /// The angle the swing starts at.
+        start_angle: f32,
+        /// The angle the swing ends at.
+        end_angle: f32,
+        /// The amount of circles the arc is made up of.
+        segments: u32,
+    },
+}
+
+impl AttackShape {
+    /// Returns the offset of the attack's collider from the attacker facing the direction.
+    pub fn offset(&self, collider_size: Vec2, direction: Direction) -> Vec2 {
+        match self {
+            AttackShape::Rectangle => match direction {
+                Direction::Left => vec2(-collider_size.x, 0.),
+                Direction::Right => vec2(collider_size.x, 0.),
+                Direction::Up => vec2(0., collider_size.y),
+                Direction::Down => vec2(0., -collider_size.y),
+            },
+            // The arc is centered on the attacker
+            AttackShape::Arc { .. } => Vec2::ZERO,
+        }
+    }
+
+    /// Creates the attack's collider for an attacker facing the direction.
+    pub fn collider(&self, collider_size: Vec2, direction: Direction) -> Collider {
+        match *self {
+            AttackShape::Rectangle => Collider::cuboid(collider_size.x, collider_size.y),
+            AttackShape::Arc {
+                radius,
+                thickness,
+                start_angle,
+                end_angle,
+                segments,
+            } => {
+                // The forward and the upward axes of the swing, mirroring the upward axis would turn the swing around
+                let (forward, upward) = match direction {
+                    Direction::Left => (vec2(-1., 0.), vec2(0., 1.)),
+                    Direction::Right => (vec2(1., 0.), vec2(0., 1.)),
+                    Direction::Up => (vec2(0., 1.), vec2(-1., 0.)),
+                    Direction::Down => (vec2(0., -1.), vec2(1., 0.)),
+                };
+
+                let segments = segments.max(1);
+
+                let circles = (0..segments)
+                    .map(|segment| {
+                        let progress = if segments == 1 {
+                            0.5
+                        } else {
+                            segment as f32 / (segments - 1) as f32
+                        };
+
+                        let angle = start_angle + (end_angle - start_angle) * progress;
+
+                        let position = (forward * angle.cos() + upward * angle.sin()) * radius;
+
+                        (position, 0., Collider::ball(thickness))
+                    })
+                    .collect();
+
+                Collider::compound(circles)
+            }
+        }
+    }
+}
+
+/// The lifetime of the melee attacks, this is a single tick of the default fixed timestep so that they only hit once.
+pub const MELEE_ATTACK_LIFETIME: Duration = Duration::from_micros(15_625);
+
+/// The speed the projectiles travel at.
+pub const PROJECTILE_SPEED: f32 = 900.;
+
+/// The time it takes to fully charge an [`AttackType::Charged`] attack.
+pub const MAX_CHARGE_DURATION: Duration = Duration::from_millis(1500);
+
+/// A moving [`AttackObject`], which travels until it hits a pawn or a map object or its lifetime expires.
+/// The server sends the projectiles' positions to the players of the arena, so that the clients can display them.
+#[derive(Component, Debug, Clone, Copy)]
+pub struct Projectile {
+    /// The arena the projectile has been fired in.
+    pub arena: usize,
+}
+
+/// The falloff of the knockback when the same attacker hits the same pawn repeatedly, this discourages infinite combos on cornered players.
+/// The knockback is multiplied by `falloff` for every recent hit, but it never goes below `min_multiplier`.
+#[derive(Debug, Clone, Copy, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct StaleHitFalloff {
+    /// Whether the knockback falls off, this is disabled by default.
+    pub enabled: bool,
+    /// The multiplier applied to the knockback for every recent hit by the same attacker.
+    pub falloff: f32,
+    /// The lowest multiplier the knockback can be scaled down to.
+    pub min_multiplier: f32,
+    /// The time it takes for a single recent hit to be forgotten.
+    pub recovery: Duration,
+}
+
+impl Default for StaleHitFalloff {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            falloff: 0.85,
+            min_multiplier: 0.4,
+            recovery: Duration::from_secs(1),
+        }
+    }
+}
+
+impl StaleHitFalloff {
+    /// Returns the knockback multiplier of a hit after the given amount of recent hits by the same attacker.
+    pub fn multiplier(&self, stale_hits: u32) -> f32 {
+        self.falloff
+            .powi(stale_hits as i32)
+            .max(self.min_multiplier)
+    }
+}
+
+/// The scaling of the knockback in crowded fights, so that the fights of large lobbies stay readable.
+/// The knockback is reduced by `reduction_per_pawn` for every other pawn within `radius` of the attacked pawn, but it never goes below `min_multiplier`.
+#[derive(Debug, Clone, Copy, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct CrowdKnockbackScaling {
+    /// Whether the knockback is scaled, this is disabled by default.
+    pub enabled: bool,
+    /// The distance from the attacked pawn the pawns are counted in.
+    pub radius: f32,
+    /// The amount the multiplier is reduced by for every nearby pawn.
+    pub reduction_per_pawn: f32,
+    /// The lowest multiplier the knockback can be scaled down to.
+    pub min_multiplier: f32,
+}
+
+impl Default for CrowdKnockbackScaling {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            radius: 250.,
+            reduction_per_pawn: 0.08,
+            min_multiplier: 0.6,
+        }
+    }
+}
+
+impl CrowdKnockbackScaling {
+    /// Returns the knockback multiplier of a hit with the given amount of pawns near the attacked pawn, the attacker and the attacked pawn aren't counted.
+    pub fn multiplier(&self, nearby_pawns: usize) -> f32 {
+        (1. - self.reduction_per_pawn * nearby_pawns as f32).max(self.min_multiplier)
+    }
+}
+
+/// The recent hits a pawn has taken, keyed by the attacker's id.
+/// Contains the amount of recent hits and the time of the last one, the hits are forgotten over time.
+#[derive(Component, Debug, Clone, Default)]
+pub struct RecentHits(HashMap<Uuid, (u32, Duration)>);
+
+impl RecentHits {
+    /// Registers a hit by the attacker at the given time, and returns the knockback multiplier of the hit.
+    pub fn register_hit(
+        &mut self,
+        attacker: Uuid,
+        now: Duration,
+        falloff: &StaleHitFalloff,
+    ) -> f32 {
+        let (stale_hits, last_hit) = self.0.entry(attacker).or_default();
+
+        // Forget a hit for every recovery period passed since the last hit
+        let recovered_hits = if falloff.recovery.is_zero() {
+            u32::MAX
+        } else {
+            (now.saturating_sub(*last_hit).as_secs_f32() / falloff.recovery.as_secs_f32()) as u32
+        };
+
+        *stale_hits = stale_hits.saturating_sub(recovered_hits);
+
+        let multiplier = falloff.multiplier(*stale_hits);
+
+        *stale_hits += 1;
+        *last_hit = now;
+
+        multiplier
+    }
+}
+
+/// An attack which is still starting up, the attack's collider is spawned when the startup timer finishes.
+#[derive(Component, Clone)]
+pub struct PendingAttack {
+    pub startup_timer: Timer,
+    pub attack_object: AttackObject,
+    pub attack_transform: Transform,
+    /// The direction the attacker has been facing, the attack's collider is turned this way.
+    pub direction: Direction,
+    /// The collision group of the attacker's arena, this is inserted into the attack's collider.
+    pub collision_group: CollisionGroups,
 }
 
 #[derive(Clone, serde::Deserialize, serde::Serialize, Debug)]
@@ -96,28 +426,279 @@ pub enum EffectType {
     Stunned,
 }
 
-/// Spawns in a Cuboid and then the collisions are checked so that we know which enemies are affected.
+/// Spawns in the attack's collider and then the collisions are checked so that we know which enemies are affected.
+/// If the attack type has a startup, a [`PendingAttack`] is spawned instead which spawns the collider when the startup has finished.
+#[allow(clippy::too_many_arguments)]
 pub fn spawn_attack(
     commands: &mut Commands,
     collision_groups: &CollisionGroupSet,
     rand: &mut SmallRng,
     entity: Entity,
-    local_player: &mut Pawn,
     transform: &Transform,
-    attack_collider: Collider,
+    attack_type: AttackType,
+    attack_transform: Transform,
+    direction: Direction,
+) {
+    let attack_profile = attack_type.attack_profile();
+
+    let attack_object = AttackObject::new(
+        attack_type,
+        rand.random_range(attack_profile.strength_range),
+        *transform,
+        entity,
+    );
+
+    if attack_profile.startup.is_zero() {
+        spawn_attack_object(
+            commands,
+            collision_groups.attack_obj,
+            attack_object,
+            attack_transform,
+            direction,
+        );
+    } else {
+        commands.spawn(PendingAttack {
+            startup_timer: Timer::new(attack_profile.startup, bevy::time::TimerMode::Once),
+            attack_object,
+            attack_transform,
+            direction,
+            collision_group: collision_groups.attack_obj,
+        });
+    }
+}
+
+/// Spawns the attack's collider, the collisions are checked in [`crate::game::collision::check_for_collision_with_attack_object`].
+fn spawn_attack_object(
+    commands: &mut Commands,
+    collision_group: CollisionGroups,
+    attack_object: AttackObject,
     attack_transform: Transform,
+    direction: Direction,
 ) {
+    let attack_profile = attack_object.attack_type.attack_profile();
+
     commands
-        .spawn(attack_collider)
+        .spawn(
+            attack_profile
+                .shape
+                .collider(attack_profile.collider_size, direction),
+        )
         .insert(ActiveEvents::COLLISION_EVENTS)
         .insert(ActiveEvents::CONTACT_FORCE_EVENTS)
-        .insert(AttackObject::new(
-            AttackType::Directional(local_player.direction),
-            rand.random_range(14.0..21.0),
-            *transform,
-            entity,
+        .insert(attack_object)
+        .insert(Sensor)
+        .insert(collision_group)
+        .insert(attack_transform);
+}
+
+/// Spawns a [`Projectile`] travelling in the direction with [`PROJECTILE_SPEED`].
+/// The collision groups should be the attacker's arena's groups.
+#[allow(clippy::too_many_arguments)]
+pub fn spawn_projectile(
+    commands: &mut Commands,
+    collision_groups: &CollisionGroupSet,
+    rand: &mut SmallRng,
+    entity: Entity,
+    transform: &Transform,
+    direction: Direction,
+    arena: usize,
+    attack_transform: Transform,
+) {
+    let attack_type = AttackType::Projectile(direction);
+
+    let attack_profile = attack_type.attack_profile();
+
+    let attack_object = AttackObject::new(
+        attack_type,
+        rand.random_range(attack_profile.strength_range),
+        *transform,
+        entity,
+    );
+
+    let velocity = match direction {
+        Direction::Left => vec2(-PROJECTILE_SPEED, 0.),
+        Direction::Right => vec2(PROJECTILE_SPEED, 0.),
+        Direction::Up => vec2(0., PROJECTILE_SPEED),
+        Direction::Down => vec2(0., -PROJECTILE_SPEED),
+    };
+
+    commands
+        .spawn(RigidBody::KinematicVelocityBased)
+        .insert(Collider::cuboid(
+            attack_profile.collider_size.x,
+            attack_profile.collider_size.y,
         ))
+        .insert(Velocity::linear(velocity))
+        // The projectiles have to collide with the kinematic map objects too
+        .insert(ActiveCollisionTypes::default() | ActiveCollisionTypes::KINEMATIC_KINEMATIC)
+        .insert(ActiveEvents::COLLISION_EVENTS)
+        .insert(attack_object)
+        .insert(Projectile { arena })
         .insert(Sensor)
         .insert(collision_groups.attack_obj)
         .insert(attack_transform);
 }
+
+/// Expires the [`Projectile`]s which have hit a map object or a pawn other than their attacker, they are despawned by [`despawn_expired_attack_objects`].
+/// The knockback of the hit pawns is applied by [`crate::game::collision::check_for_collision_with_attack_object`].
+pub fn expire_projectiles_on_impact(
+    mut collision_events: EventReader<CollisionEvent>,
+    mut projectiles: Query<&mut AttackObject, With<Projectile>>,
+    map_elements: Query<(), With<MapElement>>,
+    pawns: Query<(), With<Pawn>>,
+) {
+    for collision in collision_events.read() {
+        let CollisionEvent::Started(entity, entity1, _) = collision else {
+            continue;
+        };
+
+        for (projectile_entity, other_entity) in [(*entity, *entity1), (*entity1, *entity)] {
+            let Ok(mut attack_object) = projectiles.get_mut(projectile_entity) else {
+                continue;
+            };
+
+            // The projectile passes through the pawn which has fired it
+            if attack_object.attack_by == other_entity {
+                continue;
+            }
+
+            if map_elements.contains(other_entity) || pawns.contains(other_entity) {
+                attack_object.expire();
+            }
+        }
+    }
+}
+
+/// Ticks the startup of the [`PendingAttack`]s, and spawns the attacks which have finished starting up.
+pub fn spawn_pending_attacks(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut pending_attacks: Query<(Entity, &mut PendingAttack)>,
+) {
+    for (entity, mut pending_attack) in pending_attacks.iter_mut() {
+        pending_attack.startup_timer.tick(time.delta());
+
+        if pending_attack.startup_timer.finished() {
+            commands.entity(entity).despawn();
+
+            spawn_attack_object(
+                &mut commands,
+                pending_attack.collision_group,
+                pending_attack.attack_object.clone(),
+                pending_attack.attack_transform,
+                pending_attack.direction,
+            );
+        }
+    }
+}
+
+/// Ticks the lifetime of the [`AttackObject`]s, and despawns the attacks which have expired.
+/// This should run after the collisions with the attacks have been checked, so that the attacks can hit on their last tick.
+pub fn despawn_expired_attack_objects(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut attack_objects: Query<(Entity, &mut AttackObject)>,
+) {
+    for (entity, mut attack_object) in attack_objects.iter_mut() {
+        attack_object.lifetime.tick(time.delta());
+
+        if attack_object.lifetime.finished() {
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
+/// Ticks the attack cooldowns, the super armors and the spawn protections of the pawns, they are removed when they have finished.
+/// The charges of the charging pawns are built up here too, until they reach [`MAX_CHARGE_DURATION`].
+pub fn tick_attack_cooldowns(time: Res<Time>, mut pawns: Query<&mut Pawn>) {
+    for mut pawn in pawns.iter_mut() {
+        // Only access the pawns mutably if they have a cooldown, so that the others aren't marked as changed
+        if pawn.attack_cooldown.is_none()
+            && pawn.super_armor.is_none()
+            && pawn.spawn_protection.is_none()
+            && pawn.charge.is_none()
+        {
+            continue;
+        }
+
+        if let Some(charge) = &mut pawn.charge {
+            *charge = (*charge + time.delta()).min(MAX_CHARGE_DURATION);
+        }
+
+        if let Some(attack_cooldown) = &mut pawn.attack_cooldown {
+            attack_cooldown.tick(time.delta());
+
+            if attack_cooldown.finished() {
+                pawn.attack_cooldown = None;
+            }
+        }
+
+        if let Some(super_armor) = &mut pawn.super_armor {
+            super_armor.tick(time.delta());
+
+            if super_armor.finished() {
+                pawn.super_armor = None;
+            }
+        }
+
+        if let Some(spawn_protection) = &mut pawn.spawn_protection {
+            spawn_protection.tick(time.delta());
+
+            if spawn_protection.finished() {
+                pawn.spawn_protection = None;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stale_hit_falloff_is_disabled_by_default() {
+        assert!(!StaleHitFalloff::default().enabled);
+    }
+
+    #[test]
+    fn stale_hit_multipliers_fall_off_to_the_minimum() {
+        let falloff = StaleHitFalloff {
+            enabled: true,
+            falloff: 0.5,
+            min_multiplier: 0.2,
+            recovery: Duration::from_secs(1),
+        };
+
+        let mut recent_hits = RecentHits::default();
+        let attacker = Uuid::new_v4();
+
+        let multipliers = (0..5)
+            .map(|_| recent_hits.register_hit(attacker, Duration::ZERO, &falloff))
+            .collect::<Vec<f32>>();
+
+        assert_eq!(multipliers, vec![1., 0.5, 0.25, 0.2, 0.2]);
+    }
+
+    #[test]
+    fn stale_hits_are_forgotten_over_time() {
+        let falloff = StaleHitFalloff {
+            enabled: true,
+            falloff: 0.5,
+            min_multiplier: 0.2,
+            recovery: Duration::from_secs(1),
+        };
+
+        let mut recent_hits = RecentHits::default();
+        let attacker = Uuid::new_v4();
+
+        for _ in 0..3 {
+            recent_hits.register_hit(attacker, Duration::ZERO, &falloff);
+        }
+
+        // Two of the three recent hits are forgotten after two recovery periods
+        assert_eq!(
+            recent_hits.register_hit(attacker, Duration::from_secs(2), &falloff),
+            0.5
+        );
+    }
+}
@@ -0,0 +1,161 @@
+use std::time::Duration;
+
+use bevy::{
+    ecs::{
+        component::Component,
+        system::{Commands, Query, Res},
+    },
+    math::Vec2,
+    time::{Time, Timer},
+    transform::components::Transform,
+};
+use bevy_rapier2d::prelude::Velocity;
+use uuid::Uuid;
+
+use crate::server::ApplicationCtx;
+
+use super::{
+    collision::{CollisionGroupSet, OUT_OF_BOUNDS_HEIGHT},
+    pawns::{spawn_pawn, Pawn, PawnType, DEFAULT_PAWN_SCALE},
+};
+
+/// The distance a training dummy has to be knocked away from its home before it is reset.
+pub const DUMMY_DISPLACEMENT_TOLERANCE: f32 = 20.;
+
+/// The speed below which a freshly spawned training dummy is considered to have landed, once its spawn protection has worn off.
+pub const DUMMY_LANDING_SPEED: f32 = 1.;
+
+/// A pawn which takes hits but doesn't fight back, the players can use it to practice their combos and to test the attacks' ranges.
+/// The dummy is teleported back to its home after it has been launched, see [`reset_training_dummies`].
+#[derive(Component, Debug, Clone)]
+pub struct TrainingDummy {
+    /// The position the dummy is reset to, this is where the dummy has landed after being spawned.
+    pub home: Vec2,
+
+    /// Whether the dummy has landed after being spawned, the dummy's home is its spawn position until then.
+    pub has_landed: bool,
+
+    /// The time left until the dummy is reset, this is [`None`] while the dummy is at its home.
+    pub reset_timer: Option<Timer>,
+
+    /// The amount of hits the dummy has taken since it was last reset.
+    pub combo: u32,
+
+    /// The longest combo the dummy has taken.
+    pub best_combo: u32,
+
+    /// The total strength of the attacks the dummy has taken.
+    pub damage: f32,
+}
+
+impl TrainingDummy {
+    pub fn new(home: Vec2) -> Self {
+        Self {
+            home,
+            has_landed: false,
+            reset_timer: None,
+            combo: 0,
+            best_combo: 0,
+            damage: 0.,
+        }
+    }
+
+    /// Registers a hit taken by the dummy.
+    pub fn register_hit(&mut self, attack_strength: f32) {
+        self.combo += 1;
+        self.best_combo = self.best_combo.max(self.combo);
+        self.damage += attack_strength;
+    }
+}
+
+/// Spawns a training dummy in the arena at the position, the dummy is a regular pawn without a player controlling it.
+pub fn spawn_training_dummy(
+    commands: &mut Commands,
+    arena: usize,
+    position: Vec2,
+    collision_groups: &CollisionGroupSet,
+) {
+    let entity = spawn_pawn(
+        commands,
+        Uuid::new_v4(),
+        0,
+        None,
+        PawnType::default(),
+        arena,
+        position,
+        DEFAULT_PAWN_SCALE,
+        collision_groups,
+    );
+
+    commands.entity(entity).insert(TrainingDummy::new(position));
+}
+
+/// Teleports the training dummies back to their homes once they have been away for [`crate::server::Settings::dummy_reset_delay`].
+/// The dummies falling off the map are reset immediately, so that they aren't knocked out like the players' pawns.
+pub fn reset_training_dummies(
+    app_ctx: Res<ApplicationCtx>,
+    time: Res<Time>,
+    mut training_dummies: Query<(&mut TrainingDummy, &Pawn, &mut Transform, &mut Velocity)>,
+) {
+    for (mut training_dummy, pawn, mut transform, mut velocity) in training_dummies.iter_mut() {
+        let position = transform.translation.truncate();
+
+        let out_of_bounds = position.y < OUT_OF_BOUNDS_HEIGHT;
+
+        // The dummy's home is set where it comes to rest after being spawned
+        if !training_dummy.has_landed {
+            if out_of_bounds {
+                reset_training_dummy(&mut training_dummy, &mut transform, &mut velocity);
+            } else if pawn.spawn_protection.is_none()
+                && velocity.linvel.length() < DUMMY_LANDING_SPEED
+            {
+                training_dummy.home = position;
+                training_dummy.has_landed = true;
+            }
+
+            continue;
+        }
+
+        if position.distance(training_dummy.home) <= DUMMY_DISPLACEMENT_TOLERANCE {
+            training_dummy.reset_timer = None;
+
+            continue;
+        }
+
+        // The dummies stay where they were launched to if the resetting is disabled
+        let Some(reset_delay) = app_ctx.settings.dummy_reset_delay else {
+            if out_of_bounds {
+                reset_training_dummy(&mut training_dummy, &mut transform, &mut velocity);
+            }
+
+            continue;
+        };
+
+        let reset_timer = training_dummy.reset_timer.get_or_insert_with(|| {
+            Timer::new(
+                Duration::from_secs_f32(reset_delay),
+                bevy::time::TimerMode::Once,
+            )
+        });
+
+        reset_timer.tick(time.delta());
+
+        if reset_timer.finished() || out_of_bounds {
+            reset_training_dummy(&mut training_dummy, &mut transform, &mut velocity);
+        }
+    }
+}
+
+/// Teleports the training dummy to its home and stops it, this also ends the dummy's current combo.
+fn reset_training_dummy(
+    training_dummy: &mut TrainingDummy,
+    transform: &mut Transform,
+    velocity: &mut Velocity,
+) {
+    transform.translation = training_dummy.home.extend(transform.translation.z);
+
+    *velocity = Velocity::zero();
+
+    training_dummy.reset_timer = None;
+    training_dummy.combo = 0;
+}
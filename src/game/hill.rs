@@ -0,0 +1,251 @@
+use std::collections::HashMap;
+
+use bevy::{
+    ecs::{
+        component::Component,
+        system::{Query, Res, ResMut},
+    },
+    math::Rect,
+    time::Time,
+    transform::components::Transform,
+};
+use bevy_tokio_tasks::TokioTasksRuntime;
+use uuid::Uuid;
+
+use crate::{
+    networking::{
+        server::{clients_in_arena, send_request_to_all_clients, ServerInstance},
+        ClientStatistics, RemoteServerRequest, ServerGameState, ServerRequest,
+    },
+    server::ApplicationCtx,
+};
+
+use super::pawns::Pawn;
+
+/// The score awarded every second to the player controlling the hill.
+pub const HILL_SCORE_PER_SECOND: f32 = 10.;
+
+/// A marker for the area of the map's hill, every arena has its own copy of the hill.
+#[derive(Component, Debug, Clone, Copy)]
+pub struct HillZone {
+    /// The arena this copy of the hill belongs to.
+    pub arena: usize,
+}
+
+/// How the hill's score is awarded while multiple pawns are standing on the hill.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Default,
+    serde::Deserialize,
+    serde::Serialize,
+    strum::Display,
+    strum::VariantArray,
+)]
+pub enum ContestedHillScoring {
+    /// Nobody is awarded score while the hill is contested.
+    #[default]
+    #[strum(to_string = "No score")]
+    NoScore,
+
+    /// The score is split evenly between the pawns standing on the hill.
+    #[strum(to_string = "Split")]
+    Split,
+}
+
+/// The state of the hill in an arena, this is sent to the players of the arena every time it changes.
+#[derive(Debug, Clone, PartialEq, Eq, Default, serde::Deserialize, serde::Serialize)]
+pub enum HillState {
+    /// There are no pawns standing on the hill.
+    #[default]
+    Uncontrolled,
+
+    /// The hill is controlled by the player with this id.
+    Controlled(Uuid),
+
+    /// Multiple pawns are standing on the hill, contains the ids of their players.
+    Contested(Vec<Uuid>),
+}
+
+impl HillState {
+    /// Returns the state of the hill with the given players standing on it.
+    pub fn from_players(mut players: Vec<Uuid>) -> Self {
+        // Sort the players so that the state only changes if the players on the hill change
+        players.sort();
+
+        match players.len() {
+            0 => Self::Uncontrolled,
+            1 => Self::Controlled(players[0]),
+            _ => Self::Contested(players),
+        }
+    }
+}
+
+/// Awards score to the players standing on the map's hill every tick, and notifies the players of the arenas when their hill's state changes.
+/// The hill is only scored if [`crate::server::Settings::hill_scoring`] is enabled.
+/// The score is accumulated in [`ApplicationCtx::hill_score_progress`], so that fractions of a point are not lost between the ticks.
+pub fn handle_hill_control(
+    mut app_ctx: ResMut<ApplicationCtx>,
+    time: Res<Time>,
+    runtime: Res<TokioTasksRuntime>,
+    pawns: Query<(&Pawn, &Transform)>,
+) {
+    // Reborrow the resource so that its fields can be borrowed separately
+    let app_ctx = &mut *app_ctx;
+
+    let Some(server_instance) = &app_ctx.server_instance else {
+        return;
+    };
+
+    // The arenas' hills are only scored while their round is running, if the server has enabled it
+    let hills: Vec<Option<Rect>> = server_instance
+        .game_states
+        .read()
+        .iter()
+        .zip(app_ctx.arenas.iter())
+        .map(|(game_state, arena_round)| match game_state {
+            ServerGameState::OngoingGame(ongoing_game_data)
+                if arena_round.game_round_timer.is_some() =>
+            {
+                ongoing_game_data.current_map.hill
+            }
+            _ => None,
+        })
+        .collect();
+
+    if !app_ctx.settings.hill_scoring || hills.iter().all(Option::is_none) {
+        // The players mustn't keep seeing a controlled hill
+        for (arena, hill_state) in app_ctx.hill_states.drain() {
+            if hill_state != HillState::Uncontrolled {
+                notify_hill_state_change(server_instance, &runtime, arena, HillState::Uncontrolled);
+            }
+        }
+
+        app_ctx.hill_score_progress.clear();
+
+        return;
+    }
+
+    // Collect the players standing on their arena's hill
+    let mut players_on_hill: HashMap<usize, Vec<Uuid>> = HashMap::new();
+
+    for (pawn, transform) in pawns.iter() {
+        let Some(hill) = hills.get(pawn.arena).copied().flatten() else {
+            continue;
+        };
+
+        if hill.contains(transform.translation.truncate()) {
+            players_on_hill
+                .entry(pawn.arena)
+                .or_default()
+                .push(pawn.uuid);
+        }
+    }
+
+    let mut modified_client_stats: Vec<ClientStatistics> = Vec::new();
+
+    for arena in 0..hills.len() {
+        let hill_state =
+            HillState::from_players(players_on_hill.remove(&arena).unwrap_or_default());
+
+        // Decide who is awarded the score and how much of it
+        let scoring_players = match &hill_state {
+            HillState::Uncontrolled => Vec::new(),
+            HillState::Controlled(player) => vec![(*player, 1.)],
+            HillState::Contested(players) => match app_ctx.settings.contested_hill_scoring {
+                ContestedHillScoring::NoScore => Vec::new(),
+                ContestedHillScoring::Split => players
+                    .iter()
+                    .map(|player| (*player, 1. / players.len() as f32))
+                    .collect(),
+            },
+        };
+
+        for (player, share) in scoring_players {
+            let progress = app_ctx.hill_score_progress.entry(player).or_default();
+
+            *progress += HILL_SCORE_PER_SECOND * share * time.delta_secs();
+
+            // Only award the whole points, the fraction is kept for the next tick
+            let awarded_score = progress.floor();
+
+            if awarded_score < 1. {
+                continue;
+            }
+
+            *progress -= awarded_score;
+
+            let mut client_stats_list_handle = server_instance.connected_clients_stats.write();
+
+            let client_stats = client_stats_list_handle
+                .iter()
+                .find(|client_stats| client_stats.uuid == player)
+                .cloned();
+
+            if let Some(mut client_stats) = client_stats {
+                // Remove the original entry
+                client_stats_list_handle.remove(&client_stats);
+
+                client_stats.score += awarded_score as u32;
+
+                // Re-insert the entry
+                client_stats_list_handle.insert(client_stats.clone());
+
+                modified_client_stats.push(client_stats);
+            }
+        }
+
+        // Only notify the players of the arena if the hill's state has changed
+        if app_ctx.hill_states.get(&arena).cloned().unwrap_or_default() == hill_state {
+            continue;
+        }
+
+        app_ctx.hill_states.insert(arena, hill_state.clone());
+
+        notify_hill_state_change(server_instance, &runtime, arena, hill_state);
+    }
+
+    if modified_client_stats.is_empty() {
+        return;
+    }
+
+    // The scores are sent to every client, as the leaderboard is shared between the arenas
+    let connected_clients = server_instance.connected_client_tcp_handles.clone();
+
+    runtime.spawn_background_task(async move |_ctx| {
+        send_request_to_all_clients(
+            RemoteServerRequest {
+                request: ServerRequest::PlayersStatisticsChange(modified_client_stats),
+            },
+            connected_clients,
+        )
+        .await;
+    });
+}
+
+/// Sends the new state of the arena's hill to the players of the arena.
+fn notify_hill_state_change(
+    server_instance: &ServerInstance,
+    runtime: &TokioTasksRuntime,
+    arena: usize,
+    hill_state: HillState,
+) {
+    let connected_clients = clients_in_arena(
+        &server_instance.connected_client_tcp_handles,
+        &server_instance.arena_assignments.lock(),
+        arena,
+    );
+
+    runtime.spawn_background_task(async move |_ctx| {
+        send_request_to_all_clients(
+            RemoteServerRequest {
+                request: ServerRequest::HillStateChange(hill_state),
+            },
+            connected_clients,
+        )
+        .await;
+    });
+}
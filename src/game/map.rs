@@ -1,19 +1,26 @@
-use std::time::Duration;
+use std::{ops::Range, time::Duration};
 
 use bevy::{
+    color::Color,
     ecs::{
         component::Component,
         entity::Entity,
         query::Without,
         system::{Commands, Query},
+        world::World,
     },
-    math::{vec2, Vec2},
+    math::{vec2, Rect, Vec2},
+    render::camera::ClearColor,
+    sprite::Sprite,
     transform::components::Transform,
 };
-use bevy_rapier2d::prelude::{ActiveEvents, Ccd, Collider, Friction, Restitution};
+use bevy_rapier2d::prelude::{
+    ActiveEvents, Ccd, Collider, Friction, RapierConfiguration, Restitution,
+};
+use rand::{rngs::SmallRng, seq::IteratorRandom};
 use uuid::Uuid;
 
-use super::{collision::CollisionGroupSet, pawns::Pawn};
+use super::{collision::CollisionGroupSet, hill::HillZone, modifiers::MatchModifiers, pawns::Pawn};
 
 /// A StaticMapElement instnace is an object which is a part of the map.
 /// This is used to make difference between Entities which are a part of the obstacles contained in the map.
@@ -22,6 +29,8 @@ pub struct MapElement {
     pub id: Uuid,
     pub object_type: ObjectType,
     pub initial_position: Option<Vec2>,
+    /// The arena this copy of the map object belongs to, every arena has its own copy of the map.
+    pub arena: usize,
 }
 
 #[derive(Component, Clone, Debug, serde::Deserialize, serde::Serialize, PartialEq)]
@@ -102,10 +111,105 @@ pub enum ObjectType {
 
 #[derive(Component, Clone, Debug, serde::Deserialize, serde::Serialize, PartialEq)]
 pub struct MapInstance {
+    /// The name of the map displayed to the players, this is empty for the maps without a name.
+    #[serde(default)]
+    pub name: String,
+
     pub objects: Vec<MapObject>,
+
+    /// The multiplier of the physics world's gravity while this map is loaded, this is lower than 1 for floaty maps.
+    #[serde(default = "default_gravity_scale")]
+    pub gravity_scale: f32,
+
+    /// The direction the physics world's gravity pulls the pawns in, gimmick maps can use this to pull the pawns sideways.
+    /// The pawns always jump in the opposite direction.
+    #[serde(default = "default_gravity_direction")]
+    pub gravity_direction: Vec2,
+
+    /// The area of the map the players are awarded score for standing in, this is [`None`] if the map doesn't have a hill.
+    #[serde(default)]
+    pub hill: Option<Rect>,
+
+    /// The name of the music track played on the map, the track is looked up in the active resource pack first.
+    /// This is [`None`] if the map doesn't have music.
+    #[serde(default)]
+    pub music: Option<String>,
+
+    /// The points the pawns are respawned at, the point farthest from the enemies is picked.
+    /// The pawns are respawned at [`DEFAULT_SPAWN_POSITION`] if the map doesn't have spawn points.
+    #[serde(default)]
+    pub spawn_points: Vec<Vec2>,
+
+    /// The color the screen is cleared with behind the map, the sRGB channels range from 0 to 1.
+    /// This is [`None`] if the map uses the default [`ClearColor`].
+    #[serde(default)]
+    pub background_color: Option<[f32; 3]>,
+}
+
+fn default_gravity_scale() -> f32 {
+    1.
 }
 
+fn default_gravity_direction() -> Vec2 {
+    Vec2::NEG_Y
+}
+
+/// The gravity of the physics world with a gravity scale of 1, this matches rapier's default gravity with 100 pixels per meter.
+pub const BASE_GRAVITY: Vec2 = Vec2::new(0., -981.);
+
+/// The position the pawns are spawned at if the map doesn't have spawn points.
+pub const DEFAULT_SPAWN_POSITION: Vec2 = Vec2::new(0., 100.);
+
 impl MapInstance {
+    /// Returns the gravity pulling the pawns while this map is loaded, multiplied by the round's [`MatchModifiers`].
+    /// The gravity points straight down if the map's gravity direction is zero.
+    pub fn gravity(&self, modifiers: &MatchModifiers) -> Vec2 {
+        self.gravity_direction.normalize_or(Vec2::NEG_Y)
+            * BASE_GRAVITY.length()
+            * self.gravity_scale
+            * modifiers.gravity_scale
+    }
+
+    /// Returns the color the screen is cleared with while this map is loaded.
+    pub fn clear_color(&self) -> ClearColor {
+        self.background_color
+            .map(|[r, g, b]| ClearColor(Color::srgb(r, g, b)))
+            .unwrap_or_default()
+    }
+
+    /// Returns the spawn point farthest from the enemies, so that the respawned pawns aren't spawned into a fight.
+    /// A random spawn point is returned if none of the spawn points are at least `safe_distance` away from the enemies.
+    pub fn safe_spawn_point(
+        &self,
+        enemies: &[Vec2],
+        safe_distance: f32,
+        rand: &mut SmallRng,
+    ) -> Vec2 {
+        let distance_from_enemies = |spawn_point: &Vec2| {
+            enemies
+                .iter()
+                .map(|enemy| enemy.distance(*spawn_point))
+                .fold(f32::INFINITY, f32::min)
+        };
+
+        let safest_spawn_point = self
+            .spawn_points
+            .iter()
+            .filter(|spawn_point| distance_from_enemies(spawn_point) >= safe_distance)
+            .max_by(|lhs, rhs| distance_from_enemies(lhs).total_cmp(&distance_from_enemies(rhs)));
+
+        // Without enemies every spawn point is equally safe
+        if let (Some(spawn_point), false) = (safest_spawn_point, enemies.is_empty()) {
+            return *spawn_point;
+        }
+
+        self.spawn_points
+            .iter()
+            .choose(rand)
+            .copied()
+            .unwrap_or(DEFAULT_SPAWN_POSITION)
+    }
+
     pub fn map_flatground() -> Self {
         let mut map_objects: Vec<MapObject> = vec![];
 
@@ -118,14 +222,25 @@ impl MapInstance {
         });
 
         Self {
+            name: MapNameDiscriminants::FlatGround.to_string(),
             objects: map_objects,
+            gravity_scale: default_gravity_scale(),
+            gravity_direction: default_gravity_direction(),
+            hill: Some(Rect::from_center_size(vec2(0., -120.), vec2(200., 100.))),
+            music: Some(String::from("flatground")),
+            spawn_points: vec![vec2(-200., -100.), vec2(0., -100.), vec2(200., -100.)],
+            background_color: None,
         }
     }
 
     pub fn map_islands() -> Self {
         let mut map_objects: Vec<MapObject> = vec![];
+        let mut spawn_points: Vec<Vec2> = vec![];
 
         for position in (-400..400).step_by(150) {
+            // Every island has a spawn point above it
+            spawn_points.push(vec2(position as f32, -100.));
+
             map_objects.push(MapObject {
                 id: Uuid::new_v4(),
                 size: vec2(40., 10.),
@@ -136,7 +251,14 @@ impl MapInstance {
         }
 
         Self {
+            name: MapNameDiscriminants::Islands.to_string(),
             objects: map_objects,
+            gravity_scale: default_gravity_scale(),
+            gravity_direction: default_gravity_direction(),
+            hill: None,
+            music: Some(String::from("islands")),
+            spawn_points,
+            background_color: None,
         }
     }
 
@@ -188,7 +310,14 @@ impl MapInstance {
         });
 
         Self {
+            name: String::from("Test"),
             objects: map_objects,
+            gravity_scale: default_gravity_scale(),
+            gravity_direction: default_gravity_direction(),
+            hill: None,
+            music: None,
+            spawn_points: vec![],
+            background_color: None,
         }
     }
 }
@@ -228,39 +357,113 @@ impl MapNameDiscriminants {
     }
 }
 
+/// Sets the gravity of the physics world to the map's gravity, multiplied by the round's [`MatchModifiers`], see [`MapInstance::gravity`].
+/// The physics world has one gravity, so this is only used by the client which simulates its own arena. The server pulls every pawn with the gravity of its own arena's map instead.
+pub fn apply_map_gravity(
+    commands: &mut Commands,
+    map_instance: &MapInstance,
+    modifiers: &MatchModifiers,
+) {
+    let gravity = map_instance.gravity(modifiers);
+
+    commands.queue(move |world: &mut World| {
+        for mut rapier_configuration in world.query::<&mut RapierConfiguration>().iter_mut(world) {
+            rapier_configuration.gravity = gravity;
+        }
+    });
+}
+
 /// Loads entites in from a [`MapInstance`], this is used to load in maps provided by servers.
+/// A copy of the map is spawned for every arena in `arenas`, the copies only collide with the pawns of their own arena. The objects of the other arenas are kept.
+/// The map's gravity isn't applied, as the arenas can play on maps with different gravities, see [`apply_map_gravity`].
 pub fn load_map_from_mapinstance(
     map_instance: MapInstance,
     commands: &mut Commands,
     collision_groups: CollisionGroupSet,
-    current_game_objects: Query<(Entity, &MapElement, &mut Transform), Without<Pawn>>,
+    arenas: Range<usize>,
+    current_game_objects: &Query<(Entity, &MapElement, &mut Transform), Without<Pawn>>,
     // meshes: ResMut<Assets<Mesh>>,
     // materials: ResMut<Assets<ColorMaterial>>,
     // mut texture_atlas_layouts: ResMut<Assets<TextureAtlasLayout>>,
 ) {
-    // Delete all currently existing map objects.
-    for (entity, _game_object, _) in &current_game_objects {
-        commands.entity(entity).despawn();
+    // Delete the arenas' currently existing map objects.
+    for (entity, game_object, _) in current_game_objects.iter() {
+        if arenas.contains(&game_object.arena) {
+            commands.entity(entity).despawn();
+        }
     }
 
-    for object in map_instance.objects {
-        commands
-            .spawn(bevy_rapier2d::prelude::RigidBody::KinematicPositionBased)
-            .insert(Collider::cuboid(object.size.x, object.size.y))
-            .insert(Transform::from_xyz(
-                object.position.x,
-                object.position.y,
-                0.,
-            ))
-            .insert(ActiveEvents::COLLISION_EVENTS)
-            .insert(collision_groups.map_object)
-            .insert(Ccd::enabled())
-            .insert(Restitution::coefficient(0.))
-            .insert(Friction::coefficient(1.))
-            .insert(MapElement {
-                object_type: object.object_type,
-                id: object.id,
-                initial_position: Some(object.position),
-            });
+    // The cameras clear the screen with the map's background color
+    commands.insert_resource(map_instance.clear_color());
+
+    for arena in arenas {
+        let arena_collision_groups = collision_groups.for_arena(arena);
+
+        for object in map_instance.objects.iter().cloned() {
+            commands
+                .spawn(bevy_rapier2d::prelude::RigidBody::KinematicPositionBased)
+                .insert(Collider::cuboid(object.size.x, object.size.y))
+                .insert(Transform::from_xyz(
+                    object.position.x,
+                    object.position.y,
+                    0.,
+                ))
+                .insert(ActiveEvents::COLLISION_EVENTS)
+                .insert(arena_collision_groups.map_object)
+                .insert(Ccd::enabled())
+                .insert(Restitution::coefficient(0.))
+                .insert(Friction::coefficient(1.))
+                .insert(MapElement {
+                    object_type: object.object_type,
+                    id: object.id,
+                    initial_position: Some(object.position),
+                    arena,
+                });
+        }
+
+        // The hill is displayed behind the map, it is despawned with the map's objects
+        if let Some(hill) = map_instance.hill {
+            commands.spawn((
+                Sprite::from_color(Color::srgba(1., 1., 1., 0.15), hill.size()),
+                Transform::from_xyz(hill.center().x, hill.center().y, -1.),
+                HillZone { arena },
+                MapElement {
+                    object_type: ObjectType::Static,
+                    id: Uuid::new_v4(),
+                    initial_position: None,
+                    arena,
+                },
+            ));
+        }
     }
 }
+
+/// Returns the position the pawn should be moved to so that it doesn't overlap with any of the [`MapObject`]s, this is used when the map changes under the pawns.
+/// The pawn is pushed out along the axis it overlaps the least with the object. Returns [`None`] if the pawn doesn't overlap with any objects.
+pub fn push_out_of_map_objects(
+    map_instance: &MapInstance,
+    position: Vec2,
+    half_extents: Vec2,
+) -> Option<Vec2> {
+    let mut new_position = position;
+
+    for object in &map_instance.objects {
+        // The size of the objects is stored as half extents, just like the collider's
+        let delta = new_position - object.position;
+        let overlap = (object.size + half_extents) - delta.abs();
+
+        // Check if the pawn is inside the object
+        if overlap.x <= 0. || overlap.y <= 0. {
+            continue;
+        }
+
+        // Push the pawn out the shortest way, pawns exactly in the middle are pushed upwards
+        if overlap.x < overlap.y {
+            new_position.x += overlap.x * if delta.x < 0. { -1. } else { 1. };
+        } else {
+            new_position.y += overlap.y * if delta.y < 0. { -1. } else { 1. };
+        }
+    }
+
+    (new_position != position).then_some(new_position)
+}
@@ -1,4 +1,8 @@
 pub mod collision;
 pub mod combat;
+pub mod dummy;
+pub mod hill;
 pub mod map;
+pub mod modifiers;
 pub mod pawns;
+pub mod physics;
@@ -0,0 +1,76 @@
+use std::ops::RangeInclusive;
+
+use bevy::{
+    ecs::system::{Query, Res},
+    math::Vec3,
+    transform::components::Transform,
+};
+
+use crate::{networking::ServerGameState, server::ApplicationCtx};
+
+use super::pawns::Pawn;
+
+/// The scale of the pawns while the big heads modifier is enabled.
+pub const BIG_HEADS_SCALE: f32 = 1.5;
+
+/// The range of the gravity scale which keeps the pawns able to reach the map.
+pub const GRAVITY_SCALE_RANGE: RangeInclusive<f32> = 0.25..=1.;
+
+/// The lighthearted modifiers of a round, these are chosen by the server's operator and sent to the clients with the round's data.
+#[derive(Debug, Clone, Copy, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct MatchModifiers {
+    /// Whether the pawns are scaled up, this scales both their sprites and their colliders.
+    pub big_heads: bool,
+
+    /// The multiplier of the map's gravity, this is lower than 1 for low gravity rounds.
+    pub gravity_scale: f32,
+
+    /// Whether any hit knocks the attacked pawn out, as if it had fallen off the map.
+    pub one_hit_knockouts: bool,
+}
+
+impl Default for MatchModifiers {
+    fn default() -> Self {
+        Self {
+            big_heads: false,
+            gravity_scale: 1.,
+            one_hit_knockouts: false,
+        }
+    }
+}
+
+impl MatchModifiers {
+    /// Returns the scale of the pawns with these modifiers.
+    pub fn pawn_scale(&self) -> Vec3 {
+        if self.big_heads {
+            Vec3::splat(BIG_HEADS_SCALE)
+        } else {
+            Vec3::ONE
+        }
+    }
+}
+
+/// Scales the pawns according to their arena's current round's [`MatchModifiers`] and their own [`Pawn::scale`].
+/// The scale is sent to the clients with the pawns' transform, so the clients render the pawns scaled too.
+pub fn scale_pawns(app_ctx: Res<ApplicationCtx>, mut pawns: Query<(&Pawn, &mut Transform)>) {
+    let Some(server_instance) = &app_ctx.server_instance else {
+        return;
+    };
+
+    let game_states = server_instance.game_states.read();
+
+    for (pawn, mut transform) in pawns.iter_mut() {
+        let pawn_scale = match game_states.get(pawn.arena) {
+            Some(ServerGameState::OngoingGame(ongoing_game_data)) => {
+                ongoing_game_data.modifiers.pawn_scale() * pawn.scale
+            }
+            _ => continue,
+        };
+
+        // Only modify the transform if the scale has changed, so that the transform isn't marked as changed every frame
+        if transform.scale != pawn_scale {
+            transform.scale = pawn_scale;
+        }
+    }
+}
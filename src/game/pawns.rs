@@ -1,23 +1,168 @@
 use crate::{game::collision::CollisionGroupSet, networking::GameInput, Direction};
 use bevy::{
+    color::{Color, Hsla, Hue, Srgba},
     ecs::{component::Component, entity::Entity, system::Commands, world::Mut},
-    math::vec2,
-    time::Time,
+    math::{vec2, Vec2, Vec3},
+    time::{Time, Timer},
     transform::components::Transform,
 };
 use bevy_rapier2d::prelude::{
-    ActiveEvents, AdditionalMassProperties, Ccd, CharacterLength, Collider, CollisionGroups,
-    Friction, KinematicCharacterController, LockedAxes, RigidBody, Velocity,
+    ActiveEvents, AdditionalMassProperties, Ccd, CharacterLength, Collider, Friction, GravityScale,
+    KinematicCharacterController, LockedAxes, RigidBody, Velocity,
 };
 use rand::rngs::SmallRng;
-use std::time::Duration;
+use std::{ops::RangeInclusive, time::Duration};
 use uuid::Uuid;
 
 use super::{
     collision::LastInteractedPawn,
-    combat::{spawn_attack, Combo, Effect, EffectType},
+    combat::{
+        spawn_attack, spawn_projectile, AttackType, Combo, Effect, EffectType, RecentHits,
+        MAX_CHARGE_DURATION,
+    },
+    map::BASE_GRAVITY,
 };
 
+/// The half extents of the pawns' collider, before being scaled by the pawn's transform.
+pub const PAWN_HALF_EXTENTS: Vec2 = Vec2::new(20., 30.);
+
+/// The scale the pawns are spawned with, unless they are spawned with a different one.
+pub const DEFAULT_PAWN_SCALE: f32 = 1.;
+
+/// The range of the pawns' scale the server's operator can pick from.
+pub const PAWN_SCALE_RANGE: RangeInclusive<f32> = 0.5..=2.;
+
+/// The speed the pawns walk with in pixels per second, if they aren't slowed down.
+pub const PAWN_MOVEMENT_SPEED: f32 = 450.;
+
+/// The time the pawns can't be knocked back for after they have been spawned, so that they can't be spawn-killed.
+pub const SPAWN_PROTECTION_DURATION: Duration = Duration::from_millis(1500);
+
+/// The time a hit counts towards an assist for, if the hit pawn is knocked out within this time the attacker is awarded an assist.
+pub const ASSIST_WINDOW: Duration = Duration::from_secs(5);
+
+/// The amount of recent attackers remembered by a pawn, see [`Pawn::recent_attackers`].
+pub const MAX_RECENT_ATTACKERS: usize = 4;
+
+/// The amount of jumps the pawns get back when they land on the map.
+pub const MAX_JUMPS: u8 = 2;
+
+/// The default multiplier of the rising speed when the jump key is released early.
+pub const DEFAULT_JUMP_CUT_MULTIPLIER: f32 = 0.5;
+
+/// The largest difference between the channels of two tints for which the tints are too similar to tell the pawns apart.
+pub const TINT_SIMILARITY_THRESHOLD: u8 = 40;
+
+/// The angle in degrees a tint's hue is rotated by when it is too similar to another player's tint.
+pub const TINT_NUDGE_DEGREES: f32 = 30.;
+
+/// Returns whether the two tints are too similar to tell the pawns apart.
+pub fn tints_similar(lhs: [u8; 3], rhs: [u8; 3]) -> bool {
+    lhs.iter()
+        .zip(rhs)
+        .all(|(lhs, rhs)| lhs.abs_diff(rhs) <= TINT_SIMILARITY_THRESHOLD)
+}
+
+/// Nudges the tint's hue until it can be told apart from the tints already picked by the other players.
+/// The tint is returned unchanged if no such hue is found, ie. if the tint is gray and has no hue to rotate.
+pub fn distinct_tint(tint: [u8; 3], taken_tints: &[[u8; 3]]) -> [u8; 3] {
+    let is_taken = |tint: [u8; 3]| taken_tints.iter().any(|taken| tints_similar(tint, *taken));
+
+    if !is_taken(tint) {
+        return tint;
+    }
+
+    let hsla = Hsla::from(Srgba::rgb_u8(tint[0], tint[1], tint[2]));
+
+    (1..(360. / TINT_NUDGE_DEGREES) as usize)
+        .map(|step| {
+            let [r, g, b, _] =
+                Srgba::from(hsla.rotate_hue(step as f32 * TINT_NUDGE_DEGREES)).to_u8_array();
+
+            [r, g, b]
+        })
+        .find(|nudged_tint| !is_taken(*nudged_tint))
+        .unwrap_or(tint)
+}
+
+/// The movement inputs held by a player, the server applies the held inputs every tick until they are released.
+/// The clients only send the changes of the held inputs and a periodic keep-alive, instead of sending the held inputs every tick.
+#[derive(Clone, Copy, Default, Debug, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub struct HeldMovement {
+    pub left: bool,
+    pub right: bool,
+    pub duck: bool,
+}
+
+impl HeldMovement {
+    /// Updates the held inputs with the input, the inputs which aren't movement inputs are ignored.
+    pub fn update(&mut self, game_input: GameInput) {
+        match game_input {
+            GameInput::MoveLeft => self.left = true,
+            GameInput::MoveLeftRelease => self.left = false,
+            GameInput::MoveRight => self.right = true,
+            GameInput::MoveRightRelease => self.right = false,
+            GameInput::MoveDuck => self.duck = true,
+            GameInput::MoveDuckRelease => self.duck = false,
+            _ => (),
+        }
+    }
+
+    /// Returns whether the input presses or releases a movement input.
+    pub fn is_movement_input(game_input: GameInput) -> bool {
+        matches!(
+            game_input,
+            GameInput::MoveLeft
+                | GameInput::MoveLeftRelease
+                | GameInput::MoveRight
+                | GameInput::MoveRightRelease
+                | GameInput::MoveDuck
+                | GameInput::MoveDuckRelease
+        )
+    }
+
+    /// Returns the inputs being held.
+    pub fn held_inputs(&self) -> Vec<GameInput> {
+        [
+            (self.right, GameInput::MoveRight),
+            (self.left, GameInput::MoveLeft),
+            (self.duck, GameInput::MoveDuck),
+        ]
+        .into_iter()
+        .filter_map(|(held, game_input)| held.then_some(game_input))
+        .collect()
+    }
+
+    /// Returns the presses and releases which change the `previous` held inputs to these.
+    /// The state of every input is returned if `previous` is [`None`].
+    pub fn inputs_since(&self, previous: Option<Self>) -> Vec<GameInput> {
+        [
+            (
+                self.right,
+                previous.map(|previous| previous.right),
+                GameInput::MoveRight,
+                GameInput::MoveRightRelease,
+            ),
+            (
+                self.left,
+                previous.map(|previous| previous.left),
+                GameInput::MoveLeft,
+                GameInput::MoveLeftRelease,
+            ),
+            (
+                self.duck,
+                previous.map(|previous| previous.duck),
+                GameInput::MoveDuck,
+                GameInput::MoveDuckRelease,
+            ),
+        ]
+        .into_iter()
+        .filter(|(held, previously_held, _, _)| Some(*held) != *previously_held)
+        .map(|(held, _, press, release)| if held { press } else { release })
+        .collect()
+    }
+}
+
 /// This function modifies the direction variable of the `LocalPlayer`, the variable is always the key last pressed by the user.
 pub fn set_movement_direction_var(game_input: &GameInput, local_player: &mut Mut<'_, Pawn>) {
     if *game_input == GameInput::MoveRight {
@@ -37,6 +182,8 @@ pub fn set_movement_direction_var(game_input: &GameInput, local_player: &mut Mut
 }
 
 /// Handles the local player's input and modifying the controller of the Entity according to the input given.
+/// The pawns jump against the `gravity` of the physics world.
+/// The jump's velocity is scaled with the strength of the gravity, so that the pawns jump as high on every map and only the airtime changes.
 pub fn player_movement(
     commands: &mut Commands<'_, '_>,
     game_input: &GameInput,
@@ -44,14 +191,9 @@ pub fn player_movement(
     entity: Entity,
     player: &mut Mut<'_, Pawn>,
     controller: &mut KinematicCharacterController,
+    gravity: Vec2,
 ) {
-    let move_factor = 450. * {
-        if player.has_effect(EffectType::Slowdown) {
-            0.5
-        } else {
-            1.
-        }
-    };
+    let move_factor = player.movement_speed();
 
     if *game_input == GameInput::MoveLeft {
         // Move the local player to the left
@@ -66,16 +208,121 @@ pub fn player_movement(
     // If the user presses W we the entity should jump, and subtract 1 from the jumps_remaining counter.
     // If there are no more jumps remaining the user needs to wait until they touch a MapObject again. This indicates they've landed.
     if *game_input == GameInput::MoveJump && player.jumps_remaining != 0 {
+        let gravity_scale = gravity.length() / BASE_GRAVITY.length();
+
+        // Jump in the opposite direction of the gravity
+        let jump_direction = -gravity.normalize_or(Vec2::NEG_Y);
+
         commands.entity(entity).insert(Velocity {
-            linvel: vec2(0., 500.),
+            linvel: jump_direction * 500. * gravity_scale.sqrt(),
             angvel: 0.5,
         });
 
         player.jumps_remaining -= 1;
+        player.jump_rising = true;
     }
 }
 
-/// Handles the local player's attack
+/// Shortens the pawn's jump if the jump key has been released while the pawn is still rising, so that tapping the key results in a smaller hop.
+/// The pawn's speed against the gravity is multiplied by `jump_cut_multiplier`, see [`cut_jump_velocity`].
+pub fn cut_jump(
+    commands: &mut Commands<'_, '_>,
+    entity: Entity,
+    player: &mut Pawn,
+    velocity: &Velocity,
+    gravity: Vec2,
+    jump_cut_multiplier: f32,
+) {
+    let cut_velocity = cut_jump_velocity(
+        velocity.linvel,
+        gravity,
+        jump_cut_multiplier,
+        player.jump_rising,
+    );
+
+    // A jump can only be cut once
+    player.jump_rising = false;
+
+    if let Some(linvel) = cut_velocity {
+        commands.entity(entity).insert(Velocity {
+            linvel,
+            angvel: velocity.angvel,
+        });
+    }
+}
+
+/// Returns the velocity of a pawn whose jump has been cut, this is [`None`] if the jump can't be cut.
+/// Only the pawn's own rising jumps are cut, so releasing the jump key doesn't shorten the launches of the hits the pawn has taken.
+pub fn cut_jump_velocity(
+    linvel: Vec2,
+    gravity: Vec2,
+    jump_cut_multiplier: f32,
+    jump_rising: bool,
+) -> Option<Vec2> {
+    if !jump_rising {
+        return None;
+    }
+
+    let up = -gravity.normalize_or(Vec2::NEG_Y);
+
+    let rising_speed = linvel.dot(up);
+
+    // The pawn has already reached the peak of its jump
+    if rising_speed <= 0. {
+        return None;
+    }
+
+    Some(linvel - up * rising_speed * (1. - jump_cut_multiplier))
+}
+
+/// Applies the movement inputs held by the pawn's player, see [`HeldMovement`]. This is called once every tick.
+/// The pawn stays in place while it is stunned, but its held inputs are kept.
+pub fn apply_held_movement(
+    query: &mut (
+        Entity,
+        Mut<Pawn>,
+        Mut<KinematicCharacterController>,
+        &Transform,
+        &Velocity,
+    ),
+    commands: &mut Commands,
+    time: &Time,
+    gravity: Vec2,
+) {
+    let (entity, ref mut player, controller, _, _) = query;
+
+    if player.has_effect(EffectType::Stunned) {
+        return;
+    }
+
+    for game_input in player.held_movement.held_inputs() {
+        player_movement(
+            commands,
+            &game_input,
+            time,
+            *entity,
+            player,
+            controller,
+            gravity,
+        );
+
+        set_movement_direction_var(&game_input, player);
+
+        if game_input == GameInput::MoveDuck {
+            commands.entity(*entity).insert(Velocity {
+                linvel: vec2(0., -500.),
+                angvel: 0.5,
+            });
+
+            // Update latest direction
+            player.direction = Direction::Down;
+        }
+    }
+}
+
+/// Handles the local player's attack, the attack is ignored if the player's previous attack is still on cooldown.
+/// If `super_armor` is enabled, the player can't be knocked back during the attack's startup.
+#[allow(clippy::too_many_arguments)]
 pub fn player_attack(
     commands: &mut Commands,
     collision_groups: &CollisionGroupSet,
@@ -83,46 +330,71 @@ pub fn player_attack(
     entity: Entity,
     local_player: &mut Pawn,
     transform: &Transform,
+    attack_type: AttackType,
+    super_armor: bool,
 ) {
-    let (attack_collider_width, attack_collider_height) = (50., 50.);
-    let attack_collider = Collider::cuboid(attack_collider_width, attack_collider_height);
-
-    let attack_transform = match local_player.direction {
-        Direction::Left => Transform::from_xyz(
-            transform.translation.x - attack_collider_width,
-            transform.translation.y,
-            0.,
-        ),
-        Direction::Right => Transform::from_xyz(
-            transform.translation.x + attack_collider_width,
-            transform.translation.y,
-            0.,
-        ),
-        Direction::Up => Transform::from_xyz(
-            transform.translation.x,
-            transform.translation.y + attack_collider_height,
-            0.,
-        ),
-        Direction::Down => Transform::from_xyz(
-            transform.translation.x,
-            transform.translation.y - attack_collider_height,
-            0.,
-        ),
-    };
-
-    // Spawn in a cuboid and then caluclate the collisions from that
+    if local_player.attack_cooldown.is_some() {
+        return;
+    }
+
+    let attack_profile = attack_type.attack_profile();
+
+    local_player.attack_cooldown = Some(Timer::new(
+        attack_profile.cooldown,
+        bevy::time::TimerMode::Once,
+    ));
+
+    // The attacker commits to the attack, so it can't be knocked out of its startup
+    if super_armor && !attack_profile.startup.is_zero() {
+        local_player.super_armor = Some(Timer::new(
+            attack_profile.startup,
+            bevy::time::TimerMode::Once,
+        ));
+    }
+
+    // The attack's collider is scaled with the pawn, so bigger pawns reach further
+    let attack_offset = attack_profile
+        .shape
+        .offset(attack_profile.collider_size, local_player.direction)
+        * local_player.scale;
+
+    let attack_transform = Transform::from_xyz(
+        transform.translation.x + attack_offset.x,
+        transform.translation.y + attack_offset.y,
+        0.,
+    )
+    .with_scale(Vec3::splat(local_player.scale));
+
+    // Projectiles keep moving after they have been spawned, so they are simulated until they hit something
+    if let AttackType::Projectile(direction) = attack_type {
+        spawn_projectile(
+            commands,
+            &collision_groups.for_arena(local_player.arena),
+            rand,
+            entity,
+            transform,
+            direction,
+            local_player.arena,
+            attack_transform,
+        );
+
+        return;
+    }
+
+    // Spawn in the attack's collider and then caluclate the collisions from that
     spawn_attack(
         commands,
-        collision_groups,
+        &collision_groups.for_arena(local_player.arena),
         rand,
         entity,
-        local_player,
         transform,
-        attack_collider,
+        attack_type,
         attack_transform,
+        local_player.direction,
     );
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn handle_game_input(
     query: &mut (
         Entity,
@@ -136,31 +408,116 @@ pub fn handle_game_input(
     collision_groups: &CollisionGroupSet,
     rand: &mut SmallRng,
     time: &Time,
+    gravity: Vec2,
+    super_armor: bool,
+    jump_cut: Option<f32>,
 ) {
     // Unpack the tuple created by the tuple
-    let (entity, ref mut player, controller, transform, _) = query;
+    let (entity, ref mut player, controller, transform, velocity) = query;
 
-    if !player.has_effect(EffectType::Stunned) {
+    // The held movement inputs are applied every tick instead, see [`apply_held_movement`]
+    if !player.has_effect(EffectType::Stunned) && !HeldMovement::is_movement_input(game_input) {
         // Handle the movement of the LocalPlayer
-        player_movement(commands, &game_input, time, *entity, player, controller);
+        player_movement(
+            commands,
+            &game_input,
+            time,
+            *entity,
+            player,
+            controller,
+            gravity,
+        );
 
         // Set the variables for the LocalPlayer
         set_movement_direction_var(&game_input, player);
 
-        if game_input == GameInput::MoveDuck {
-            commands.entity(*entity).insert(Velocity {
-                linvel: vec2(0., -500.),
-                angvel: 0.5,
-            });
-
-            // Update latest direction
-            player.direction = Direction::Down;
+        if let (GameInput::MoveJumpRelease, Some(jump_cut_multiplier)) = (game_input, jump_cut) {
+            cut_jump(
+                commands,
+                *entity,
+                player,
+                velocity,
+                gravity,
+                jump_cut_multiplier,
+            );
         }
     }
 
+    // The held state is tracked even while stunned, so that a release isn't missed
+    match game_input {
+        GameInput::Defend => player.defending = true,
+        GameInput::DefendRelease => player.defending = false,
+        _ => player.held_movement.update(game_input),
+    }
+
     // if the player is attacking, handle the local player's attack
     if game_input == GameInput::Attack {
-        player_attack(commands, collision_groups, rand, *entity, player, transform);
+        // Light attacks push the attacked pawn vertically too if the player is facing up or down
+        let attack_type = match player.direction {
+            Direction::Up | Direction::Down => AttackType::Directional(player.direction),
+            Direction::Left | Direction::Right => AttackType::Quick,
+        };
+
+        player_attack(
+            commands,
+            collision_groups,
+            rand,
+            *entity,
+            player,
+            transform,
+            attack_type,
+            super_armor,
+        );
+    }
+
+    if game_input == GameInput::SuperAttack {
+        player_attack(
+            commands,
+            collision_groups,
+            rand,
+            *entity,
+            player,
+            transform,
+            AttackType::Super,
+            super_armor,
+        );
+    }
+
+    if game_input == GameInput::RangedAttack && player.pawn_type.has_ranged_attack() {
+        let direction = player.direction;
+
+        player_attack(
+            commands,
+            collision_groups,
+            rand,
+            *entity,
+            player,
+            transform,
+            AttackType::Projectile(direction),
+            super_armor,
+        );
+    }
+
+    // The charge is built up while the input is held, see [`crate::game::combat::tick_attack_cooldowns`]
+    if game_input == GameInput::ChargeAttack && player.attack_cooldown.is_none() {
+        player.charge = Some(Duration::ZERO);
+    }
+
+    if game_input == GameInput::ChargeAttackRelease && player.charge.is_some() {
+        let charge_level = player.charge_level();
+
+        player.charge = None;
+
+        player_attack(
+            commands,
+            collision_groups,
+            rand,
+            *entity,
+            player,
+            transform,
+            AttackType::Charged(charge_level),
+            super_armor,
+        );
     }
 
     // Increment effects
@@ -187,6 +544,49 @@ pub struct Pawn {
     pub pawn_attributes: PawnAttribute,
 
     pub pawn_type: PawnType,
+
+    /// The player slot of the client controlling this pawn, this decides the color the player is displayed with.
+    pub player_slot: usize,
+
+    /// The time left until the pawn can attack again, this is [`None`] if the pawn can attack.
+    pub attack_cooldown: Option<Timer>,
+
+    /// The time left until the pawn can be knocked back again, this is [`None`] if the pawn has no super armor.
+    /// The pawn still takes the hits' damage while it has super armor.
+    pub super_armor: Option<Timer>,
+
+    /// The arena the pawn is playing in, pawns only interact with the pawns and map objects of their own arena.
+    pub arena: usize,
+
+    /// Whether the player is holding the defend key, this is set by [`GameInput::Defend`] and cleared by [`GameInput::DefendRelease`].
+    pub defending: bool,
+
+    /// The time left until the pawn can be knocked back after spawning, this is [`None`] if the spawn protection has worn off.
+    pub spawn_protection: Option<Timer>,
+
+    /// The time the pawn has been charging its attack for, this is [`None`] if the pawn isn't charging.
+    /// The charge is cancelled if the pawn is hit.
+    pub charge: Option<Duration>,
+
+    /// The color the pawn's sprite is tinted with, this is picked by the player and is [`None`] if the sprite isn't tinted.
+    pub tint: Option<[u8; 3]>,
+
+    /// The movement inputs the player is holding, these are applied every tick by [`apply_held_movement`].
+    pub held_movement: HeldMovement,
+
+    /// The size of the pawn, this scales its sprite, its collider and the reach of its attacks.
+    /// Bigger pawns are heavier too, so they are knocked back less.
+    pub scale: f32,
+
+    /// Whether the pawn is rising from its own jump, only these jumps are shortened by the jump cut.
+    /// This is cleared when the pawn lands, gets hit or its jump is cut. Only the server keeps track of this, so it isn't sent to the clients.
+    #[serde(skip)]
+    pub jump_rising: bool,
+
+    /// The players who have recently hit the pawn and the time of their last hits, these are awarded assists if the pawn is knocked out.
+    /// Only the server keeps track of these, so they aren't sent to the clients.
+    #[serde(skip)]
+    pub recent_attackers: Vec<(Uuid, Duration)>,
 }
 
 impl Pawn {
@@ -213,9 +613,79 @@ impl Pawn {
         self.effects.iter().any(|effect| effect.effect_type == rhs)
     }
 
+    /// Returns the charge level of the pawn's attack in percent, this is 0 if the pawn isn't charging.
+    pub fn charge_level(&self) -> u8 {
+        let charge =
+            self.charge.unwrap_or_default().as_secs_f32() / MAX_CHARGE_DURATION.as_secs_f32();
+
+        (charge.min(1.) * 100.) as u8
+    }
+
+    /// Returns the speed the pawn walks with in pixels per second, the clients use this to move their own pawn before the server's update arrives.
+    pub fn movement_speed(&self) -> f32 {
+        PAWN_MOVEMENT_SPEED
+            * if self.has_effect(EffectType::Slowdown) {
+                0.5
+            } else {
+                1.
+            }
+    }
+
+    /// Returns the color the pawn's sprite is displayed with.
+    pub fn tint_color(&self) -> Color {
+        self.tint
+            .map(|[r, g, b]| Color::srgb_u8(r, g, b))
+            .unwrap_or(Color::WHITE)
+    }
+
+    /// Registers a hit by the attacker at the given time, only the last [`MAX_RECENT_ATTACKERS`] attackers are remembered.
+    pub fn register_attacker(&mut self, attacker: Uuid, now: Duration) {
+        self.recent_attackers
+            .retain(|(recent_attacker, _)| *recent_attacker != attacker);
+
+        self.recent_attackers.push((attacker, now));
+
+        if self.recent_attackers.len() > MAX_RECENT_ATTACKERS {
+            self.recent_attackers.remove(0);
+        }
+    }
+
+    /// Returns the players who have hit the pawn within [`ASSIST_WINDOW`], apart from the one who has knocked it out.
+    pub fn assisting_attackers(&self, killer: Option<Uuid>, now: Duration) -> Vec<Uuid> {
+        self.recent_attackers
+            .iter()
+            .filter(|(attacker, hit_at)| {
+                Some(*attacker) != killer && now.saturating_sub(*hit_at) <= ASSIST_WINDOW
+            })
+            .map(|(attacker, _)| *attacker)
+            .collect()
+    }
+
+    /// Handles the pawn being hit, the hit cancels the pawn's charge and its launch can't be shortened by the jump cut.
+    /// The pawn loses its own combo too if `hit_resets_combo` is enabled, so that the players lose their combo if they get punished.
+    pub fn take_hit(&mut self, hit_resets_combo: bool) {
+        self.charge = None;
+        self.jump_rising = false;
+
+        if hit_resets_combo {
+            self.combo_stats = None;
+        }
+    }
+
+    /// Returns the velocity the pawn is knocked back with by a hit's `launch`, this is [`None`] if the pawn can't be knocked back.
+    /// Pawns with super armor or spawn protection take the hit without being knocked back, and bigger pawns are heavier so they are launched less far.
+    pub fn knockback(&self, launch: Vec2) -> Option<Vec2> {
+        if self.super_armor.is_some() || self.spawn_protection.is_some() {
+            return None;
+        }
+
+        Some(launch / self.scale.max(f32::EPSILON))
+    }
+
     pub fn new_from_id(id: Uuid) -> Self {
         Self {
             uuid: id,
+            scale: DEFAULT_PAWN_SCALE,
             ..Default::default()
         }
     }
@@ -225,6 +695,8 @@ impl Pawn {
     Default,
     Clone,
     Copy,
+    PartialEq,
+    Eq,
     serde::Deserialize,
     serde::Serialize,
     Debug,
@@ -242,6 +714,11 @@ pub enum PawnType {
 }
 
 impl PawnType {
+    /// Returns whether the pawn type can use [`GameInput::RangedAttack`].
+    pub fn has_ranged_attack(&self) -> bool {
+        matches!(self, PawnType::Soldier | PawnType::Ninja)
+    }
+
     pub fn into_pawn_attribute(&self) -> PawnAttribute {
         match self {
             PawnType::Knight => PawnAttribute {
@@ -301,11 +778,27 @@ pub trait CustomAttack {
     fn spawn_attack(&self, commands: Commands);
 }
 
-pub fn spawn_pawn(commands: &mut Commands, uuid: Uuid, collision_group: CollisionGroups) {
+/// Spawns the pawn of a player at the position, the pawn has spawn protection for [`SPAWN_PROTECTION_DURATION`].
+/// The pawn's collider is scaled with its transform, see [`Pawn::scale`].
+/// Returns the entity of the spawned pawn.
+#[allow(clippy::too_many_arguments)]
+pub fn spawn_pawn(
+    commands: &mut Commands,
+    uuid: Uuid,
+    player_slot: usize,
+    tint: Option<[u8; 3]>,
+    pawn_type: PawnType,
+    arena: usize,
+    position: Vec2,
+    scale: f32,
+    collision_groups: &CollisionGroupSet,
+) -> Entity {
+    let collision_group = collision_groups.for_arena(arena).pawn;
+
     commands
         .spawn(RigidBody::Dynamic)
-        .insert(Collider::cuboid(20.0, 30.0))
-        .insert(Transform::from_xyz(0., 100., 0.))
+        .insert(Collider::cuboid(PAWN_HALF_EXTENTS.x, PAWN_HALF_EXTENTS.y))
+        .insert(Transform::from_translation(position.extend(0.)).with_scale(Vec3::splat(scale)))
         .insert(ActiveEvents::COLLISION_EVENTS)
         .insert(LockedAxes::ROTATION_LOCKED)
         .insert(AdditionalMassProperties::Mass(1.))
@@ -313,11 +806,128 @@ pub fn spawn_pawn(commands: &mut Commands, uuid: Uuid, collision_group: Collisio
         .insert(KinematicCharacterController {
             apply_impulse_to_dynamic_bodies: false,
             snap_to_ground: Some(CharacterLength::Relative(0.2)),
+            // The character controller should only collide with the objects of the pawn's arena
+            filter_groups: Some(collision_group),
             ..Default::default()
         })
         .insert(collision_group)
         .insert(Ccd::enabled())
         .insert(Velocity::default())
+        // The pawn is pulled by its arena's gravity instead of the physics world's, as the arenas can play on maps with different gravities
+        .insert(GravityScale(0.))
         .insert(LastInteractedPawn::default())
-        .insert(Pawn::new_from_id(uuid));
+        .insert(RecentHits::default())
+        .insert(Pawn {
+            player_slot,
+            tint,
+            pawn_type,
+            arena,
+            scale,
+            spawn_protection: Some(Timer::new(
+                SPAWN_PROTECTION_DURATION,
+                bevy::time::TimerMode::Once,
+            )),
+            ..Pawn::new_from_id(uuid)
+        })
+        .id()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pawn_with_combo(combo_counter: u32) -> Pawn {
+        Pawn {
+            combo_stats: Some(Combo {
+                combo_counter,
+                ..Combo::new(Duration::from_secs(2))
+            }),
+            ..Pawn::new_from_id(Uuid::new_v4())
+        }
+    }
+
+    #[test]
+    fn hit_resets_combo() {
+        let mut pawn = pawn_with_combo(3);
+
+        pawn.take_hit(true);
+
+        assert_eq!(
+            pawn.combo_stats
+                .map(|combo| combo.combo_counter)
+                .unwrap_or_default(),
+            0
+        );
+    }
+
+    #[test]
+    fn hit_keeps_combo_if_disabled() {
+        let mut pawn = pawn_with_combo(3);
+
+        pawn.take_hit(false);
+
+        assert_eq!(pawn.combo_stats.map(|combo| combo.combo_counter), Some(3));
+    }
+
+    /// Returns the height a pawn rises to with the velocity, before the gravity stops it.
+    fn peak_height(linvel: Vec2, gravity: Vec2) -> f32 {
+        linvel.y.max(0.).powi(2) / (2. * gravity.length())
+    }
+
+    #[test]
+    fn jump_cut_lowers_the_jumps_peak() {
+        let jump_velocity = vec2(0., 500.);
+
+        let cut_velocity = cut_jump_velocity(
+            jump_velocity,
+            BASE_GRAVITY,
+            DEFAULT_JUMP_CUT_MULTIPLIER,
+            true,
+        )
+        .unwrap();
+
+        assert!(peak_height(cut_velocity, BASE_GRAVITY) < peak_height(jump_velocity, BASE_GRAVITY));
+    }
+
+    #[test]
+    fn jump_cut_keeps_the_knockbacks_peak() {
+        let knockback_velocity = vec2(300., 800.);
+
+        let cut_velocity = cut_jump_velocity(
+            knockback_velocity,
+            BASE_GRAVITY,
+            DEFAULT_JUMP_CUT_MULTIPLIER,
+            false,
+        )
+        .unwrap_or(knockback_velocity);
+
+        assert_eq!(
+            peak_height(cut_velocity, BASE_GRAVITY),
+            peak_height(knockback_velocity, BASE_GRAVITY)
+        );
+    }
+
+    #[test]
+    fn armored_pawns_velocity_is_unchanged_by_a_hit() {
+        let pawn = Pawn {
+            super_armor: Some(Timer::new(
+                Duration::from_millis(200),
+                bevy::time::TimerMode::Once,
+            )),
+            ..Pawn::new_from_id(Uuid::new_v4())
+        };
+
+        let velocity = vec2(120., -40.);
+
+        let hit_velocity = velocity + pawn.knockback(vec2(400., 500.)).unwrap_or_default();
+
+        assert_eq!(hit_velocity, velocity);
+    }
+
+    #[test]
+    fn unarmored_pawn_is_knocked_back_by_a_hit() {
+        let pawn = Pawn::new_from_id(Uuid::new_v4());
+
+        assert_eq!(pawn.knockback(vec2(400., 500.)), Some(vec2(400., 500.)));
+    }
 }
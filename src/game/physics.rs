@@ -0,0 +1,74 @@
+use std::{num::NonZeroUsize, ops::RangeInclusive};
+
+use bevy::ecs::{system::Commands, world::World};
+use bevy_rapier2d::prelude::{RapierContextSimulation, TimestepMode};
+
+/// The range of the solver iterations which keep the simulation stable without wasting CPU time.
+pub const SOLVER_ITERATIONS_RANGE: RangeInclusive<usize> = 1..=16;
+
+/// The range of the substeps which keep the simulation stable without wasting CPU time.
+pub const SUBSTEPS_RANGE: RangeInclusive<usize> = 1..=8;
+
+/// The settings of the physics simulation, these let the operator trade CPU time for the simulation's stability.
+/// The server sends these to the clients when they connect, so that the local simulation doesn't diverge from the server's.
+#[derive(Debug, Clone, Copy, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct PhysicsSettings {
+    /// The amount of times the constraint solver runs per physics step.
+    /// Higher values reduce the jittering of pawns under stacked knockbacks, 4 is enough for most matches and values above 8 rarely make a difference.
+    pub solver_iterations: usize,
+
+    /// The amount of substeps a physics step is split into.
+    /// Higher values prevent fast pawns from tunneling through the map, 1 is enough for most matches and values above 4 are only needed with extreme knockbacks.
+    pub substeps: usize,
+}
+
+impl Default for PhysicsSettings {
+    fn default() -> Self {
+        Self {
+            solver_iterations: 4,
+            substeps: 1,
+        }
+    }
+}
+
+/// Applies the [`PhysicsSettings`] to the physics world, the values are clamped into their sensible ranges.
+pub fn apply_physics_settings(commands: &mut Commands, physics_settings: PhysicsSettings) {
+    let solver_iterations = NonZeroUsize::new(physics_settings.solver_iterations.clamp(
+        *SOLVER_ITERATIONS_RANGE.start(),
+        *SOLVER_ITERATIONS_RANGE.end(),
+    ))
+    .unwrap();
+
+    let substeps = physics_settings
+        .substeps
+        .clamp(*SUBSTEPS_RANGE.start(), *SUBSTEPS_RANGE.end());
+
+    commands.queue(move |world: &mut World| {
+        for mut rapier_simulation in world
+            .query::<&mut RapierContextSimulation>()
+            .iter_mut(world)
+        {
+            rapier_simulation
+                .integration_parameters
+                .num_solver_iterations = solver_iterations;
+        }
+
+        if let Some(mut timestep_mode) = world.get_resource_mut::<TimestepMode>() {
+            match &mut *timestep_mode {
+                TimestepMode::Fixed {
+                    substeps: timestep_substeps,
+                    ..
+                }
+                | TimestepMode::Variable {
+                    substeps: timestep_substeps,
+                    ..
+                }
+                | TimestepMode::Interpolated {
+                    substeps: timestep_substeps,
+                    ..
+                } => *timestep_substeps = substeps,
+            }
+        }
+    });
+}
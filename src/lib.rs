@@ -29,20 +29,400 @@ pub enum PauseWindowState {
     #[default]
     Main,
     Settings,
+    Mods,
+    /// Asks the user to confirm quitting the application.
+    QuitConfirmation,
+    /// Asks the user to confirm leaving the match, so that a misclick doesn't disconnect them.
+    LeaveConfirmation,
 }
 
 pub mod server {
+    use std::{
+        collections::HashMap,
+        path::Path,
+        time::{Duration, Instant},
+    };
 
+    use anyhow::Context;
     use bevy::{ecs::system::Resource, time::Timer};
+    use chrono::{DateTime, TimeDelta, Utc};
 
     use rand::{rngs::SmallRng, SeedableRng};
+    use strum::VariantArray;
     use tokio::sync::mpsc::{channel, Receiver};
     use tokio_util::sync::CancellationToken;
+    use uuid::Uuid;
 
-    use crate::{networking::server::ServerInstance, UiLayer};
+    use crate::{
+        game::{
+            collision::MAX_ARENAS,
+            combat::{CrowdKnockbackScaling, StaleHitFalloff},
+            hill::{ContestedHillScoring, HillState},
+            map::{MapInstance, MapNameDiscriminants},
+            modifiers::MatchModifiers,
+            pawns::{PawnType, DEFAULT_PAWN_SCALE, PAWN_SCALE_RANGE},
+            physics::PhysicsSettings,
+        },
+        networking::{
+            error::NetError,
+            metrics::MetricsSnapshot,
+            server::{ConnectionLimits, ServerInstance},
+            ClientStatistics,
+        },
+        UiLayer,
+    };
 
     #[derive(Default)]
-    pub struct UiState {}
+    pub struct UiState {
+        /// The map selected by the operator to be forced onto the ongoing round.
+        pub force_map: Option<MapNameDiscriminants>,
+
+        /// Whether the operator is asked to confirm stopping the server.
+        pub confirm_stop_server: bool,
+    }
+
+    /// The operator-tunable settings of the server.
+    #[derive(Debug, Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+    #[serde(default)]
+    pub struct Settings {
+        /// The minimum amount of connected players needed to start a round.
+        /// The round timer is frozen until enough players have joined.
+        pub min_players_to_start: usize,
+
+        /// The amount of lives every player starts the round with.
+        /// Players who have lost all of their lives are eliminated until the next round, this is [`None`] if the lives are unlimited.
+        pub stock_lives: Option<u32>,
+
+        /// The pawn types the players are allowed to pick.
+        pub allowed_pawn_types: Vec<PawnType>,
+
+        /// Whether a sudden death is played between the players who have tied for first when the round's time is up.
+        pub sudden_death: bool,
+
+        /// Whether the pawns can't be knocked back during their attacks' startup, so that trading hits favors the committed attacker.
+        pub super_armor: bool,
+
+        /// Whether getting hit resets the attacked pawn's own combo, so that the players lose their combo if they get punished.
+        pub hit_resets_combo: bool,
+
+        /// The falloff of the knockback when the same attacker hits the same pawn repeatedly.
+        pub stale_hit_falloff: StaleHitFalloff,
+
+        /// The scaling of the knockback when many pawns are fighting close to each other.
+        pub crowd_knockback_scaling: CrowdKnockbackScaling,
+
+        /// The port the server's metrics are served on over HTTP, this is [`None`] if the metrics endpoint is disabled.
+        pub metrics_endpoint_port: Option<u16>,
+
+        /// The settings of the physics simulation, these are sent to the clients so that their local simulation matches the server's.
+        pub physics: PhysicsSettings,
+
+        /// The amount of arenas hosted by the server, every arena is a separate match with its own players, rounds and vote.
+        /// The arenas share the leaderboard, and the joining players are assigned to the least full arena.
+        pub arena_count: usize,
+
+        /// The lighthearted modifiers of the rounds, changing these takes effect from the start of the next round.
+        pub modifiers: MatchModifiers,
+
+        /// Whether the players standing on the map's hill are awarded score, this is disabled by default.
+        pub hill_scoring: bool,
+
+        /// How the hill's score is awarded while multiple pawns are standing on the hill.
+        pub contested_hill_scoring: ContestedHillScoring,
+
+        /// The amount of maps offered in an intermission's vote, the maps are chosen randomly.
+        /// Every map is offered if there are no more maps than this.
+        pub maps_per_vote: usize,
+
+        /// Whether the map played in the last round is left out of the next vote, so that the same map isn't played twice in a row.
+        /// The map is kept if it is the only one.
+        pub exclude_last_played_map: bool,
+
+        /// The multiplier of the pawns' rising speed when the jump key is released early, this is [`None`] if the jumps always have the full height.
+        pub jump_cut: Option<f32>,
+
+        /// The distance the respawned pawns are kept from their enemies, a random spawn point is picked if no spawn point is this far away.
+        pub safe_spawn_distance: f32,
+
+        /// The scale the players' pawns are spawned with, see [`crate::game::pawns::Pawn::scale`].
+        pub pawn_scale: f32,
+
+        /// The time in seconds the training dummies are reset after once they have been launched, this is [`None`] if the dummies stay where they land.
+        pub dummy_reset_delay: Option<f32>,
+
+        /// Whether the players joining mid-round spectate until the next round, instead of joining the ongoing round.
+        pub late_joiners_spectate: bool,
+
+        /// The time in seconds the knocked out pawns are respawned after, this is [`None`] if the pawns are respawned immediately.
+        pub respawn_delay: Option<f32>,
+
+        /// The round trip time in milliseconds the players are kicked above, if their ping stays this high for [`HIGH_PING_KICK_GRACE_PERIOD`].
+        /// This is [`None`] if the players are never kicked for their ping.
+        pub high_ping_kick_threshold: Option<u32>,
+
+        /// Whether the ongoing match is saved to the [`MATCH_SNAPSHOT_FILE`] periodically, so that the match can be resumed after the server is restarted.
+        pub persist_match: bool,
+
+        /// The limits of the incoming connections, these protect the server from connection floods.
+        pub connection_limits: ConnectionLimits,
+    }
+
+    impl Default for Settings {
+        fn default() -> Self {
+            Self {
+                min_players_to_start: 1,
+                stock_lives: None,
+                allowed_pawn_types: PawnType::VARIANTS.to_vec(),
+                sudden_death: false,
+                super_armor: false,
+                hit_resets_combo: false,
+                stale_hit_falloff: StaleHitFalloff::default(),
+                crowd_knockback_scaling: CrowdKnockbackScaling::default(),
+                metrics_endpoint_port: None,
+                physics: PhysicsSettings::default(),
+                arena_count: 1,
+                modifiers: MatchModifiers::default(),
+                hill_scoring: false,
+                contested_hill_scoring: ContestedHillScoring::default(),
+                maps_per_vote: 4,
+                exclude_last_played_map: false,
+                jump_cut: None,
+                safe_spawn_distance: 200.,
+                pawn_scale: DEFAULT_PAWN_SCALE,
+                dummy_reset_delay: Some(2.),
+                late_joiners_spectate: false,
+                respawn_delay: None,
+                high_ping_kick_threshold: None,
+                persist_match: false,
+                connection_limits: ConnectionLimits::default(),
+            }
+        }
+    }
+
+    impl Settings {
+        /// Clamps the values into the ranges allowed by the server's UI, as the settings may have been edited by hand in the [`SERVER_CONFIG_FILE`].
+        pub fn validate(&mut self) {
+            self.min_players_to_start = self.min_players_to_start.clamp(1, 64);
+            self.stock_lives = self.stock_lives.map(|stock_lives| stock_lives.clamp(1, 99));
+            self.arena_count = self.arena_count.clamp(1, MAX_ARENAS);
+            self.maps_per_vote = self
+                .maps_per_vote
+                .clamp(1, MapNameDiscriminants::VARIANTS.len());
+            self.jump_cut = self.jump_cut.map(|jump_cut| jump_cut.clamp(0., 1.));
+            self.safe_spawn_distance = self.safe_spawn_distance.clamp(0., 1000.);
+            self.pawn_scale = self
+                .pawn_scale
+                .clamp(*PAWN_SCALE_RANGE.start(), *PAWN_SCALE_RANGE.end());
+            self.dummy_reset_delay = self
+                .dummy_reset_delay
+                .map(|dummy_reset_delay| dummy_reset_delay.clamp(0., 10.));
+            self.respawn_delay = self
+                .respawn_delay
+                .map(|respawn_delay| respawn_delay.clamp(0., 10.));
+            self.high_ping_kick_threshold = self
+                .high_ping_kick_threshold
+                .map(|high_ping_kick_threshold| high_ping_kick_threshold.clamp(100, 2000));
+            self.connection_limits.max_pending_handshakes =
+                self.connection_limits.max_pending_handshakes.clamp(1, 1024);
+            self.connection_limits.connections_per_minute =
+                self.connection_limits.connections_per_minute.clamp(1, 600);
+
+            // The players couldn't pick any pawn type without an allowed one
+            if self.allowed_pawn_types.is_empty() {
+                self.allowed_pawn_types = PawnType::VARIANTS.to_vec();
+            }
+        }
+    }
+
+    /// The file the [`ServerConfig`] is saved to, relative to the server's working directory.
+    pub const SERVER_CONFIG_FILE: &str = "server_config.ron";
+
+    /// The operator's configuration, this is kept between the launches of the server.
+    #[derive(Debug, Clone, PartialEq, Default, serde::Deserialize, serde::Serialize)]
+    #[serde(default)]
+    pub struct ServerConfig {
+        pub settings: Settings,
+
+        /// The password the clients have to enter to connect, see [`ApplicationCtx::password`].
+        pub password: Option<String>,
+    }
+
+    impl ServerConfig {
+        /// Loads the configuration from the file, the missing fields are filled in with their defaults and the invalid values are clamped.
+        /// Returns the default configuration if the file doesn't exist, ie. when the server is launched for the first time.
+        pub fn load(path: &Path) -> anyhow::Result<Self> {
+            let config = match std::fs::read_to_string(path) {
+                Ok(config) => config,
+                Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+                    return Ok(Self::default());
+                }
+                Err(err) => return Err(err.into()),
+            };
+
+            let mut config: Self = ron::from_str(&config)
+                .with_context(|| format!("Malformed config: {}", path.display()))?;
+
+            config.settings.validate();
+
+            Ok(config)
+        }
+
+        /// Writes the configuration to the file.
+        pub fn save(&self, path: &Path) -> anyhow::Result<()> {
+            std::fs::write(
+                path,
+                ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())?,
+            )?;
+
+            Ok(())
+        }
+    }
+
+    /// The file the [`MatchSnapshot`] is saved to, relative to the server's working directory.
+    pub const MATCH_SNAPSHOT_FILE: &str = "match_snapshot.ron";
+
+    /// The interval in seconds the [`MatchSnapshot`] is saved in, if [`Settings::persist_match`] is enabled.
+    pub const MATCH_SNAPSHOT_INTERVAL_SECS: f32 = 10.;
+
+    /// The age in minutes after which a [`MatchSnapshot`] is considered stale, the stale snapshots are ignored at startup.
+    pub const MATCH_SNAPSHOT_MAX_AGE_MINS: i64 = 10;
+
+    /// The time a player's ping has to stay above the [`Settings::high_ping_kick_threshold`] for the player to be kicked, so that a single lag spike doesn't get anyone kicked.
+    pub const HIGH_PING_KICK_GRACE_PERIOD: Duration = Duration::from_secs(30);
+
+    /// The state of an ongoing match, this is saved periodically so that the match can be resumed after the server is restarted.
+    /// The snapshot holds the round of every arena, and the standings of every arena's players.
+    #[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
+    pub struct MatchSnapshot {
+        /// The date the snapshot has been saved at.
+        pub saved_at: DateTime<Utc>,
+
+        /// The rounds of the arenas indexed by the arena, this is [`None`] for the arenas which weren't playing a round.
+        pub arenas: Vec<Option<ArenaSnapshot>>,
+
+        /// The standings of the players keyed by their persistent identity, these are restored when the players reconnect.
+        pub client_stats: Vec<(Uuid, ClientStatistics)>,
+    }
+
+    impl MatchSnapshot {
+        /// Loads the snapshot from the file.
+        /// Returns [`None`] if there is no snapshot, or if it is older than [`MATCH_SNAPSHOT_MAX_AGE_MINS`].
+        pub fn load(path: &Path) -> anyhow::Result<Option<Self>> {
+            let snapshot = match std::fs::read_to_string(path) {
+                Ok(snapshot) => snapshot,
+                Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+                    return Ok(None);
+                }
+                Err(err) => return Err(err.into()),
+            };
+
+            let snapshot: Self = ron::from_str(&snapshot)
+                .with_context(|| format!("Malformed match snapshot: {}", path.display()))?;
+
+            let is_stale = Utc::now().signed_duration_since(snapshot.saved_at)
+                > TimeDelta::minutes(MATCH_SNAPSHOT_MAX_AGE_MINS);
+
+            Ok((!is_stale).then_some(snapshot))
+        }
+
+        /// Writes the snapshot to the file.
+        pub fn save(&self, path: &Path) -> anyhow::Result<()> {
+            std::fs::write(
+                path,
+                ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())?,
+            )?;
+
+            Ok(())
+        }
+
+        /// Deletes the snapshot's file, so that a finished match isn't resumed. It isn't an error if there is no snapshot.
+        pub fn delete(path: &Path) -> anyhow::Result<()> {
+            match std::fs::remove_file(path) {
+                Err(err) if err.kind() != std::io::ErrorKind::NotFound => Err(err.into()),
+                _ => Ok(()),
+            }
+        }
+
+        /// Takes the standings of the player with the identity, so that they are only restored once.
+        pub fn take_client_stats(&mut self, identity: Uuid) -> Option<ClientStatistics> {
+            let index = self
+                .client_stats
+                .iter()
+                .position(|(stats_identity, _)| *stats_identity == identity)?;
+
+            Some(self.client_stats.remove(index).1)
+        }
+
+        /// Returns the saved round of the arena, this is [`None`] if the arena wasn't playing a round.
+        pub fn arena_mut(&mut self, arena: usize) -> Option<&mut ArenaSnapshot> {
+            self.arenas.get_mut(arena)?.as_mut()
+        }
+    }
+
+    /// The round of an arena saved in a [`MatchSnapshot`].
+    #[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
+    pub struct ArenaSnapshot {
+        /// The map the arena's round is played on.
+        pub current_map: MapInstance,
+
+        /// The modifiers of the arena's round.
+        pub modifiers: MatchModifiers,
+
+        /// The time left from the round, this is [`None`] if the round was waiting for players.
+        pub round_time_left: Option<Duration>,
+    }
+
+    /// The state of the sudden death, which is played between the players who have tied for first when the round's time is up.
+    pub struct SuddenDeath {
+        /// The players competing in the sudden death.
+        pub players: Vec<Uuid>,
+
+        /// The players spectating the sudden death, their pawns are respawned when the sudden death ends.
+        pub spectators: Vec<Uuid>,
+
+        /// The round ends without a winner if this timer finishes.
+        pub timer: Timer,
+
+        /// Whether one of the competing players has fallen off, this ends the sudden death.
+        pub has_ended: bool,
+    }
+
+    impl SuddenDeath {
+        pub fn new(players: Vec<Uuid>, spectators: Vec<Uuid>, duration: Duration) -> Self {
+            Self {
+                players,
+                spectators,
+                timer: Timer::new(duration, bevy::time::TimerMode::Once),
+                has_ended: false,
+            }
+        }
+    }
+
+    /// The state of an arena's rounds, every arena plays its own rounds with their own timers and map vote.
+    /// The arena's [`crate::networking::ServerGameState`] is stored in [`ServerInstance::game_states`], as it is shared with the connection handlers.
+    #[derive(Default)]
+    pub struct ArenaRound {
+        pub intermission_timer: Option<Timer>,
+
+        pub intermission_total_votes: usize,
+
+        pub game_round_timer: Option<Timer>,
+
+        /// Whether the arena is waiting for enough players to join to start the round.
+        pub waiting_for_players: bool,
+
+        /// The amount of the arena's players the clients have last been notified about while waiting for players.
+        pub announced_player_count: Option<usize>,
+
+        /// The players who have lost all of their lives in the current round, in the order they have been eliminated.
+        pub elimination_order: Vec<Uuid>,
+
+        /// The ongoing sudden death, this is [`None`] if there is no sudden death being played.
+        pub sudden_death: Option<SuddenDeath>,
+
+        /// The map won by the last intermission's vote, this is [`None`] if no vote has finished yet.
+        pub last_played_map: Option<MapNameDiscriminants>,
+    }
 
     #[derive(Resource)]
     pub struct ApplicationCtx {
@@ -55,7 +435,7 @@ pub mod server {
         /// Please note, that the [`SmallRng`] is insecure and should not be used in crypto contexts.
         pub rand: rand::rngs::SmallRng,
 
-        pub server_instance_receiver: Receiver<anyhow::Result<ServerInstance>>,
+        pub server_instance_receiver: Receiver<Result<ServerInstance, NetError>>,
 
         pub server_instance: Option<ServerInstance>,
 
@@ -63,12 +443,74 @@ pub mod server {
 
         pub tick_count: u64,
 
-        pub intermission_timer: Option<Timer>,
+        /// The rounds of the server's arenas indexed by the arena, these are created when the server is started.
+        pub arenas: Vec<ArenaRound>,
 
-        pub intermission_total_votes: usize,
+        /// The pawn type chosen by the clients, this is used to respawn the clients' pawns with their chosen pawn type.
+        pub pawn_types: HashMap<Uuid, PawnType>,
 
-        pub game_round_timer: Option<Timer>,
-        // pub pawn_types: Arc<DashMap<Uuid, PawnType>>
+        /// The date of the last ping sent by the clients, this is used to rate-limit the pings.
+        pub last_ping_dates: HashMap<Uuid, DateTime<Utc>>,
+
+        /// The date of the last pawn type change of the clients, this is used to rate-limit the pawn type changes.
+        pub last_pawn_type_change_dates: HashMap<Uuid, DateTime<Utc>>,
+
+        pub settings: Settings,
+
+        /// The map which should replace the current map of every arena without resetting their rounds, this is taken by the server's systems.
+        pub pending_map_change: Option<MapInstance>,
+
+        /// The server's metrics are sampled every time this timer finishes.
+        pub metrics_sample_timer: Timer,
+
+        /// The value of the metrics' counters at the last sample.
+        pub last_metrics_snapshot: MetricsSnapshot,
+
+        /// The time the ongoing fixed tick has started at, this is set by the tick watchdog.
+        pub tick_started_at: Option<Instant>,
+
+        /// The time the last tick overrun has been logged at, the warnings are rate limited so that an overloaded server doesn't flood the log.
+        pub last_tick_overrun_warning: Option<Instant>,
+
+        /// The time the players' ping has risen above the [`Settings::high_ping_kick_threshold`] at, the players are kicked if it doesn't come down in time.
+        pub high_ping_since: HashMap<Uuid, Instant>,
+
+        /// The password the clients have to enter to connect, this is [`None`] if the server isn't password protected.
+        pub password: Option<String>,
+
+        /// The state of the hill in every arena, as the players of the arenas have last been notified about.
+        pub hill_states: HashMap<usize, HillState>,
+
+        /// The fraction of a point the players have accumulated on the hill, the whole points are added to the players' score.
+        pub hill_score_progress: HashMap<Uuid, f32>,
+
+        /// The match loaded from the [`MATCH_SNAPSHOT_FILE`] at startup, this is resumed in the first arena when the server is started.
+        /// The standings left in the snapshot are waiting for their players to reconnect, until the first arena's round ends.
+        pub restored_match: Option<MatchSnapshot>,
+    }
+
+    impl ApplicationCtx {
+        /// Returns the operator's current configuration.
+        pub fn config(&self) -> ServerConfig {
+            ServerConfig {
+                settings: self.settings.clone(),
+                password: self.password.clone(),
+            }
+        }
+
+        /// Applies the configuration loaded from the [`SERVER_CONFIG_FILE`].
+        pub fn apply_config(&mut self, config: ServerConfig) {
+            self.settings = config.settings;
+            self.password = config.password;
+        }
+
+        /// Forgets the state kept about the client who has left, so that it isn't kept for every client who has ever connected.
+        /// A reconnecting client connects with a new uuid, so its chosen pawn type isn't needed anymore either.
+        pub fn forget_player_state(&mut self, uuid: &Uuid) {
+            self.last_ping_dates.remove(uuid);
+            self.last_pawn_type_change_dates.remove(uuid);
+            self.pawn_types.remove(uuid);
+        }
     }
 
     impl Default for ApplicationCtx {
@@ -81,36 +523,94 @@ pub mod server {
                 server_instance: None,
                 cancellation_token: CancellationToken::new(),
                 tick_count: 0,
-                intermission_timer: None,
-                game_round_timer: None,
-                intermission_total_votes: 0,
+                arenas: Vec::new(),
+                pawn_types: HashMap::new(),
+                last_ping_dates: HashMap::new(),
+                last_pawn_type_change_dates: HashMap::new(),
+                settings: Settings::default(),
+                pending_map_change: None,
+                metrics_sample_timer: Timer::new(
+                    Duration::from_secs(1),
+                    bevy::time::TimerMode::Repeating,
+                ),
+                last_metrics_snapshot: MetricsSnapshot::default(),
+                tick_started_at: None,
+                last_tick_overrun_warning: None,
+                high_ping_since: HashMap::new(),
+                password: None,
+                hill_states: HashMap::new(),
+                hill_score_progress: HashMap::new(),
+                restored_match: None,
             }
         }
     }
 }
 
 pub mod client {
-    use std::path::PathBuf;
+    use std::{
+        collections::{HashMap, HashSet},
+        path::{Path, PathBuf},
+        time::{Duration, Instant},
+    };
 
-    use bevy_egui::egui::Rect;
+    use anyhow::Context;
+    use bevy_egui::egui::{Color32, Rect};
     use tokio::sync::mpsc::Sender;
 
-    use bevy::{asset::Handle, ecs::system::Resource, sprite::TextureAtlasLayout};
+    use bevy::{
+        asset::Handle, ecs::system::Resource, input::keyboard::KeyCode, math::Vec2,
+        sprite::TextureAtlasLayout, window::PresentMode,
+    };
 
     use egui_toast::Toasts;
 
     use rand::{rngs::SmallRng, SeedableRng};
     use tokio::sync::mpsc::{channel, Receiver};
     use tokio_util::sync::CancellationToken;
+    use uuid::Uuid;
 
-    use crate::{networking::client::ClientConnection, UiLayer};
+    use crate::{
+        game::{hill::HillState, map::MapNameDiscriminants},
+        networking::{client::ClientConnection, error::NetError, ClientStatistics, GameInput},
+        UiLayer,
+    };
 
     #[derive(serde::Serialize, serde::Deserialize)]
+    #[serde(default)]
     pub struct UiState {
         pub connect_to_address: String,
         pub leaderboard_rect: Rect,
         pub current_resource_pack: Option<PathBuf>,
         pub username_buffer: String,
+
+        /// The resource packs found in the [`MODS_DIRECTORY`], with the error of the packs which failed to load.
+        #[serde(skip)]
+        pub resource_packs: Vec<(PathBuf, Result<ResourcePack, String>)>,
+
+        /// The page of the intermission's map carousel currently displayed.
+        #[serde(skip)]
+        pub intermission_page: usize,
+
+        /// The password entered for the server, this is never saved.
+        #[serde(skip)]
+        pub password_buffer: String,
+
+        /// The players whose pings are hidden locally, the server isn't notified about the mutes.
+        pub muted_players: HashSet<Uuid>,
+
+        /// The address of the last server the client has successfully connected to, this is [`None`] if the client has never connected to a server.
+        pub last_server_address: Option<String>,
+
+        /// Whether the scoreboard has been toggled open, this is only used if the [`ScoreboardMode`] is [`ScoreboardMode::Toggle`].
+        #[serde(skip)]
+        pub scoreboard_open: bool,
+
+        /// The action whose key is being rebound, the next pressed key is bound to it.
+        #[serde(skip)]
+        pub rebinding_action: Option<BoundAction>,
+
+        /// The order of the scoreboard picked by the player, this is [`None`] if the scoreboard is displayed in the server's order.
+        pub scoreboard_sort: Option<ScoreboardSort>,
     }
 
     impl Default for UiState {
@@ -120,6 +620,14 @@ pub mod client {
                 username_buffer: String::new(),
                 leaderboard_rect: Rect::NOTHING,
                 current_resource_pack: None,
+                resource_packs: Vec::new(),
+                intermission_page: 0,
+                password_buffer: String::new(),
+                muted_players: HashSet::new(),
+                last_server_address: None,
+                scoreboard_open: false,
+                rebinding_action: None,
+                scoreboard_sort: None,
             }
         }
     }
@@ -145,9 +653,9 @@ pub mod client {
 
         /// Receives the connecting threads connection result.
         #[serde(skip)]
-        pub connection_receiver: Receiver<anyhow::Result<ClientConnection>>,
+        pub connection_receiver: Receiver<Result<ClientConnection, NetError>>,
         #[serde(skip)]
-        pub connection_sender: Sender<anyhow::Result<ClientConnection>>,
+        pub connection_sender: Sender<Result<ClientConnection, NetError>>,
 
         /// Used to display notifications with egui
         #[serde(skip)]
@@ -161,15 +669,72 @@ pub mod client {
         #[serde(skip)]
         pub texture_atlas_layouts: Handle<TextureAtlasLayout>,
 
-        pub has_voted: bool,
+        /// The map the player has voted for in the current intermission, this is [`None`] if the player hasn't voted yet.
+        #[serde(skip)]
+        pub voted_map: Option<MapNameDiscriminants>,
+
+        /// The saved fields are identified by their position, so this unused field keeps the place of the removed `has_voted` field.
+        /// The older saves couldn't be decoded without it.
+        has_voted: bool,
 
         pub custom_textures: Option<CustomTexture>,
+
+        /// The music tracks of the active resource pack, see [`ResourcePack::music`].
+        pub custom_music: HashMap<String, PathBuf>,
+
+        /// The map thumbnails of the active resource pack, see [`ResourcePack::map_thumbnails`].
+        pub custom_map_thumbnails: HashMap<String, PathBuf>,
+
+        /// The cursor's position when the ping wheel was opened, this is [`None`] if the ping wheel is closed.
+        #[serde(skip)]
+        pub ping_wheel_origin: Option<Vec2>,
+
+        /// The amount of players connected and the amount of players needed for the round to start.
+        /// This is [`None`] if the server isn't waiting for players.
+        #[serde(skip)]
+        pub waiting_for_players: Option<(usize, usize)>,
+
+        /// The players eliminated in the last round, in the order they have been eliminated.
+        #[serde(skip)]
+        pub elimination_summary: Option<Vec<ClientStatistics>>,
+
+        /// Whether the player has joined between the rounds, and is waiting for the next round to start to play.
+        #[serde(skip)]
+        pub queued_for_next_round: bool,
+
+        /// The players competing in the ongoing sudden death, this is [`None`] if there is no sudden death being played.
+        #[serde(skip)]
+        pub sudden_death: Option<Vec<Uuid>>,
+
+        /// The player's best results, recorded from the player's own pawn.
+        pub personal_bests: PersonalBests,
+
+        /// The local player's death count when its current life has started, and the time it has survived since.
+        /// This is [`None`] if the player isn't in a round.
+        #[serde(skip)]
+        pub current_survival: Option<(u32, Duration)>,
+
+        /// The state of the hill in the player's arena, as sent by the server.
+        #[serde(skip)]
+        pub hill_state: HillState,
+
+        /// The time the local player's pawn is respawned at, this is [`None`] if the pawn isn't waiting to be respawned.
+        #[serde(skip)]
+        pub respawn_at: Option<Instant>,
+
+        /// The recent knockouts, the oldest first. These are displayed for [`KILL_FEED_DURATION`].
+        #[serde(skip)]
+        pub kill_feed: Vec<KillFeedEntry>,
+
+        /// The player's persistent identity, this is generated on the first run and sent to the servers on every connection.
+        /// The servers recognize the player by it across connections, unlike the id assigned to every connection.
+        pub identity: Uuid,
     }
 
     impl Default for ApplicationCtx {
         fn default() -> Self {
             let (connection_sender, connection_receiver) =
-                channel::<anyhow::Result<ClientConnection>>(2000);
+                channel::<Result<ClientConnection, NetError>>(2000);
 
             Self {
                 ui_layer: UiLayer::MainMenu,
@@ -182,17 +747,617 @@ pub mod client {
                 cancellation_token: CancellationToken::new(),
                 settings: Settings::default(),
                 texture_atlas_layouts: Handle::<TextureAtlasLayout>::default(),
+                voted_map: None,
                 has_voted: false,
                 custom_textures: None,
+                custom_music: HashMap::new(),
+                custom_map_thumbnails: HashMap::new(),
+                ping_wheel_origin: None,
+                waiting_for_players: None,
+                elimination_summary: None,
+                queued_for_next_round: false,
+                sudden_death: None,
+                personal_bests: PersonalBests::default(),
+                current_survival: None,
+                hill_state: HillState::default(),
+                respawn_at: None,
+                kill_feed: Vec::new(),
+                identity: Uuid::new_v4(),
             }
         }
     }
 
-    #[derive(Debug, Default, Clone, serde::Deserialize, serde::Serialize)]
+    /// The time a knockout is displayed in the kill feed for.
+    pub const KILL_FEED_DURATION: Duration = Duration::from_secs(6);
+
+    /// The most knockouts displayed in the kill feed at once, the oldest ones are dropped first.
+    pub const KILL_FEED_LENGTH: usize = 5;
+
+    /// A knockout displayed in the kill feed.
+    #[derive(Debug, Clone)]
+    pub struct KillFeedEntry {
+        /// The line displayed in the kill feed, the names are resolved when the knockout is received as the players may leave afterwards.
+        pub text: String,
+
+        /// The time the knockout has been received at.
+        pub received_at: Instant,
+    }
+
+    impl KillFeedEntry {
+        /// Creates the kill feed's line of the knockout, the players are looked up by their ids in the statistics.
+        pub fn new(
+            victim: Uuid,
+            killer: Option<Uuid>,
+            assists: &[Uuid],
+            client_stats: &[ClientStatistics],
+        ) -> Self {
+            let username = |uuid: &Uuid| {
+                client_stats
+                    .iter()
+                    .find(|client| client.uuid == *uuid)
+                    .map(|client| client.username.clone())
+                    .unwrap_or_else(|| String::from("Unknown"))
+            };
+
+            let mut text = match killer {
+                Some(killer) => format!("{} killed {}", username(&killer), username(&victim)),
+                None => format!("{} fell off", username(&victim)),
+            };
+
+            if !assists.is_empty() {
+                text.push_str(&format!(
+                    ", assist {}",
+                    assists.iter().map(username).collect::<Vec<_>>().join(", ")
+                ));
+            }
+
+            Self {
+                text,
+                received_at: Instant::now(),
+            }
+        }
+    }
+
+    /// The player's best results across every match played, these are displayed on the main menu.
+    #[derive(Debug, Clone, Default, PartialEq, serde::Deserialize, serde::Serialize)]
+    #[serde(default)]
+    pub struct PersonalBests {
+        /// The highest combo the player has achieved.
+        pub highest_combo: u32,
+
+        /// The longest time the player has survived in a round without falling off.
+        pub longest_survival: Duration,
+    }
+
+    #[derive(Debug, Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+    #[serde(default)]
     pub struct Settings {
         pub fps: f64,
+
+        /// Whether fading afterimages are displayed behind fast moving pawns.
+        pub afterimages: bool,
+
+        /// The opacity of the afterimages, ranging from 0 to 1.
+        pub afterimage_intensity: f32,
+
+        /// Whether the active resource pack is reloaded when its files change, this is useful when creating resource packs.
+        pub hot_reload_resource_pack: bool,
+
+        /// The amount of maps displayed at once in the intermission's map carousel.
+        pub intermission_maps_per_page: usize,
+
+        /// Whether a spark is displayed where the local player's attacks hit.
+        pub hit_sparks: bool,
+
+        /// Whether an arrow is displayed where the training dummies are hit, showing the direction and the strength of the knockback.
+        pub knockback_arrows: bool,
+
+        /// Whose colliders are outlined while the debug overlay is enabled, this only has an effect in debug builds.
+        pub hitbox_display: HitboxDisplay,
+
+        /// Whether a ghost of the pawns is drawn at their position in the last update received from the server while the debug overlay is enabled.
+        /// This only has an effect in debug builds.
+        pub server_position_ghosts: bool,
+
+        /// Whether the game is rendered in a 16:9 viewport with black bars filling the rest of the window.
+        /// This prevents wider monitors from displaying more of the arena.
+        pub letterboxing: bool,
+
+        /// The volume of the music, ranging from 0 to 1.
+        pub music_volume: f32,
+
+        /// Whether the server's tick rate and the rate of the updates received from the server are displayed under the ping.
+        pub network_stats: bool,
+
+        /// The round trip time and the update loss at which the connection indicator turns fair or poor.
+        pub connection_quality_thresholds: ConnectionQualityThresholds,
+
+        /// The present mode of the window, this decides whether the frames are synced to the monitor's refresh rate.
+        pub present_mode: PresentModeSetting,
+
+        /// Whether the scoreboard is displayed while its key is held, or is toggled by pressing its key.
+        pub scoreboard_mode: ScoreboardMode,
+
+        /// The keys the player's inputs are bound to.
+        pub key_bindings: KeyBindings,
+
+        /// The amount of players listed on the scoreboard, the local player's row is pinned below them if they aren't among them.
+        pub scoreboard_rows: usize,
+
+        /// The color the player's pawn is tinted with, this is [`None`] if the pawn isn't tinted.
+        pub pawn_tint: Option<[u8; 3]>,
+    }
+
+    impl Default for Settings {
+        fn default() -> Self {
+            Self {
+                fps: 0.,
+                afterimages: true,
+                afterimage_intensity: 0.5,
+                hot_reload_resource_pack: false,
+                intermission_maps_per_page: 3,
+                hit_sparks: true,
+                knockback_arrows: false,
+                hitbox_display: HitboxDisplay::default(),
+                server_position_ghosts: false,
+                letterboxing: false,
+                music_volume: 0.5,
+                network_stats: false,
+                connection_quality_thresholds: ConnectionQualityThresholds::default(),
+                present_mode: PresentModeSetting::default(),
+                scoreboard_mode: ScoreboardMode::default(),
+                key_bindings: KeyBindings::default(),
+                scoreboard_rows: 10,
+                pawn_tint: None,
+            }
+        }
+    }
+
+    /// The present modes the players can choose from.
+    /// While VSync is on the framerate is limited to the monitor's refresh rate, so the framerate limit isn't applied.
+    #[derive(
+        Debug,
+        Clone,
+        Copy,
+        PartialEq,
+        Eq,
+        Default,
+        serde::Deserialize,
+        serde::Serialize,
+        strum::Display,
+        strum::VariantArray,
+    )]
+    pub enum PresentModeSetting {
+        /// The frames are synced to the monitor's refresh rate, this prevents tearing.
+        #[default]
+        #[strum(to_string = "VSync")]
+        Vsync,
+
+        /// The frames are presented immediately, this has the lowest latency but may tear.
+        #[strum(to_string = "No VSync")]
+        NoVsync,
+
+        /// The latest frame is presented at the monitor's refresh rate without blocking the rendering, this falls back to VSync if it isn't supported.
+        #[strum(to_string = "Mailbox")]
+        Mailbox,
+    }
+
+    impl PresentModeSetting {
+        /// Returns the window's [`PresentMode`].
+        pub fn present_mode(&self) -> PresentMode {
+            match self {
+                Self::Vsync => PresentMode::AutoVsync,
+                Self::NoVsync => PresentMode::AutoNoVsync,
+                Self::Mailbox => PresentMode::Mailbox,
+            }
+        }
+
+        /// Returns whether the present mode limits the framerate to the monitor's refresh rate.
+        pub fn limits_framerate(&self) -> bool {
+            *self == Self::Vsync
+        }
+    }
+
+    /// Whose colliders are outlined by the physics engine's debug renderer.
+    #[derive(
+        Debug,
+        Clone,
+        Copy,
+        PartialEq,
+        Eq,
+        Default,
+        serde::Deserialize,
+        serde::Serialize,
+        strum::Display,
+        strum::VariantArray,
+    )]
+    pub enum HitboxDisplay {
+        /// No colliders are outlined.
+        #[strum(to_string = "Off")]
+        Off,
+
+        /// Only the local player's pawn is outlined, so that the players can learn their own ranges without the clutter.
+        #[strum(to_string = "Own pawn only")]
+        SelfOnly,
+
+        /// Every collider is outlined.
+        #[default]
+        #[strum(to_string = "All")]
+        All,
+    }
+
+    /// How the scoreboard is opened with its key.
+    #[derive(
+        Debug,
+        Clone,
+        Copy,
+        PartialEq,
+        Eq,
+        Default,
+        serde::Deserialize,
+        serde::Serialize,
+        strum::Display,
+        strum::VariantArray,
+    )]
+    pub enum ScoreboardMode {
+        /// The scoreboard is displayed while the key is held.
+        #[default]
+        #[strum(to_string = "Hold")]
+        Hold,
+
+        /// The scoreboard is opened and closed by pressing the key, it is also closed when the game is paused.
+        #[strum(to_string = "Toggle")]
+        Toggle,
+    }
+
+    /// The columns the scoreboard can be sorted by.
+    #[derive(
+        Debug,
+        Clone,
+        Copy,
+        PartialEq,
+        Eq,
+        serde::Deserialize,
+        serde::Serialize,
+        strum::Display,
+        strum::VariantArray,
+    )]
+    pub enum ScoreboardColumn {
+        #[strum(to_string = "Username")]
+        Username,
+
+        #[strum(to_string = "Kills")]
+        Kills,
+
+        #[strum(to_string = "Deaths")]
+        Deaths,
+
+        #[strum(to_string = "Assists")]
+        Assists,
+
+        #[strum(to_string = "Score")]
+        Score,
+
+        #[strum(to_string = "K/D")]
+        KillDeathRatio,
+    }
+
+    /// The order of the scoreboard picked by the player.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+    pub struct ScoreboardSort {
+        /// The column the scoreboard is sorted by.
+        pub column: ScoreboardColumn,
+
+        /// Whether the highest values are displayed first.
+        pub descending: bool,
     }
 
+    impl ScoreboardSort {
+        /// Sorts the players' statistics by the column.
+        pub fn sort(&self, client_stats: &mut [ClientStatistics]) {
+            client_stats.sort_by(|a, b| {
+                let ordering = match self.column {
+                    ScoreboardColumn::Username => {
+                        a.username.to_lowercase().cmp(&b.username.to_lowercase())
+                    }
+                    ScoreboardColumn::Kills => a.kills.cmp(&b.kills),
+                    ScoreboardColumn::Deaths => a.deaths.cmp(&b.deaths),
+                    ScoreboardColumn::Assists => a.assists.cmp(&b.assists),
+                    ScoreboardColumn::Score => a.score.cmp(&b.score),
+                    ScoreboardColumn::KillDeathRatio => {
+                        a.kill_death_ratio().total_cmp(&b.kill_death_ratio())
+                    }
+                };
+
+                if self.descending {
+                    ordering.reverse()
+                } else {
+                    ordering
+                }
+            });
+        }
+
+        /// Returns the order after the player has clicked the column's header.
+        /// Clicking the column the scoreboard is sorted by reverses the order, clicking another column sorts by it with the highest values first.
+        pub fn clicked(current: Option<Self>, column: ScoreboardColumn) -> Self {
+            match current {
+                Some(current) if current.column == column => Self {
+                    column,
+                    descending: !current.descending,
+                },
+                _ => Self {
+                    column,
+                    descending: true,
+                },
+            }
+        }
+    }
+
+    /// The limits above which the connection is displayed as fair or poor.
+    #[derive(Debug, Clone, Copy, PartialEq, serde::Deserialize, serde::Serialize)]
+    #[serde(default)]
+    pub struct ConnectionQualityThresholds {
+        /// The round trip time in milliseconds above which the connection is fair.
+        pub fair_rtt_ms: i64,
+
+        /// The round trip time in milliseconds above which the connection is poor.
+        pub poor_rtt_ms: i64,
+
+        /// The percentage of the lost updates above which the connection is fair.
+        pub fair_loss_percent: f32,
+
+        /// The percentage of the lost updates above which the connection is poor.
+        pub poor_loss_percent: f32,
+    }
+
+    impl Default for ConnectionQualityThresholds {
+        fn default() -> Self {
+            Self {
+                fair_rtt_ms: 80,
+                poor_rtt_ms: 160,
+                fair_loss_percent: 2.,
+                poor_loss_percent: 10.,
+            }
+        }
+    }
+
+    /// The key which needs to be held to open the ping wheel.
+    pub const PING_WHEEL_KEY: KeyCode = KeyCode::KeyQ;
+
+    /// The key which writes a netcode report to the disk, this is only bound in debug builds.
+    pub const NETCODE_REPORT_KEY: KeyCode = KeyCode::F9;
+
+    /// The key which toggles the debug overlay, this is only bound in debug builds.
+    pub const DEBUG_OVERLAY_KEY: KeyCode = KeyCode::F10;
+
+    /// The actions which can be bound to a key in the settings.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum BoundAction {
+        /// An input sent to the server.
+        GameInput(GameInput),
+
+        /// Opening the scoreboard.
+        Scoreboard,
+    }
+
+    /// The reasons a key can't be bound to an action.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+    pub enum BindError {
+        /// The key is one of the [`KeyBindings::RESERVED_KEYS`].
+        #[error("{0:?} is reserved")]
+        Reserved(KeyCode),
+
+        /// The key is already bound to another action, the action's name is stored.
+        #[error("{0:?} is already bound to {1}")]
+        AlreadyBound(KeyCode, &'static str),
+    }
+
+    /// The keys the player's inputs and the scoreboard are bound to.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+    #[serde(default)]
+    pub struct KeyBindings {
+        pub jump: KeyCode,
+        pub left: KeyCode,
+        pub right: KeyCode,
+        pub duck: KeyCode,
+        pub attack: KeyCode,
+        pub super_attack: KeyCode,
+        pub ranged_attack: KeyCode,
+        pub charge_attack: KeyCode,
+        pub defend: KeyCode,
+        pub scoreboard: KeyCode,
+    }
+
+    impl Default for KeyBindings {
+        fn default() -> Self {
+            Self {
+                jump: KeyCode::KeyW,
+                left: KeyCode::KeyA,
+                right: KeyCode::KeyD,
+                duck: KeyCode::KeyS,
+                attack: KeyCode::Space,
+                super_attack: KeyCode::KeyF,
+                ranged_attack: KeyCode::KeyE,
+                charge_attack: KeyCode::KeyR,
+                defend: KeyCode::ShiftLeft,
+                scoreboard: KeyCode::Tab,
+            }
+        }
+    }
+
+    impl KeyBindings {
+        /// The actions which can be bound to a key, with their names displayed in the settings.
+        pub const BINDABLE_ACTIONS: [(BoundAction, &'static str); 10] = [
+            (BoundAction::GameInput(GameInput::MoveJump), "Jump"),
+            (BoundAction::GameInput(GameInput::MoveLeft), "Move left"),
+            (BoundAction::GameInput(GameInput::MoveRight), "Move right"),
+            (BoundAction::GameInput(GameInput::MoveDuck), "Duck"),
+            (BoundAction::GameInput(GameInput::Attack), "Attack"),
+            (
+                BoundAction::GameInput(GameInput::SuperAttack),
+                "Super attack",
+            ),
+            (
+                BoundAction::GameInput(GameInput::RangedAttack),
+                "Ranged attack",
+            ),
+            (
+                BoundAction::GameInput(GameInput::ChargeAttack),
+                "Charge attack",
+            ),
+            (BoundAction::GameInput(GameInput::Defend), "Defend"),
+            (BoundAction::Scoreboard, "Scoreboard"),
+        ];
+
+        /// The keys which are used by the client outside of the key bindings, these can't be bound.
+        /// Escape opens the pause menu and cancels the rebinding.
+        pub const RESERVED_KEYS: [KeyCode; 4] = [
+            KeyCode::Escape,
+            PING_WHEEL_KEY,
+            NETCODE_REPORT_KEY,
+            DEBUG_OVERLAY_KEY,
+        ];
+
+        /// Returns the key bound to the action, this is [`None`] if the action isn't one of the [`Self::BINDABLE_ACTIONS`].
+        pub fn key(&self, action: BoundAction) -> Option<KeyCode> {
+            let key = match action {
+                BoundAction::GameInput(GameInput::MoveJump) => self.jump,
+                BoundAction::GameInput(GameInput::MoveLeft) => self.left,
+                BoundAction::GameInput(GameInput::MoveRight) => self.right,
+                BoundAction::GameInput(GameInput::MoveDuck) => self.duck,
+                BoundAction::GameInput(GameInput::Attack) => self.attack,
+                BoundAction::GameInput(GameInput::SuperAttack) => self.super_attack,
+                BoundAction::GameInput(GameInput::RangedAttack) => self.ranged_attack,
+                BoundAction::GameInput(GameInput::ChargeAttack) => self.charge_attack,
+                BoundAction::GameInput(GameInput::Defend) => self.defend,
+                BoundAction::Scoreboard => self.scoreboard,
+                BoundAction::GameInput(_) => return None,
+            };
+
+            Some(key)
+        }
+
+        /// Binds the key to the action, nothing is bound if the action isn't one of the [`Self::BINDABLE_ACTIONS`].
+        /// The key is rejected if it is one of the [`Self::RESERVED_KEYS`], or if it is already bound to another action.
+        pub fn bind(&mut self, action: BoundAction, key: KeyCode) -> Result<(), BindError> {
+            if Self::RESERVED_KEYS.contains(&key) {
+                return Err(BindError::Reserved(key));
+            }
+
+            if let Some((_, name)) = Self::BINDABLE_ACTIONS.iter().find(|(bound_action, _)| {
+                *bound_action != action && self.key(*bound_action) == Some(key)
+            }) {
+                return Err(BindError::AlreadyBound(key, name));
+            }
+
+            if let Some(bound_key) = self.key_mut(action) {
+                *bound_key = key;
+            }
+
+            Ok(())
+        }
+
+        /// Returns the input bound to the key, this is [`None`] if the key isn't bound to an input.
+        pub fn game_input(&self, key: KeyCode) -> Option<GameInput> {
+            Self::BINDABLE_ACTIONS
+                .iter()
+                .find(|(action, _)| self.key(*action) == Some(key))
+                .and_then(|(action, _)| match action {
+                    BoundAction::GameInput(game_input) => Some(*game_input),
+                    BoundAction::Scoreboard => None,
+                })
+        }
+
+        fn key_mut(&mut self, action: BoundAction) -> Option<&mut KeyCode> {
+            let key = match action {
+                BoundAction::GameInput(GameInput::MoveJump) => &mut self.jump,
+                BoundAction::GameInput(GameInput::MoveLeft) => &mut self.left,
+                BoundAction::GameInput(GameInput::MoveRight) => &mut self.right,
+                BoundAction::GameInput(GameInput::MoveDuck) => &mut self.duck,
+                BoundAction::GameInput(GameInput::Attack) => &mut self.attack,
+                BoundAction::GameInput(GameInput::SuperAttack) => &mut self.super_attack,
+                BoundAction::GameInput(GameInput::RangedAttack) => &mut self.ranged_attack,
+                BoundAction::GameInput(GameInput::ChargeAttack) => &mut self.charge_attack,
+                BoundAction::GameInput(GameInput::Defend) => &mut self.defend,
+                BoundAction::Scoreboard => &mut self.scoreboard,
+                BoundAction::GameInput(_) => return None,
+            };
+
+            Some(key)
+        }
+    }
+
+    /// The quality of the connection to the server displayed next to the ping.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, strum::Display)]
+    pub enum ConnectionQuality {
+        #[strum(to_string = "Good")]
+        Good,
+
+        #[strum(to_string = "Fair")]
+        Fair,
+
+        #[strum(to_string = "Poor")]
+        Poor,
+    }
+
+    impl ConnectionQuality {
+        /// Rates the connection by its round trip time and the share of the lost updates ranging from 0 to 1, the worse of the two decides the quality.
+        pub fn from_metrics(
+            rtt_ms: i64,
+            loss: f32,
+            thresholds: &ConnectionQualityThresholds,
+        ) -> Self {
+            let rtt_quality = if rtt_ms > thresholds.poor_rtt_ms {
+                Self::Poor
+            } else if rtt_ms > thresholds.fair_rtt_ms {
+                Self::Fair
+            } else {
+                Self::Good
+            };
+
+            let loss_percent = loss * 100.;
+
+            let loss_quality = if loss_percent > thresholds.poor_loss_percent {
+                Self::Poor
+            } else if loss_percent > thresholds.fair_loss_percent {
+                Self::Fair
+            } else {
+                Self::Good
+            };
+
+            rtt_quality.max(loss_quality)
+        }
+
+        /// The color the connection's indicator is displayed with.
+        pub fn color(&self) -> Color32 {
+            match self {
+                Self::Good => Color32::from_rgb(70, 190, 80),
+                Self::Fair => Color32::from_rgb(240, 200, 50),
+                Self::Poor => Color32::from_rgb(230, 60, 60),
+            }
+        }
+    }
+
+    /// The colors the players are displayed with, indexed by their player slot.
+    pub const PLAYER_SLOT_COLORS: [Color32; 8] = [
+        Color32::from_rgb(230, 60, 60),
+        Color32::from_rgb(60, 120, 230),
+        Color32::from_rgb(70, 190, 80),
+        Color32::from_rgb(240, 200, 50),
+        Color32::from_rgb(170, 80, 210),
+        Color32::from_rgb(240, 140, 40),
+        Color32::from_rgb(60, 200, 200),
+        Color32::from_rgb(230, 110, 180),
+    ];
+
+    /// Returns the color of the player slot, the colors repeat if there are more players than colors.
+    pub fn player_slot_color(player_slot: usize) -> Color32 {
+        PLAYER_SLOT_COLORS[player_slot % PLAYER_SLOT_COLORS.len()]
+    }
+
+    /// The directory the resource packs are looked for in, every subdirectory is a resource pack.
+    pub const MODS_DIRECTORY: &str = "mods";
+
     #[derive(Debug, Default, Clone, serde::Deserialize, serde::Serialize)]
     pub struct CustomTexture {
         pub walk: PathBuf,
@@ -201,6 +1366,275 @@ pub mod client {
         pub hurt: PathBuf,
         pub jump: PathBuf,
     }
+
+    impl CustomTexture {
+        /// Returns the path of the texture.
+        pub fn get(&self, texture: PawnTexture) -> &PathBuf {
+            match texture {
+                PawnTexture::Walk => &self.walk,
+                PawnTexture::Idle => &self.idle,
+                PawnTexture::Attack => &self.attack,
+                PawnTexture::Hurt => &self.hurt,
+                PawnTexture::Jump => &self.jump,
+            }
+        }
+    }
+
+    /// The textures a pawn is displayed with.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum PawnTexture {
+        Walk,
+        Idle,
+        Attack,
+        Hurt,
+        Jump,
+    }
+
+    impl PawnTexture {
+        /// The name of the texture's file in a resource pack, and in the default assets.
+        pub fn file_name(&self) -> &'static str {
+            match self {
+                PawnTexture::Walk => "walk.png",
+                PawnTexture::Idle => "idle.png",
+                PawnTexture::Attack => "attack.png",
+                PawnTexture::Hurt => "hurt.png",
+                PawnTexture::Jump => "jump.png",
+            }
+        }
+
+        /// Returns the asset path of the texture, the resource pack's texture is used if there is one active.
+        pub fn asset_path(&self, custom_textures: &Option<CustomTexture>) -> String {
+            match custom_textures {
+                // The relative asset paths are relative to the assets folder, the absolute paths are used as they are
+                Some(custom_textures) => {
+                    let path = custom_textures.get(*self);
+
+                    if path.is_relative() {
+                        format!("../{}", path.display())
+                    } else {
+                        path.display().to_string()
+                    }
+                }
+                None => format!("../assets/{}", self.file_name()),
+            }
+        }
+    }
+
+    /// The name of the music track played in the menus.
+    pub const MENU_MUSIC_TRACK: &str = "menu";
+
+    /// The directory the default music tracks are looked for in, the tracks are named `<track>.ogg`.
+    pub const MUSIC_DIRECTORY: &str = "assets/music";
+
+    /// Returns the path of the music track's file, the resource pack's track is used if the active pack has one.
+    pub fn music_path(track: &str, custom_music: &HashMap<String, PathBuf>) -> PathBuf {
+        custom_music
+            .get(track)
+            .cloned()
+            .unwrap_or_else(|| Path::new(MUSIC_DIRECTORY).join(format!("{track}.ogg")))
+    }
+
+    /// The name of the manifest file every resource pack's directory has to contain.
+    pub const RESOURCE_PACK_MANIFEST: &str = "pack.ron";
+
+    /// A resource pack's manifest, this is deserialized from the [`RESOURCE_PACK_MANIFEST`] file in the pack's directory.
+    /// The paths contained in the manifest are relative to the pack's directory.
+    #[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
+    pub struct ResourcePack {
+        pub name: String,
+        pub author: String,
+        pub version: String,
+
+        /// The textures the pawns are displayed with.
+        pub textures: CustomTexture,
+
+        /// The thumbnails of the maps displayed when voting, the keys are the names of the maps.
+        #[serde(default)]
+        pub map_thumbnails: HashMap<String, PathBuf>,
+
+        /// The image displayed behind the maps.
+        #[serde(default)]
+        pub background: Option<PathBuf>,
+
+        /// The music tracks, the keys are the names of the tracks.
+        /// The maps' tracks are named by [`crate::game::map::MapInstance::music`], and the main menu's track is [`MENU_MUSIC_TRACK`].
+        #[serde(default)]
+        pub music: HashMap<String, PathBuf>,
+    }
+
+    impl ResourcePack {
+        /// Loads the resource pack from its directory, and resolves the paths in the manifest against the directory.
+        /// Returns an error if the manifest is malformed, or if any of the referenced images don't exist or can't be decoded.
+        pub fn load_from_dir(path: &Path) -> anyhow::Result<Self> {
+            let manifest_path = path.join(RESOURCE_PACK_MANIFEST);
+
+            let manifest = std::fs::read_to_string(&manifest_path)
+                .with_context(|| format!("Missing manifest: {RESOURCE_PACK_MANIFEST}"))?;
+
+            let mut resource_pack: Self = ron::from_str(&manifest)
+                .with_context(|| format!("Malformed manifest: {RESOURCE_PACK_MANIFEST}"))?;
+
+            // Resolve the paths against the pack's directory, and check that every image can be used
+            for image_path in resource_pack.image_paths_mut() {
+                if image_path.is_relative() {
+                    *image_path = path.join(&image_path);
+                }
+
+                image::open(&image_path)
+                    .with_context(|| format!("Invalid image: {}", image_path.display()))?;
+            }
+
+            // The music tracks aren't checked, as the missing tracks are just not played
+            for music_path in resource_pack.music.values_mut() {
+                if music_path.is_relative() {
+                    *music_path = path.join(&music_path);
+                }
+            }
+
+            Ok(resource_pack)
+        }
+
+        /// Returns the paths of all of the images referenced by the resource pack.
+        fn image_paths_mut(&mut self) -> impl Iterator<Item = &mut PathBuf> {
+            [
+                &mut self.textures.walk,
+                &mut self.textures.idle,
+                &mut self.textures.attack,
+                &mut self.textures.hurt,
+                &mut self.textures.jump,
+            ]
+            .into_iter()
+            .chain(self.map_thumbnails.values_mut())
+            .chain(self.background.iter_mut())
+        }
+    }
+
+    /// Scans the directory for resource packs, every subdirectory is treated as a resource pack.
+    /// The packs which failed to load are returned with their error, so that they can be displayed.
+    pub fn scan_resource_packs(mods_dir: &Path) -> Vec<(PathBuf, Result<ResourcePack, String>)> {
+        let Ok(read_dir) = std::fs::read_dir(mods_dir) else {
+            return Vec::new();
+        };
+
+        let mut resource_packs = read_dir
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| path.is_dir())
+            .map(|path| {
+                // Display the whole chain of errors, so that the cause is shown too
+                let resource_pack =
+                    ResourcePack::load_from_dir(&path).map_err(|err| format!("{err:#}"));
+
+                (path, resource_pack)
+            })
+            .collect::<Vec<(PathBuf, Result<ResourcePack, String>)>>();
+
+        resource_packs.sort_by(|(path, _), (other_path, _)| path.cmp(other_path));
+
+        resource_packs
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn baseline_save_decodes() {
+            // The layout of the saves written before the removal of `has_voted`
+            let baseline_save = rmp_serde::to_vec(&(
+                (
+                    String::from("127.0.0.1:4000"),
+                    Rect::NOTHING,
+                    None::<PathBuf>,
+                    String::from("player"),
+                ),
+                (144.,),
+                true,
+                None::<CustomTexture>,
+            ))
+            .unwrap();
+
+            let app_ctx = rmp_serde::from_slice::<ApplicationCtx>(&baseline_save).unwrap();
+
+            assert_eq!(app_ctx.ui_state.username_buffer, "player");
+            assert_eq!(app_ctx.settings.fps, 144.);
+        }
+
+        #[test]
+        fn reserved_keys_can_not_be_bound() {
+            let mut key_bindings = KeyBindings::default();
+
+            for key in KeyBindings::RESERVED_KEYS {
+                assert_eq!(
+                    key_bindings.bind(BoundAction::GameInput(GameInput::Attack), key),
+                    Err(BindError::Reserved(key))
+                );
+                assert_eq!(
+                    key_bindings.bind(BoundAction::Scoreboard, key),
+                    Err(BindError::Reserved(key))
+                );
+            }
+
+            assert_eq!(key_bindings, KeyBindings::default());
+        }
+
+        #[test]
+        fn scoreboard_key_conflicts_with_the_inputs() {
+            let mut key_bindings = KeyBindings::default();
+
+            assert_eq!(
+                key_bindings.bind(BoundAction::GameInput(GameInput::Attack), KeyCode::Tab),
+                Err(BindError::AlreadyBound(KeyCode::Tab, "Scoreboard"))
+            );
+            assert_eq!(
+                key_bindings.bind(BoundAction::Scoreboard, KeyCode::Space),
+                Err(BindError::AlreadyBound(KeyCode::Space, "Attack"))
+            );
+
+            assert!(key_bindings
+                .bind(BoundAction::Scoreboard, KeyCode::KeyM)
+                .is_ok());
+            assert_eq!(key_bindings.scoreboard, KeyCode::KeyM);
+            assert_eq!(key_bindings.game_input(KeyCode::KeyM), None);
+        }
+
+        #[test]
+        fn save_round_trips() {
+            let mut app_ctx = ApplicationCtx::default();
+
+            app_ctx.ui_state.username_buffer = String::from("player");
+            app_ctx.settings.fps = 144.;
+
+            let save = rmp_serde::to_vec(&app_ctx).unwrap();
+
+            let app_ctx = rmp_serde::from_slice::<ApplicationCtx>(&save).unwrap();
+
+            assert_eq!(app_ctx.ui_state.username_buffer, "player");
+            assert_eq!(app_ctx.settings.fps, 144.);
+        }
+
+        #[test]
+        fn only_relative_texture_paths_are_prefixed() {
+            let custom_textures = CustomTexture {
+                walk: PathBuf::from("packs/walk.png"),
+                idle: PathBuf::from("/packs/idle.png"),
+                attack: PathBuf::from("packs/attack.png"),
+                hurt: PathBuf::from("packs/hurt.png"),
+                jump: PathBuf::from("packs/jump.png"),
+            };
+
+            let custom_textures = Some(custom_textures);
+
+            assert_eq!(
+                PawnTexture::Walk.asset_path(&custom_textures),
+                "../packs/walk.png"
+            );
+            assert_eq!(
+                PawnTexture::Idle.asset_path(&custom_textures),
+                "/packs/idle.png"
+            );
+        }
+    }
 }
 
 /// This [`RandomEngine`] should never be used in crypto cases, as it uses a [`SmallRng`] in inside.
@@ -208,6 +1642,8 @@ pub mod client {
 #[derive(Resource)]
 pub struct RandomEngine {
     pub inner: SmallRng,
+    /// The seed the [`SmallRng`] has been created from, the server sends this to the clients so that they can include it in their bug reports.
+    pub seed: u64,
 }
 
 impl Default for RandomEngine {
@@ -217,9 +1653,16 @@ impl Default for RandomEngine {
 }
 
 impl RandomEngine {
+    /// Creates a new [`RandomEngine`] with a random seed.
     pub fn new() -> Self {
+        Self::from_seed(rand::random())
+    }
+
+    /// Creates a new [`RandomEngine`] from the seed, the same seed always produces the same numbers.
+    pub fn from_seed(seed: u64) -> Self {
         Self {
-            inner: SmallRng::from_rng(&mut rand::rng()),
+            inner: SmallRng::seed_from_u64(seed),
+            seed,
         }
     }
 }
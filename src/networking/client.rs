@@ -6,22 +6,27 @@ use std::{
 };
 
 use bevy::ecs::system::Resource;
-use chrono::Local;
+use chrono::{DateTime, Local, TimeDelta, Utc};
 use parking_lot::RwLock;
 use tokio::{
-    io::AsyncReadExt,
+    io::{AsyncReadExt, AsyncWriteExt},
     net::{TcpStream, UdpSocket},
     select,
     sync::mpsc::{channel, Receiver, Sender},
+    task::JoinHandle,
+    time::{interval_at, sleep_until, timeout, Instant},
 };
 use tokio_util::sync::CancellationToken;
 use uuid::Uuid;
 
-use crate::networking::{GameInput, RemoteClientGameRequest, ServerTickUpdate, UDP_DATAGRAM_SIZE};
+use crate::networking::{
+    error::NetError, read_tick_update_batch, simulation::deliver_packet, GameInput,
+    RemoteClientGameRequest, ServerTickUpdate, KEEP_ALIVE_TIMEOUT, UDP_DATAGRAM_SIZE,
+};
 
 use super::{
-    write_to_buf_with_len, ClientMetadata, ClientStatistics, RemoteClientRequest,
-    RemoteServerRequest, ServerMetadata,
+    read_metadata_with_len, write_to_buf_with_len, ClientMetadata, ClientStatistics,
+    RemoteClientRequest, RemoteServerRequest, ServerMetadata,
 };
 
 #[derive(Resource)]
@@ -39,18 +44,40 @@ pub struct ClientConnection {
     pub connected_clients_stats: Arc<RwLock<BTreeSet<ClientStatistics>>>,
 
     pub rtt_ms: Arc<AtomicI64>,
+
+    /// The offset of the server's clock from the client's clock in milliseconds, this is estimated with every RTT measurement.
+    pub clock_offset_ms: Arc<AtomicI64>,
+
+    /// The address of the server as it was entered by the user.
+    pub address: String,
+
+    /// The token which stops the connection's tasks when cancelled.
+    pub cancellation_token: CancellationToken,
+
+    /// The tasks handling the connection, these finish after the [`ClientConnection::cancellation_token`] has been cancelled.
+    pub tasks: Vec<JoinHandle<()>>,
 }
 
+/// The time the server has to answer the connection and the metadata exchange in.
+pub const CONNECTION_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The interval the client measures its round trip time to the server in.
+pub const RTT_MEASUREMENT_INTERVAL: Duration = Duration::from_secs(10);
+
 impl ClientConnection {
     pub async fn connect_to_address(
         address: String,
         username: String,
+        password: String,
+        tint: Option<[u8; 3]>,
+        identity: Uuid,
         cancellation_token: CancellationToken,
-    ) -> anyhow::Result<Self> {
+    ) -> Result<Self, NetError> {
         // Parse destination address.
         let dest_address: SocketAddr = address.parse()?;
 
-        let mut tcp_stream = TcpStream::connect(dest_address).await?;
+        let mut tcp_stream =
+            timeout(CONNECTION_TIMEOUT, TcpStream::connect(dest_address)).await??;
 
         // Create a new UdpSocket instance.
         // This is used to send ServerTicks to the client from the server.
@@ -60,11 +87,15 @@ impl ClientConnection {
         // We will send this to the server so that it knows where to send the ticks to.
         let socket_port = udp_socket.local_addr()?.port();
 
-        let client_metadata = ClientMetadata::new(socket_port, username);
+        let client_metadata = ClientMetadata::new(socket_port, username, password, tint, identity);
 
         // Exchange metadata with the server.
         // We will send the UdpSocket's port and the server will send our unique uuid, and the port of the Server's UdpSocket.
-        let server_metadata = exchange_metadata(&mut tcp_stream, client_metadata).await?;
+        let server_metadata = timeout(
+            CONNECTION_TIMEOUT,
+            exchange_metadata(&mut tcp_stream, client_metadata),
+        )
+        .await??;
 
         // Create a new channel pair for managing server main instructions
         let (remote_sender, remote_receiver) = channel::<RemoteServerRequest>(2000);
@@ -74,12 +105,15 @@ impl ClientConnection {
 
         let rtt_ms = Arc::new(AtomicI64::new(0));
 
-        setup_server_handler(
+        let clock_offset_ms = Arc::new(AtomicI64::new(0));
+
+        let server_handler = setup_server_handler(
             tcp_stream,
             cancellation_token.clone(),
             remote_sender,
             remote_server_receiver,
             rtt_ms.clone(),
+            clock_offset_ms.clone(),
             server_metadata.client_uuid,
         )
         .await;
@@ -95,7 +129,7 @@ impl ClientConnection {
         // Create a new channel pair for managing inputs
         let (sender, receiver) = channel::<Vec<GameInput>>(2000);
 
-        setup_server_sender(
+        let server_sender = setup_server_sender(
             receiver,
             cancellation_token.clone(),
             udp_socket.clone(),
@@ -105,7 +139,8 @@ impl ClientConnection {
 
         let (client_sender, client_receiver) = channel::<ServerTickUpdate>(2000);
 
-        setup_server_game_listener(cancellation_token, udp_socket, client_sender).await;
+        let server_game_listener =
+            setup_server_game_listener(cancellation_token.clone(), udp_socket, client_sender).await;
 
         Ok(ClientConnection {
             server_metadata,
@@ -115,8 +150,32 @@ impl ClientConnection {
             remote_server_sender,
             connected_clients_stats: Arc::new(RwLock::new(BTreeSet::new())),
             rtt_ms,
+            clock_offset_ms,
+            address,
+            cancellation_token,
+            tasks: vec![server_handler, server_sender, server_game_listener],
         })
     }
+
+    /// Disconnects from the server cleanly, so that the server removes the player's pawn right away.
+    /// The server is sent a [`GameInput::Exit`] and the TCP connection is shut down, this returns after all of the connection's tasks have finished.
+    pub async fn disconnect(self) {
+        self.cancellation_token.cancel();
+
+        for task in self.tasks {
+            let _ = task.await;
+        }
+    }
+
+    /// Returns the current time of the server, estimated from the client's clock.
+    /// The countdowns should be displayed in the server's time, so that they aren't skewed by the clocks' difference.
+    pub fn server_time(&self) -> DateTime<Utc> {
+        let clock_offset_ms = self
+            .clock_offset_ms
+            .load(std::sync::atomic::Ordering::Relaxed);
+
+        Local::now().to_utc() + TimeDelta::milliseconds(clock_offset_ms)
+    }
 }
 
 pub async fn setup_server_sender(
@@ -124,7 +183,7 @@ pub async fn setup_server_sender(
     cancellation_token: CancellationToken,
     udp_socket: Arc<UdpSocket>,
     client_uuid: Uuid,
-) {
+) -> JoinHandle<()> {
     tokio::spawn(async move {
         loop {
             select! {
@@ -140,14 +199,14 @@ pub async fn setup_server_sender(
                 }
             }
         }
-    });
+    })
 }
 
 pub async fn setup_server_game_listener(
     cancellation_token: CancellationToken,
     socket: Arc<UdpSocket>,
     client_sender: Sender<ServerTickUpdate>,
-) {
+) -> JoinHandle<()> {
     tokio::spawn(async move {
         loop {
             let mut buf = vec![0; UDP_DATAGRAM_SIZE];
@@ -157,21 +216,25 @@ pub async fn setup_server_game_listener(
                     break;
                 }
 
-                Ok(_) = socket.peek(&mut buf) => {
-                    let message_length = u32::from_be_bytes(buf[..4].try_into().unwrap());
-
-                    let mut msg_buf = vec![0; message_length as usize + 4];
-
-                    socket.recv(&mut msg_buf).await.unwrap();
-
-                    let remote_client_request = rmp_serde::from_slice::<ServerTickUpdate>(&msg_buf[4..]).unwrap();
-
-                    // This will return a SendError if the receiver is dropped before the select is completed.
-                    let _ = client_sender.send(remote_client_request).await;
+                Ok(datagram_length) = socket.recv(&mut buf) => {
+                    // The server batches the updates of a tick, so a datagram may contain multiple updates
+                    // A malformed datagram is dropped, the next tick's updates replace it anyway
+                    let Ok(server_tick_updates) = read_tick_update_batch(&buf[..datagram_length]) else {
+                        continue;
+                    };
+
+                    let client_sender = client_sender.clone();
+
+                    deliver_packet(async move {
+                        for server_tick_update in server_tick_updates {
+                            // This will return a SendError if the receiver is dropped before the select is completed.
+                            let _ = client_sender.send(server_tick_update).await;
+                        }
+                    }).await;
                 }
             }
         }
-    });
+    })
 }
 
 async fn setup_server_handler(
@@ -180,16 +243,29 @@ async fn setup_server_handler(
     remote_server_sender: Sender<RemoteServerRequest>,
     mut remote_client_receiver: Receiver<RemoteClientRequest>,
     rtt_ms: Arc<AtomicI64>,
+    clock_offset_ms: Arc<AtomicI64>,
     uuid: Uuid,
-) {
+) -> JoinHandle<()> {
     // Spawn a server handler thread
     tokio::spawn(async move {
         // Send rtt measurement packet before handling all the messages
         send_rtt_measurement(uuid, &mut tcp_stream).await;
 
+        // The server sends keep-alives, so it is considered unreachable if nothing has been received for a while
+        let mut last_received = Instant::now();
+
+        // The interval isn't reset by the received messages, unlike a sleep created in every iteration of the loop
+        let mut rtt_interval = interval_at(
+            Instant::now() + RTT_MEASUREMENT_INTERVAL,
+            RTT_MEASUREMENT_INTERVAL,
+        );
+
         loop {
             select! {
                 _ = cancellation_token.cancelled() => {
+                    // Close the connection gracefully, so that the server notices the disconnect immediately
+                    let _ = tcp_stream.shutdown().await;
+
                     break;
                 }
 
@@ -204,30 +280,59 @@ async fn setup_server_handler(
                 }
 
                 Ok(message_length) = tcp_stream.read_u32() => {
+                    last_received = Instant::now();
+
                     let mut buf = vec![0; message_length as usize];
 
                     tcp_stream.read_exact(&mut buf).await.unwrap();
 
                     let request = rmp_serde::from_slice::<RemoteServerRequest>(&buf).unwrap();
 
-                    if let crate::networking::ServerRequest::RTTMeasurement(timestamp) = &request.request {
-                        let time_delta = Local::now().to_utc().signed_duration_since(timestamp);
+                    if let crate::networking::ServerRequest::RTTMeasurement { client_timestamp, server_timestamp } = &request.request {
+                        let time_delta = Local::now().to_utc().signed_duration_since(client_timestamp);
 
                         let rtt_ms_fetched = time_delta.num_milliseconds();
 
                         rtt_ms.store(rtt_ms_fetched, std::sync::atomic::Ordering::Relaxed);
+
+                        // The server has answered halfway through the round trip
+                        let estimated_local_time = *client_timestamp + time_delta / 2;
+
+                        clock_offset_ms.store(server_timestamp.signed_duration_since(estimated_local_time).num_milliseconds(), std::sync::atomic::Ordering::Relaxed);
+                    }
+                    else if let crate::networking::ServerRequest::KeepAlive = &request.request {
+                        send_keep_alive(uuid, &mut tcp_stream).await;
                     }
                     else {
                         remote_server_sender.send(request).await.unwrap();
                     }
                 }
 
-                _ = tokio::time::sleep(Duration::from_secs(10)) => {
+                // The server's network may have dropped without the connection being closed
+                _ = sleep_until(last_received + KEEP_ALIVE_TIMEOUT) => {
+                    let _ = remote_server_sender.send(RemoteServerRequest { request: crate::networking::ServerRequest::ConnectionLost }).await;
+
+                    break;
+                }
+
+                _ = rtt_interval.tick() => {
                     send_rtt_measurement(uuid, &mut tcp_stream).await;
                 }
             }
         }
-    });
+    })
+}
+
+/// Answers the server's [`crate::networking::ServerRequest::KeepAlive`], so that the server knows that the client is still connected.
+pub async fn send_keep_alive(uuid: Uuid, tcp_stream: &mut TcpStream) {
+    let sendable_message = RemoteClientRequest {
+        uuid,
+        request: crate::networking::ClientRequest::KeepAlive,
+    };
+
+    let buf = rmp_serde::to_vec(&sendable_message).unwrap();
+
+    write_to_buf_with_len(tcp_stream, &buf).await.unwrap();
 }
 
 pub async fn send_rtt_measurement(uuid: Uuid, tcp_stream: &mut TcpStream) {
@@ -246,18 +351,9 @@ pub async fn send_rtt_measurement(uuid: Uuid, tcp_stream: &mut TcpStream) {
 async fn exchange_metadata(
     tcp_stream: &mut TcpStream,
     client_metadata: ClientMetadata,
-) -> anyhow::Result<ServerMetadata> {
-    // Allocate a buffer for the incoming message
-    let mut msg_header_buf = vec![0; 4];
-
-    // Read the bytes into the buffer
-    tcp_stream.read_exact(&mut msg_header_buf).await?;
-
-    // Allocate buffer
-    let mut buf = vec![0; u32::from_be_bytes(msg_header_buf.try_into().unwrap()) as usize];
-
-    // Write the bytes into the buffer
-    tcp_stream.read_exact(&mut buf).await?;
+) -> Result<ServerMetadata, NetError> {
+    // Read the server's metadata, a server announcing an oversized message is rejected
+    let buf = read_metadata_with_len(tcp_stream).await?;
 
     // Deserialize the bytes and return the result
     let server_metadata = rmp_serde::from_slice::<ServerMetadata>(&buf)?;
@@ -272,18 +368,21 @@ async fn exchange_metadata(
 }
 
 async fn send_game_action(send: Arc<UdpSocket>, game_input: Vec<GameInput>, uuid: Uuid) {
-    let message_bytes = rmp_serde::to_vec(&RemoteClientGameRequest {
-        id: uuid,
-        inputs: game_input,
-        timestamp: chrono::Local::now().to_utc(),
-    })
+    let message_bytes = rmp_serde::to_vec(&RemoteClientGameRequest::new(
+        uuid,
+        &game_input,
+        chrono::Local::now().to_utc(),
+    ))
     .unwrap();
 
     let mut message_header = (message_bytes.len() as u32).to_be_bytes().to_vec();
 
     message_header.extend(message_bytes);
 
-    send.writable().await.unwrap();
+    deliver_packet(async move {
+        send.writable().await.unwrap();
 
-    send.send(&message_header).await.unwrap();
+        send.send(&message_header).await.unwrap();
+    })
+    .await;
 }
@@ -0,0 +1,67 @@
+use std::{io, net::AddrParseError};
+
+use tokio::time::error::Elapsed;
+
+/// The errors which can occur while connecting to a server or while setting one up.
+/// The variants separate the failures the user can act on, so that they can be displayed with a specific message.
+#[derive(Debug, thiserror::Error)]
+pub enum NetError {
+    /// The address entered by the user couldn't be parsed.
+    #[error("Invalid address: {0}")]
+    InvalidAddress(#[from] AddrParseError),
+
+    /// The remote has refused the connection, this happens if there is no server running at the address.
+    #[error("The connection has been refused")]
+    ConnectionRefused,
+
+    /// The remote hasn't answered in time.
+    #[error("The connection has timed out")]
+    Timeout,
+
+    /// The remote has closed the connection before the metadata exchange has finished.
+    #[error("The connection has been closed by the remote")]
+    ConnectionClosed,
+
+    /// The metadata couldn't be serialized or deserialized, this happens if the client and the server run different versions of the game.
+    #[error("Invalid metadata: {0}")]
+    InvalidMetadata(String),
+
+    /// The client has sent a password which doesn't match the server's.
+    #[error("Wrong password")]
+    WrongPassword,
+
+    /// Any other IO error.
+    #[error("IO error: {0}")]
+    Io(io::Error),
+}
+
+impl From<io::Error> for NetError {
+    fn from(error: io::Error) -> Self {
+        match error.kind() {
+            io::ErrorKind::ConnectionRefused => Self::ConnectionRefused,
+            io::ErrorKind::TimedOut => Self::Timeout,
+            io::ErrorKind::UnexpectedEof
+            | io::ErrorKind::ConnectionReset
+            | io::ErrorKind::ConnectionAborted => Self::ConnectionClosed,
+            _ => Self::Io(error),
+        }
+    }
+}
+
+impl From<Elapsed> for NetError {
+    fn from(_: Elapsed) -> Self {
+        Self::Timeout
+    }
+}
+
+impl From<rmp_serde::encode::Error> for NetError {
+    fn from(error: rmp_serde::encode::Error) -> Self {
+        Self::InvalidMetadata(error.to_string())
+    }
+}
+
+impl From<rmp_serde::decode::Error> for NetError {
+    fn from(error: rmp_serde::decode::Error) -> Self {
+        Self::InvalidMetadata(error.to_string())
+    }
+}
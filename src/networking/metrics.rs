@@ -0,0 +1,273 @@
+use std::{
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use bevy_tokio_tasks::TokioTasksRuntime;
+use parking_lot::RwLock;
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpListener,
+    select,
+};
+use tokio_util::sync::CancellationToken;
+
+/// The counters of the server's runtime metrics, these are incremented by the networking tasks and the tick systems.
+pub static SERVER_METRICS: ServerMetrics = ServerMetrics::new();
+
+/// Contains the counters of the server's network traffic since the application has started.
+#[derive(Debug, Default)]
+pub struct ServerMetrics {
+    pub udp_packets_received: AtomicU64,
+    pub udp_bytes_received: AtomicU64,
+    pub udp_packets_sent: AtomicU64,
+    pub udp_bytes_sent: AtomicU64,
+
+    pub tcp_messages_received: AtomicU64,
+    pub tcp_bytes_received: AtomicU64,
+    pub tcp_messages_sent: AtomicU64,
+    pub tcp_bytes_sent: AtomicU64,
+
+    /// The time it took to process the last received tick in microseconds.
+    pub recv_tick_micros: AtomicU64,
+    /// The time it took to process the last sent tick in microseconds.
+    pub send_tick_micros: AtomicU64,
+
+    /// The amount of ticks which took longer than the fixed timestep.
+    pub tick_overruns: AtomicU64,
+}
+
+impl ServerMetrics {
+    pub const fn new() -> Self {
+        Self {
+            udp_packets_received: AtomicU64::new(0),
+            udp_bytes_received: AtomicU64::new(0),
+            udp_packets_sent: AtomicU64::new(0),
+            udp_bytes_sent: AtomicU64::new(0),
+            tcp_messages_received: AtomicU64::new(0),
+            tcp_bytes_received: AtomicU64::new(0),
+            tcp_messages_sent: AtomicU64::new(0),
+            tcp_bytes_sent: AtomicU64::new(0),
+            recv_tick_micros: AtomicU64::new(0),
+            send_tick_micros: AtomicU64::new(0),
+            tick_overruns: AtomicU64::new(0),
+        }
+    }
+
+    pub fn record_udp_received(&self, bytes: usize) {
+        self.udp_packets_received.fetch_add(1, Ordering::Relaxed);
+        self.udp_bytes_received
+            .fetch_add(bytes as u64, Ordering::Relaxed);
+    }
+
+    pub fn record_udp_sent(&self, bytes: usize) {
+        self.udp_packets_sent.fetch_add(1, Ordering::Relaxed);
+        self.udp_bytes_sent
+            .fetch_add(bytes as u64, Ordering::Relaxed);
+    }
+
+    pub fn record_tcp_received(&self, bytes: usize) {
+        self.tcp_messages_received.fetch_add(1, Ordering::Relaxed);
+        self.tcp_bytes_received
+            .fetch_add(bytes as u64, Ordering::Relaxed);
+    }
+
+    pub fn record_tcp_sent(&self, bytes: usize) {
+        self.tcp_messages_sent.fetch_add(1, Ordering::Relaxed);
+        self.tcp_bytes_sent
+            .fetch_add(bytes as u64, Ordering::Relaxed);
+    }
+
+    /// Returns the current value of the counters.
+    pub fn snapshot(&self) -> MetricsSnapshot {
+        MetricsSnapshot {
+            udp_packets_received: self.udp_packets_received.load(Ordering::Relaxed),
+            udp_bytes_received: self.udp_bytes_received.load(Ordering::Relaxed),
+            udp_packets_sent: self.udp_packets_sent.load(Ordering::Relaxed),
+            udp_bytes_sent: self.udp_bytes_sent.load(Ordering::Relaxed),
+            tcp_messages_received: self.tcp_messages_received.load(Ordering::Relaxed),
+            tcp_bytes_received: self.tcp_bytes_received.load(Ordering::Relaxed),
+            tcp_messages_sent: self.tcp_messages_sent.load(Ordering::Relaxed),
+            tcp_bytes_sent: self.tcp_bytes_sent.load(Ordering::Relaxed),
+            tick_duration: Duration::from_micros(
+                self.recv_tick_micros.load(Ordering::Relaxed)
+                    + self.send_tick_micros.load(Ordering::Relaxed),
+            ),
+            tick_overruns: self.tick_overruns.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// The value of the [`ServerMetrics`]' counters at a point in time.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MetricsSnapshot {
+    pub udp_packets_received: u64,
+    pub udp_bytes_received: u64,
+    pub udp_packets_sent: u64,
+    pub udp_bytes_sent: u64,
+    pub tcp_messages_received: u64,
+    pub tcp_bytes_received: u64,
+    pub tcp_messages_sent: u64,
+    pub tcp_bytes_sent: u64,
+    /// The time it took to process the last received and sent ticks.
+    pub tick_duration: Duration,
+    pub tick_overruns: u64,
+}
+
+/// The server's health over the last sampling period, this is displayed in the server's UI and served by the metrics endpoint.
+#[derive(Debug, Clone, Default)]
+pub struct MetricsReport {
+    pub connected_players: usize,
+    pub tick_duration: Duration,
+
+    /// The average duration of the whole fixed ticks, measured by the tick watchdog.
+    pub average_tick_duration: Duration,
+    /// The fixed timestep, the simulation falls behind if the ticks take longer than this.
+    pub tick_budget: Duration,
+    /// The amount of ticks which have overrun the budget during the sampling period.
+    pub tick_overruns: u64,
+
+    pub udp_packets_received_per_sec: f64,
+    pub udp_packets_sent_per_sec: f64,
+    pub tcp_messages_received_per_sec: f64,
+    pub tcp_messages_sent_per_sec: f64,
+
+    /// The total amount of bytes received since the application has started.
+    pub bytes_received: u64,
+    /// The total amount of bytes sent since the application has started.
+    pub bytes_sent: u64,
+
+    /// The amount of messages waiting in the channel of the game inputs received over UDP.
+    pub pending_udp_messages: usize,
+    /// The amount of messages waiting in the channel of the requests received over TCP.
+    pub pending_tcp_messages: usize,
+}
+
+impl MetricsReport {
+    /// Creates a report from the change of the counters over the elapsed time.
+    pub fn new(
+        previous: &MetricsSnapshot,
+        current: &MetricsSnapshot,
+        elapsed: Duration,
+        connected_players: usize,
+        pending_udp_messages: usize,
+        pending_tcp_messages: usize,
+    ) -> Self {
+        let elapsed_secs = elapsed.as_secs_f64().max(f64::EPSILON);
+
+        let per_sec =
+            |previous: u64, current: u64| current.saturating_sub(previous) as f64 / elapsed_secs;
+
+        Self {
+            connected_players,
+            tick_duration: current.tick_duration,
+            // The tick watchdog's measurements are filled in by the sampling system
+            average_tick_duration: Duration::ZERO,
+            tick_budget: Duration::ZERO,
+            tick_overruns: current.tick_overruns.saturating_sub(previous.tick_overruns),
+            udp_packets_received_per_sec: per_sec(
+                previous.udp_packets_received,
+                current.udp_packets_received,
+            ),
+            udp_packets_sent_per_sec: per_sec(previous.udp_packets_sent, current.udp_packets_sent),
+            tcp_messages_received_per_sec: per_sec(
+                previous.tcp_messages_received,
+                current.tcp_messages_received,
+            ),
+            tcp_messages_sent_per_sec: per_sec(
+                previous.tcp_messages_sent,
+                current.tcp_messages_sent,
+            ),
+            bytes_received: current.udp_bytes_received + current.tcp_bytes_received,
+            bytes_sent: current.udp_bytes_sent + current.tcp_bytes_sent,
+            pending_udp_messages,
+            pending_tcp_messages,
+        }
+    }
+
+    /// Returns whether the server can't keep up with the fixed timestep.
+    pub fn is_over_capacity(&self) -> bool {
+        self.tick_overruns > 0 || self.average_tick_duration > self.tick_budget
+    }
+
+    /// Returns the report in a plain text format, one metric per line.
+    pub fn to_text(&self) -> String {
+        format!(
+            "connected_players {}\n\
+            tick_duration_micros {}\n\
+            average_tick_duration_micros {}\n\
+            tick_budget_micros {}\n\
+            tick_overruns {}\n\
+            udp_packets_received_per_sec {:.2}\n\
+            udp_packets_sent_per_sec {:.2}\n\
+            tcp_messages_received_per_sec {:.2}\n\
+            tcp_messages_sent_per_sec {:.2}\n\
+            bytes_received {}\n\
+            bytes_sent {}\n\
+            pending_udp_messages {}\n\
+            pending_tcp_messages {}\n",
+            self.connected_players,
+            self.tick_duration.as_micros(),
+            self.average_tick_duration.as_micros(),
+            self.tick_budget.as_micros(),
+            self.tick_overruns,
+            self.udp_packets_received_per_sec,
+            self.udp_packets_sent_per_sec,
+            self.tcp_messages_received_per_sec,
+            self.tcp_messages_sent_per_sec,
+            self.bytes_received,
+            self.bytes_sent,
+            self.pending_udp_messages,
+            self.pending_tcp_messages,
+        )
+    }
+}
+
+/// Serves the latest [`MetricsReport`] over HTTP on the given port, until the cancellation token is cancelled.
+/// Every request is answered with the report regardless of its path.
+pub fn setup_metrics_endpoint(
+    tokio_runtime: &TokioTasksRuntime,
+    port: u16,
+    metrics_report: Arc<RwLock<MetricsReport>>,
+    cancellation_token: CancellationToken,
+) {
+    tokio_runtime.spawn_background_task(move |_ctx| async move {
+        let tcp_listener = match TcpListener::bind(("::", port)).await {
+            Ok(tcp_listener) => tcp_listener,
+            Err(err) => {
+                eprintln!("Failed to start the metrics endpoint on port {port}: {err}");
+
+                return;
+            }
+        };
+
+        loop {
+            select! {
+                _ = cancellation_token.cancelled() => {
+                    break;
+                }
+
+                Ok((mut tcp_stream, _)) = tcp_listener.accept() => {
+                    let body = metrics_report.read().to_text();
+
+                    tokio::spawn(async move {
+                        // Read the request, its contents are ignored
+                        let mut buf = vec![0; 1024];
+
+                        let _ = tcp_stream.read(&mut buf).await;
+
+                        let response = format!(
+                            "HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                            body.len()
+                        );
+
+                        let _ = tcp_stream.write_all(response.as_bytes()).await;
+                    });
+                }
+            }
+        }
+    });
+}
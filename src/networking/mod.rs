@@ -1,18 +1,27 @@
-use std::cmp::Ordering;
+use std::{cmp::Ordering, sync::Arc, time::Duration};
 
-use bevy::transform::components::Transform;
+use bevy::{math::Vec2, transform::components::Transform};
 use bevy_rapier2d::prelude::Velocity;
 use chrono::{DateTime, Utc};
-use tokio::io::AsyncWriteExt;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
 use uuid::Uuid;
 
-use crate::game::{
-    map::{MapInstance, MapNameDiscriminants, MapObjectUpdate},
-    pawns::{Pawn, PawnType},
+use crate::{
+    game::{
+        hill::HillState,
+        map::{MapInstance, MapNameDiscriminants, MapObjectUpdate},
+        modifiers::MatchModifiers,
+        pawns::{Pawn, PawnType},
+        physics::PhysicsSettings,
+    },
+    networking::error::NetError,
 };
 
 pub mod client;
+pub mod error;
+pub mod metrics;
 pub mod server;
+pub mod simulation;
 
 /// This struct serves as a way to send a message by the clients, messages sent via the [`RemoteClientGameRequest`] are applied to the server's game world.
 #[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
@@ -20,13 +29,54 @@ pub struct RemoteClientGameRequest {
     /// The id of the client who has sent the message.
     /// IDs are handed out to the clients on connection.
     pub id: Uuid,
-    /// The input of the clients connected to the server.
+    /// The inputs of the client in this tick, packed into a bitmask so that the packet stays small.
     /// Multiple inputs can be input at once.
-    pub inputs: Vec<GameInput>,
+    pub inputs: GameInputBits,
+    /// The inputs which don't fit in the bitmask in the order they have been input, these are applied after the bitmask's inputs.
+    /// These are the connection's events like [`GameInput::Exit`], and the inputs whose order would be lost in the bitmask.
+    pub events: Vec<GameInput>,
     /// Time timestamp whem the client has sent this request, this can be used to measure RTT.
     pub timestamp: DateTime<Utc>,
 }
 
+impl RemoteClientGameRequest {
+    /// Creates the request from the inputs, the inputs are packed into a [`GameInputBits`] as long as their order is kept.
+    /// The bitmask can't hold an input twice, or tell whether a press or its release came first.
+    /// So the first input which doesn't fit is sent as an event, together with every input after it.
+    pub fn new(id: Uuid, game_inputs: &[GameInput], timestamp: DateTime<Utc>) -> Self {
+        let mut inputs = GameInputBits::default();
+        let mut events = Vec::new();
+
+        for (index, game_input) in game_inputs.iter().enumerate() {
+            let loses_order = inputs.contains(*game_input)
+                || game_input
+                    .counterpart()
+                    .is_some_and(|counterpart| inputs.contains(counterpart));
+
+            if loses_order || !inputs.insert(*game_input) {
+                events.extend_from_slice(&game_inputs[index..]);
+
+                break;
+            }
+        }
+
+        Self {
+            id,
+            inputs,
+            events,
+            timestamp,
+        }
+    }
+
+    /// Returns every input of the request, the inputs of the bitmask are followed by the events.
+    pub fn game_inputs(&self) -> Vec<GameInput> {
+        self.inputs
+            .iter()
+            .chain(self.events.iter().copied())
+            .collect()
+    }
+}
+
 /// This message type is used by the clients to send important information to the server.
 /// *These messages should be sent thorugh TCP, as they contain critical information.*
 #[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
@@ -59,13 +109,121 @@ pub enum ServerRequest {
 
     PlayersStatisticsChange(Vec<ClientStatistics>),
 
-    RTTMeasurement(DateTime<Utc>),
+    /// This message is sent when a player has disconnected, the clients remove the player's row from the leaderboard.
+    /// Contains the id of the disconnected player.
+    PlayerStatsRemoved(Uuid),
+
+    /// The answer to the client's [`ClientRequest::RTTMeasurement`].
+    /// The server's time is used by the client to estimate the offset between the clocks, so that the countdowns can be displayed in the server's time.
+    RTTMeasurement {
+        /// The timestamp sent by the client.
+        client_timestamp: DateTime<Utc>,
+        /// The server's time when it has answered the measurement.
+        server_timestamp: DateTime<Utc>,
+    },
 
     ClientPawnSync(Vec<PawnUpdate>),
 
     PawnTypeChange((Uuid, PawnType)),
 
-    PlayerVote((Uuid, MapNameDiscriminants))
+    PlayerVote((Uuid, MapNameDiscriminants)),
+
+    /// This message is sent every time a client votes during the intermission.
+    /// Contains the current vote count of every selectable map, which replaces the counts displayed by the clients.
+    VoteTally(Vec<(MapNameDiscriminants, usize)>),
+
+    /// This message is sent when a client has used the ping wheel.
+    /// The clients display the ping at the world position for a brief period of time.
+    Ping {
+        /// The id of the client who has placed the ping.
+        uuid: Uuid,
+        /// The type of the ping, this decides what is displayed.
+        kind: PingKind,
+        /// The world position the ping was placed at.
+        position: Vec2,
+    },
+
+    /// This message is sent when the server is waiting for enough players to join to start the round.
+    /// The round timer is frozen until enough players have joined, and the round is started by sending an [`ServerGameState::OngoingGame`].
+    WaitingForPlayers {
+        /// The amount of players currently connected.
+        current: usize,
+        /// The amount of players needed to start the round.
+        needed: usize,
+    },
+
+    /// This message is sent at the end of a round if the players have limited lives.
+    /// Contains the statistics of the eliminated players, in the order they have been eliminated.
+    EliminationSummary(Vec<ClientStatistics>),
+
+    /// This message is sent when the server swaps the map in the middle of a round.
+    /// Unlike [`ServerGameState::OngoingGame`] this only replaces the map and leaves the round's state untouched.
+    MapChange(MapInstance),
+
+    /// This message is sent when the operator stops the server, the clients should return to the main menu.
+    ServerShutdown,
+
+    /// This message is sent when the round's time is up and multiple players have tied for first.
+    /// Contains the players competing in the sudden death, the first one of them to fall off loses and the round ends.
+    SuddenDeath(Vec<Uuid>),
+
+    /// This message is sent to a client whose request has been rejected by the server.
+    /// Contains the reason of the rejection, which is displayed to the user.
+    RequestRejected(String),
+
+    /// This message is sent to a client whose connection has been rejected by the server, the connection is closed afterwards.
+    /// Contains the reason of the rejection, which is displayed to the user.
+    ConnectionRejected(String),
+
+    /// This message is sent to a client who has been disconnected by the server, the connection is closed afterwards.
+    /// Contains the reason of the kick, which is displayed to the user.
+    Kicked(String),
+
+    /// This message is sent to the players of the arena when an attack has hit a pawn.
+    /// The clients display a hit spark at the position of the hit.
+    HitRegistered {
+        /// The id of the client who has landed the hit.
+        attacker: Uuid,
+        /// The id of the client whose pawn has been hit.
+        victim: Uuid,
+        /// The world position of the hit pawn.
+        position: Vec2,
+        /// The direction the hit pawn is knocked towards.
+        direction: Vec2,
+        /// The velocity added to the hit pawn, this is zero if the pawn hasn't been knocked back.
+        knockback: Vec2,
+        /// Whether the hit pawn is a training dummy, the clients can visualize the knockback of these hits.
+        on_training_dummy: bool,
+    },
+
+    /// This message is sent to all of the clients when a pawn has been knocked out, the clients display it in their kill feed.
+    PlayerKnockedOut {
+        /// The id of the client whose pawn has been knocked out.
+        victim: Uuid,
+        /// The id of the client who has landed the last hit on the pawn, this is [`None`] if the pawn has fallen off on its own.
+        killer: Option<Uuid>,
+        /// The ids of the other clients who have hit the pawn shortly before it was knocked out.
+        assists: Vec<Uuid>,
+    },
+
+    /// This message is sent to a client whose pawn has been knocked out, if the server respawns the pawns with a delay.
+    /// Contains the time in seconds until the pawn is respawned, the client displays a countdown until then.
+    RespawnIn(f32),
+
+    /// This message is sent to the players of the arena when the state of the map's hill changes.
+    /// The clients display who controls the hill, the players on the hill are awarded score by the server.
+    HillStateChange(HillState),
+
+    /// This message is sent to a client who has joined between the rounds, or mid-round if the server makes the late joiners spectate.
+    /// The client's pawn is spawned when the next round starts, which is signaled by a [`ServerGameState::OngoingGame`].
+    QueuedForNextRound,
+
+    /// This message is sent to every client every [`KEEP_ALIVE_INTERVAL`], the clients answer with a [`ClientRequest::KeepAlive`].
+    /// The connections whose peer hasn't sent anything for [`KEEP_ALIVE_TIMEOUT`] are considered dead, as a dropped network doesn't always close the connection.
+    KeepAlive,
+
+    /// This message isn't sent by the server, the client's connection handler delivers it when the server hasn't sent anything for [`KEEP_ALIVE_TIMEOUT`].
+    ConnectionLost,
 }
 
 /// The types of GameStates which a server can request a client to enter.
@@ -84,15 +242,27 @@ pub enum ServerGameState {
 pub struct OngoingGameData {
     /// Current map loaded
     pub current_map: MapInstance,
+    /// The date the round has started at, the clients use this to display the round's progress.
+    pub round_start_date: DateTime<Utc>,
     /// Round end date
     pub round_end_date: DateTime<Utc>,
+    /// The modifiers of this round, the clients render the round according to these.
+    #[serde(default)]
+    pub modifiers: MatchModifiers,
 }
 
 impl OngoingGameData {
-    pub fn new(current_map: MapInstance, round_end_date: DateTime<Utc>) -> Self {
+    pub fn new(
+        current_map: MapInstance,
+        round_start_date: DateTime<Utc>,
+        round_end_date: DateTime<Utc>,
+        modifiers: MatchModifiers,
+    ) -> Self {
         Self {
             current_map,
+            round_start_date,
             round_end_date,
+            modifiers,
         }
     }
 }
@@ -109,6 +279,36 @@ pub enum ClientRequest {
     PawnTypeChange(PawnType),
 
     ClientPawnSync,
+
+    /// This message is sent when the client places a ping with the ping wheel.
+    /// The server broadcasts it to all the clients, if the client isn't rate-limited.
+    Ping { kind: PingKind, position: Vec2 },
+
+    /// The answer to the server's [`ServerRequest::KeepAlive`], this lets the server know that the client is still connected.
+    KeepAlive,
+}
+
+/// The preset signals a client can send with the ping wheel.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    serde::Deserialize,
+    serde::Serialize,
+    strum::Display,
+    strum::VariantArray,
+)]
+pub enum PingKind {
+    #[strum(to_string = "Help!")]
+    Help,
+    #[strum(to_string = "Nice!")]
+    Nice,
+    #[strum(to_string = "Retreat!")]
+    Retreat,
+    #[strum(to_string = "Attack!")]
+    Attack,
 }
 
 /// The message the server sends to all the clients, to share all the important information about the current intermission. ie.: Maps available for voting, duration of the intermission.
@@ -128,6 +328,16 @@ impl IntermissionData {
             intermission_end_date,
         }
     }
+
+    /// Returns the map which has received the most votes.
+    /// Falls back to [`MapNameDiscriminants::FlatGround`] if there was nothing to vote for, so that the server doesn't get stuck without a map.
+    pub fn voted_map(&self) -> MapNameDiscriminants {
+        self.selectable_maps
+            .iter()
+            .max_by_key(|(_, vote_count)| *vote_count)
+            .map(|(map_name, _)| *map_name)
+            .unwrap_or(MapNameDiscriminants::FlatGround)
+    }
 }
 
 /// This server as a way for the server to send the state of an entity in the world.
@@ -168,23 +378,51 @@ impl PawnUpdate {
     }
 }
 
+/// The state of a [`crate::game::combat::Projectile`] in the tick, the clients only display the projectiles.
+#[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
+pub struct ProjectileUpdate {
+    /// The id of the projectile, this is the bits of the projectile's [`bevy::ecs::entity::Entity`] on the server.
+    pub id: u64,
+    /// The position of the projectile in the tick.
+    pub position: Vec2,
+}
+
 #[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
 pub enum TickUpdateType {
     Pawn(PawnUpdate),
     MapObject(MapObjectUpdate),
+    Projectile(ProjectileUpdate),
 }
 
+/// The rate of the server's fixed update in hertz, the server sends the state of the world to the clients on every fixed update.
+pub const SERVER_TICK_RATE: f64 = 64.;
+
 #[derive(Debug, serde::Deserialize, serde::Serialize)]
 pub struct ServerMetadata {
     pub client_uuid: Uuid,
     pub game_socket_port: u16,
+    /// The server's physics settings, the clients apply these to their local simulation.
+    pub physics_settings: PhysicsSettings,
+    /// The rate the server sends the tick updates at in hertz.
+    pub tick_rate: f64,
+    /// The seed of the server's random number generator, see [`crate::RandomEngine::seed`].
+    pub seed: u64,
 }
 
 impl ServerMetadata {
-    pub fn new(client_uuid: Uuid, game_socket_port: u16) -> Self {
+    pub fn new(
+        client_uuid: Uuid,
+        game_socket_port: u16,
+        physics_settings: PhysicsSettings,
+        tick_rate: f64,
+        seed: u64,
+    ) -> Self {
         Self {
             client_uuid,
             game_socket_port,
+            physics_settings,
+            tick_rate,
+            seed,
         }
     }
 }
@@ -192,32 +430,75 @@ impl ServerMetadata {
 #[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
 pub struct ConnectionMetadata {
     pub game_socket_port: u16,
+    pub physics_settings: PhysicsSettings,
+    pub tick_rate: f64,
+    pub seed: u64,
 }
 
 impl ConnectionMetadata {
-    pub fn new(game_socket_port: u16) -> Self {
-        Self { game_socket_port }
+    pub fn new(
+        game_socket_port: u16,
+        physics_settings: PhysicsSettings,
+        tick_rate: f64,
+        seed: u64,
+    ) -> Self {
+        Self {
+            game_socket_port,
+            physics_settings,
+            tick_rate,
+            seed,
+        }
     }
 
     pub fn into_server_metadata(&self, id: Uuid) -> ServerMetadata {
         ServerMetadata {
             game_socket_port: self.game_socket_port,
             client_uuid: id,
+            physics_settings: self.physics_settings,
+            tick_rate: self.tick_rate,
+            seed: self.seed,
         }
     }
 }
 
-#[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
+#[derive(Clone, serde::Deserialize, serde::Serialize)]
 pub struct ClientMetadata {
     pub game_socket_port: u16,
     pub username: String,
+    /// The password the client has entered, this is checked by password protected servers.
+    pub password: String,
+    /// The tint the player has picked for their pawn, the server nudges the tint if it is too similar to another player's.
+    pub tint: Option<[u8; 3]>,
+    /// The player's persistent identity, the server recognizes the reconnecting players by it.
+    pub identity: Uuid,
+}
+
+impl std::fmt::Debug for ClientMetadata {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // The password is left out so that it never ends up in the logs
+        f.debug_struct("ClientMetadata")
+            .field("game_socket_port", &self.game_socket_port)
+            .field("username", &self.username)
+            .field("tint", &self.tint)
+            .field("identity", &self.identity)
+            .finish_non_exhaustive()
+    }
 }
 
 impl ClientMetadata {
-    pub fn new(game_socket_port: u16, username: String) -> Self {
+    pub fn new(
+        game_socket_port: u16,
+        username: String,
+        password: String,
+        tint: Option<[u8; 3]>,
+        identity: Uuid,
+    ) -> Self {
         Self {
             game_socket_port,
             username,
+            password,
+            tint,
+            identity,
         }
     }
 
@@ -225,13 +506,39 @@ impl ClientMetadata {
         ServerMetadata {
             game_socket_port: self.game_socket_port,
             client_uuid: id,
+            physics_settings: PhysicsSettings::default(),
+            tick_rate: SERVER_TICK_RATE,
+            seed: 0,
         }
     }
 }
 
+/// The largest metadata message the peers accept during the handshake, the connection is aborted if a larger message is announced.
+pub const MAX_METADATA_LENGTH: u32 = 64 * 1024;
+
+/// Reads a metadata message prefixed with its length, the message is rejected without being read if it is longer than [`MAX_METADATA_LENGTH`].
+pub async fn read_metadata_with_len<T>(buf: &mut T) -> Result<Vec<u8>, NetError>
+where
+    T: AsyncReadExt + Unpin,
+{
+    let metadata_length = buf.read_u32().await?;
+
+    if metadata_length > MAX_METADATA_LENGTH {
+        return Err(NetError::InvalidMetadata(format!(
+            "The metadata is too long: {metadata_length} bytes"
+        )));
+    }
+
+    let mut metadata_bytes = vec![0; metadata_length as usize];
+
+    buf.read_exact(&mut metadata_bytes).await?;
+
+    Ok(metadata_bytes)
+}
+
 /// Writes a slice to a buffer with the slice's length as the header.
 /// This results in the first 4 bytes being the [`u32`] representation of the slice's length.
-pub async fn write_to_buf_with_len<T>(buf: &mut T, slice: &[u8]) -> anyhow::Result<()>
+pub async fn write_to_buf_with_len<T>(buf: &mut T, slice: &[u8]) -> std::io::Result<()>
 where
     T: AsyncWriteExt + Unpin,
 {
@@ -249,37 +556,233 @@ where
 
 pub const UDP_DATAGRAM_SIZE: usize = 65536;
 
+/// The interval the server sends a [`ServerRequest::KeepAlive`] to the clients in.
+pub const KEEP_ALIVE_INTERVAL: Duration = Duration::from_secs(5);
+
+/// The time after which a connection is considered dead if the peer hasn't sent anything.
+pub const KEEP_ALIVE_TIMEOUT: Duration = Duration::from_secs(20);
+
+/// The size the datagrams of a [`TickUpdateBatch`] are kept under, so that they aren't fragmented on common networks.
+pub const TICK_BATCH_DATAGRAM_SIZE: usize = 1200;
+
+/// The tick updates sent to an arena's players in a tick, every update is serialized once and packed into as few datagrams as possible.
+/// Every update in a datagram is prefixed with its length, see [`read_tick_update_batch`].
+#[derive(Debug, Clone, Default)]
+pub struct TickUpdateBatch {
+    datagrams: Vec<Vec<u8>>,
+}
+
+impl TickUpdateBatch {
+    /// Appends the update to the last datagram, a new datagram is started if the update doesn't fit into it.
+    /// An update larger than [`TICK_BATCH_DATAGRAM_SIZE`] is sent in a datagram of its own.
+    pub fn push(&mut self, server_tick_update: &ServerTickUpdate) {
+        let message_bytes = rmp_serde::to_vec(server_tick_update).unwrap();
+
+        let frame_length = message_bytes.len() + 4;
+
+        let datagram = match self.datagrams.last_mut() {
+            Some(datagram) if datagram.len() + frame_length <= TICK_BATCH_DATAGRAM_SIZE => datagram,
+            _ => {
+                self.datagrams.push(Vec::with_capacity(frame_length));
+
+                self.datagrams.last_mut().unwrap()
+            }
+        };
+
+        datagram.extend((message_bytes.len() as u32).to_be_bytes());
+        datagram.extend(message_bytes);
+    }
+
+    pub fn datagrams(&self) -> &[Vec<u8>] {
+        &self.datagrams
+    }
+
+    /// Moves the datagrams into a shared buffer, so that the payload isn't copied for every client.
+    pub fn into_shared(self) -> Arc<[Arc<[u8]>]> {
+        self.datagrams.into_iter().map(Arc::from).collect()
+    }
+}
+
+/// Reads the length prefixed tick updates of a received datagram, a datagram may contain a single update or a whole [`TickUpdateBatch`] datagram.
+pub fn read_tick_update_batch(
+    mut datagram: &[u8],
+) -> Result<Vec<ServerTickUpdate>, rmp_serde::decode::Error> {
+    let mut server_tick_updates = Vec::new();
+
+    while datagram.len() >= 4 {
+        let message_length = u32::from_be_bytes(datagram[..4].try_into().unwrap()) as usize;
+
+        // Ignore the rest of a truncated datagram
+        let Some(message_bytes) = datagram.get(4..4 + message_length) else {
+            break;
+        };
+
+        server_tick_updates.push(rmp_serde::from_slice(message_bytes)?);
+
+        datagram = &datagram[4 + message_length..];
+    }
+
+    Ok(server_tick_updates)
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
 pub enum GameInput {
     MoveJump,
+    /// The jump key has been released, this shortens the pawn's jump if the server has enabled the jump cut.
+    MoveJumpRelease,
+    /// The duck key has been pressed, the pawn is pushed down every tick until [`GameInput::MoveDuckRelease`] is received.
     MoveDuck,
+    /// The duck key has been released.
+    MoveDuckRelease,
+    /// The right key has been pressed, the pawn moves right every tick until [`GameInput::MoveRightRelease`] is received.
     MoveRight,
+    /// The right key has been released.
+    MoveRightRelease,
+    /// The left key has been pressed, the pawn moves left every tick until [`GameInput::MoveLeftRelease`] is received.
     MoveLeft,
+    /// The left key has been released.
+    MoveLeftRelease,
+    /// The light attack, this is a [`crate::game::combat::AttackType::Directional`] attack if the pawn is facing up or down.
     Attack,
-
+    /// The heavy attack, see [`crate::game::combat::AttackType::Super`].
+    SuperAttack,
+    /// The ranged attack, see [`crate::game::combat::AttackType::Projectile`]. This is ignored if the pawn type has no ranged attack.
+    RangedAttack,
+    /// The charge attack input has been pressed, the pawn builds up charge until [`GameInput::ChargeAttackRelease`] is received.
+    ChargeAttack,
+    /// The charge attack input has been released, this performs a [`crate::game::combat::AttackType::Charged`] attack.
+    ChargeAttackRelease,
+
+    /// The defend key has been pressed, the pawn is defending until [`GameInput::DefendRelease`] is received.
     Defend,
+    /// The defend key has been released.
+    DefendRelease,
 
     Join,
     Exit,
 }
 
+impl GameInput {
+    /// The inputs which have a bit in a [`GameInputBits`], the index of an input is its bit.
+    pub const BITMASK_INPUTS: [GameInput; 15] = [
+        GameInput::MoveJump,
+        GameInput::MoveJumpRelease,
+        GameInput::MoveDuck,
+        GameInput::MoveDuckRelease,
+        GameInput::MoveRight,
+        GameInput::MoveRightRelease,
+        GameInput::MoveLeft,
+        GameInput::MoveLeftRelease,
+        GameInput::Attack,
+        GameInput::SuperAttack,
+        GameInput::RangedAttack,
+        GameInput::ChargeAttack,
+        GameInput::ChargeAttackRelease,
+        GameInput::Defend,
+        GameInput::DefendRelease,
+    ];
+
+    /// Returns the release of a held input, or the press of a released input.
+    /// This is [`None`] if the input isn't held.
+    pub fn counterpart(self) -> Option<GameInput> {
+        let counterpart = match self {
+            GameInput::MoveJump => GameInput::MoveJumpRelease,
+            GameInput::MoveJumpRelease => GameInput::MoveJump,
+            GameInput::MoveDuck => GameInput::MoveDuckRelease,
+            GameInput::MoveDuckRelease => GameInput::MoveDuck,
+            GameInput::MoveRight => GameInput::MoveRightRelease,
+            GameInput::MoveRightRelease => GameInput::MoveRight,
+            GameInput::MoveLeft => GameInput::MoveLeftRelease,
+            GameInput::MoveLeftRelease => GameInput::MoveLeft,
+            GameInput::ChargeAttack => GameInput::ChargeAttackRelease,
+            GameInput::ChargeAttackRelease => GameInput::ChargeAttack,
+            GameInput::Defend => GameInput::DefendRelease,
+            GameInput::DefendRelease => GameInput::Defend,
+            _ => return None,
+        };
+
+        Some(counterpart)
+    }
+}
+
+/// A set of [`GameInput`]s packed into a bitmask, every input of [`GameInput::BITMASK_INPUTS`] has a bit.
+/// The inputs are applied in the order of [`GameInput::BITMASK_INPUTS`], so the bitmask never holds both a press and its release, see [`RemoteClientGameRequest::new`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub struct GameInputBits(pub u16);
+
+impl GameInputBits {
+    /// Returns the bit of the input, this is [`None`] if the input doesn't have a bit.
+    fn bit(game_input: GameInput) -> Option<u16> {
+        GameInput::BITMASK_INPUTS
+            .iter()
+            .position(|input| *input == game_input)
+            .map(|index| 1 << index)
+    }
+
+    /// Sets the bit of the input, returns whether the input has a bit.
+    pub fn insert(&mut self, game_input: GameInput) -> bool {
+        let Some(bit) = Self::bit(game_input) else {
+            return false;
+        };
+
+        self.0 |= bit;
+
+        true
+    }
+
+    /// Returns whether the bit of the input is set.
+    pub fn contains(&self, game_input: GameInput) -> bool {
+        Self::bit(game_input).is_some_and(|bit| self.0 & bit != 0)
+    }
+
+    /// Returns whether none of the bits are set.
+    pub fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+
+    /// Returns the inputs whose bit is set.
+    pub fn iter(&self) -> impl Iterator<Item = GameInput> + '_ {
+        GameInput::BITMASK_INPUTS
+            .into_iter()
+            .filter(|game_input| self.contains(*game_input))
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, serde::Deserialize, serde::Serialize, Eq, Ord, Default)]
 pub struct ClientStatistics {
     pub uuid: Uuid,
     pub username: String,
     pub kills: u32,
     pub deaths: u32,
+    /// The amount of knockouts the player has helped with, by hitting the knocked out pawn shortly before another player's finishing hit.
+    pub assists: u32,
     pub score: u32,
+    /// The remaining lives of the player, this is [`None`] if the server doesn't limit the lives.
+    pub lives: Option<u32>,
+    /// The player slot assigned to the client by the server, this decides the color the player is displayed with.
+    pub player_slot: usize,
+    /// The tint of the player's pawn assigned by the server, see [`crate::game::pawns::Pawn::tint`].
+    pub tint: Option<[u8; 3]>,
 }
 
 impl ClientStatistics {
-    pub fn new(uuid: Uuid, username: String) -> Self {
+    pub fn new(uuid: Uuid, username: String, player_slot: usize) -> Self {
         Self {
             uuid,
             username,
+            player_slot,
             ..Default::default()
         }
     }
+
+    /// Returns the player's kills per death, the kills are returned as is if the player hasn't died yet.
+    pub fn kill_death_ratio(&self) -> f32 {
+        if self.deaths == 0 {
+            self.kills as f32
+        } else {
+            self.kills as f32 / self.deaths as f32
+        }
+    }
 }
 
 impl PartialOrd for ClientStatistics {
@@ -322,3 +825,119 @@ impl PartialOrd for ClientStatistics {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pawn_tick_update(tick_count: u64) -> ServerTickUpdate {
+        ServerTickUpdate::new(TickUpdateType::Pawn(PawnUpdate::new(
+            Transform::default(),
+            Velocity::default(),
+            Pawn::new_from_id(Uuid::new_v4()),
+            tick_count,
+        )))
+    }
+
+    #[test]
+    fn tick_update_batch_round_trips() {
+        let server_tick_updates = (0..32).map(pawn_tick_update).collect::<Vec<_>>();
+
+        let mut tick_update_batch = TickUpdateBatch::default();
+
+        for server_tick_update in &server_tick_updates {
+            tick_update_batch.push(server_tick_update);
+        }
+
+        let mut received_tick_counts = Vec::new();
+
+        for datagram in tick_update_batch.datagrams() {
+            assert!(datagram.len() <= TICK_BATCH_DATAGRAM_SIZE);
+
+            for server_tick_update in read_tick_update_batch(datagram).unwrap() {
+                let TickUpdateType::Pawn(pawn_update) = server_tick_update.tick_update_type else {
+                    panic!("Only pawn updates have been sent");
+                };
+
+                received_tick_counts.push(pawn_update.tick_count);
+            }
+        }
+
+        assert_eq!(received_tick_counts, (0..32).collect::<Vec<_>>());
+    }
+
+    fn sent_game_inputs(game_inputs: &[GameInput]) -> Vec<GameInput> {
+        RemoteClientGameRequest::new(Uuid::new_v4(), game_inputs, Utc::now()).game_inputs()
+    }
+
+    #[test]
+    fn game_inputs_keep_the_order_of_presses_and_releases() {
+        for (press, release) in [
+            (GameInput::MoveJump, GameInput::MoveJumpRelease),
+            (GameInput::ChargeAttack, GameInput::ChargeAttackRelease),
+            (GameInput::Defend, GameInput::DefendRelease),
+        ] {
+            assert_eq!(sent_game_inputs(&[release, press]), [release, press]);
+            assert_eq!(sent_game_inputs(&[press, release]), [press, release]);
+            assert_eq!(
+                sent_game_inputs(&[press, release, press]),
+                [press, release, press]
+            );
+        }
+    }
+
+    #[test]
+    fn game_inputs_keep_duplicates() {
+        assert_eq!(
+            sent_game_inputs(&[GameInput::Attack, GameInput::Attack]),
+            [GameInput::Attack, GameInput::Attack]
+        );
+    }
+
+    #[test]
+    fn game_inputs_are_packed_into_the_bitmask() {
+        let request = RemoteClientGameRequest::new(
+            Uuid::new_v4(),
+            &[GameInput::MoveLeft, GameInput::Attack],
+            Utc::now(),
+        );
+
+        assert!(request.events.is_empty());
+        assert_eq!(
+            request.game_inputs(),
+            [GameInput::MoveLeft, GameInput::Attack]
+        );
+    }
+
+    #[test]
+    fn read_tick_update_batch_rejects_malformed_datagrams() {
+        let mut datagram = 4_u32.to_be_bytes().to_vec();
+
+        datagram.extend([0xc1; 4]);
+
+        assert!(read_tick_update_batch(&datagram).is_err());
+    }
+
+    #[test]
+    fn voted_map_falls_back_without_selectable_maps() {
+        let intermission_data = IntermissionData::new(Vec::new(), Utc::now());
+
+        assert_eq!(
+            intermission_data.voted_map(),
+            MapNameDiscriminants::FlatGround
+        );
+    }
+
+    #[test]
+    fn voted_map_is_the_most_voted() {
+        let intermission_data = IntermissionData::new(
+            vec![
+                (MapNameDiscriminants::FlatGround, 1),
+                (MapNameDiscriminants::Islands, 2),
+            ],
+            Utc::now(),
+        );
+
+        assert_eq!(intermission_data.voted_map(), MapNameDiscriminants::Islands);
+    }
+}
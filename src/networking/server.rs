@@ -1,32 +1,56 @@
-use std::{collections::BTreeSet, net::SocketAddr, sync::Arc, time::Duration};
+use std::{
+    collections::{BTreeSet, HashMap},
+    net::{IpAddr, SocketAddr},
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
-use bevy::ecs::system::ResMut;
-use bevy_tokio_tasks::TokioTasksRuntime;
+use bevy::ecs::{entity::Entity, system::ResMut};
+use bevy_tokio_tasks::{TaskContext, TokioTasksRuntime};
 use chrono::{Local, TimeDelta};
 use dashmap::DashMap;
 use parking_lot::{Mutex, RwLock};
 use tokio::{
-    io::AsyncReadExt,
+    io::{AsyncReadExt, AsyncWriteExt},
     net::{
         tcp::{OwnedReadHalf, OwnedWriteHalf},
         TcpListener, TcpSocket, TcpStream, UdpSocket,
     },
     select,
     sync::mpsc::{channel, Receiver, Sender},
+    time::timeout,
 };
 use tokio_util::sync::CancellationToken;
 use uuid::Uuid;
 
 use crate::{
-    game::{collision::CollisionGroupSet, map::MapInstance, pawns::spawn_pawn},
+    game::{
+        collision::{CollisionGroupSet, MAX_ARENAS},
+        map::{MapInstance, DEFAULT_SPAWN_POSITION},
+        modifiers::MatchModifiers,
+        pawns::{distinct_tint, spawn_pawn, Pawn, PawnType},
+        physics::PhysicsSettings,
+    },
     networking::{RemoteClientRequest, UDP_DATAGRAM_SIZE},
+    server::ApplicationCtx,
 };
 
 use super::{
+    error::NetError,
+    metrics::{MetricsReport, SERVER_METRICS},
+    read_metadata_with_len,
+    simulation::deliver_packet,
     write_to_buf_with_len, ClientMetadata, ClientStatistics, ConnectionMetadata, OngoingGameData,
     RemoteClientGameRequest, RemoteServerRequest, ServerGameState, ServerMetadata, ServerRequest,
+    KEEP_ALIVE_INTERVAL, KEEP_ALIVE_TIMEOUT, SERVER_TICK_RATE,
 };
 
+/// The time a connecting client has to finish the metadata exchange in, the connection is closed if the client stalls.
+pub const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The amount of malformed UDP packets a sender can send in a row before its packets are dropped, until the server is restarted.
+pub const MAX_UDP_DECODE_FAILURES: u32 = 64;
+
 #[derive(Debug, Clone)]
 pub struct RemoteGameClient {
     pub uid: Uuid,
@@ -42,6 +66,215 @@ impl RemoteGameClient {
     }
 }
 
+/// Assigns a stable player slot to every connected client, the slots decide the colors the players are displayed with.
+/// The lowest free slot is assigned to new players, reconnecting players get their previous slot back if it hasn't been taken since.
+#[derive(Debug, Default)]
+pub struct PlayerSlots {
+    /// The slots of the connected clients.
+    occupied: HashMap<Uuid, usize>,
+
+    /// The slot every player has last been assigned, keyed by the players' persistent identity as the clients get a new id on every connection.
+    previous: HashMap<Uuid, usize>,
+}
+
+impl PlayerSlots {
+    /// Assigns a slot to the client and returns it.
+    pub fn assign(&mut self, uuid: Uuid, identity: Uuid) -> usize {
+        let is_free = |slot: &usize| !self.occupied.values().any(|occupied| occupied == slot);
+
+        let slot = self
+            .previous
+            .get(&identity)
+            .copied()
+            .filter(is_free)
+            .unwrap_or_else(|| (0..).find(is_free).unwrap());
+
+        self.occupied.insert(uuid, slot);
+        self.previous.insert(identity, slot);
+
+        slot
+    }
+
+    /// Frees the client's slot so that it can be assigned to another player.
+    pub fn free(&mut self, uuid: &Uuid) {
+        self.occupied.remove(uuid);
+    }
+}
+
+/// Assigns every connected client to one of the server's arenas, the arenas are independent matches sharing the server's round.
+/// New players are assigned to the arena with the least players, so that the arenas stay balanced.
+#[derive(Debug)]
+pub struct ArenaAssignments {
+    /// The amount of arenas hosted by the server.
+    arena_count: usize,
+
+    /// The arenas of the connected clients.
+    assigned: HashMap<Uuid, usize>,
+}
+
+impl ArenaAssignments {
+    pub fn new(arena_count: usize) -> Self {
+        Self {
+            arena_count: arena_count.clamp(1, MAX_ARENAS),
+            assigned: HashMap::new(),
+        }
+    }
+
+    /// Assigns the client to the least full arena and returns it.
+    pub fn assign(&mut self, uuid: Uuid) -> usize {
+        let arena = (0..self.arena_count)
+            .min_by_key(|arena| self.player_count(*arena))
+            .unwrap_or_default();
+
+        self.assigned.insert(uuid, arena);
+
+        arena
+    }
+
+    /// Removes the client from its arena.
+    pub fn free(&mut self, uuid: &Uuid) {
+        self.assigned.remove(uuid);
+    }
+
+    /// Returns the arena the client has been assigned to, this is [`None`] if the client isn't connected.
+    pub fn arena_of(&self, uuid: &Uuid) -> Option<usize> {
+        self.assigned.get(uuid).copied()
+    }
+
+    /// Returns the amount of players assigned to the arena.
+    pub fn player_count(&self, arena: usize) -> usize {
+        self.assigned
+            .values()
+            .filter(|assigned| **assigned == arena)
+            .count()
+    }
+
+    pub fn arena_count(&self) -> usize {
+        self.arena_count
+    }
+}
+
+/// The limits of the incoming connections, these protect the server from connection floods.
+#[derive(Debug, Clone, Copy, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct ConnectionLimits {
+    /// The amount of connections which can be exchanging the metadata at once, the connections above this are closed right away.
+    pub max_pending_handshakes: usize,
+
+    /// The amount of connections an IP address can open in a minute, the connections above this are closed right away.
+    pub connections_per_minute: usize,
+
+    /// The time in seconds the IP addresses exceeding [`Self::connections_per_minute`] are banned for, this is [`None`] if they aren't banned.
+    pub flood_ban_secs: Option<u64>,
+}
+
+impl Default for ConnectionLimits {
+    fn default() -> Self {
+        Self {
+            max_pending_handshakes: 16,
+            connections_per_minute: 10,
+            flood_ban_secs: Some(60),
+        }
+    }
+}
+
+/// The refused connections are logged at most once in this interval, so that a connection flood doesn't flood the log too.
+pub const REFUSED_CONNECTIONS_LOG_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Admits the incoming connections within the [`ConnectionLimits`], and keeps track of the IP addresses flooding the server.
+#[derive(Debug)]
+pub struct ConnectionGate {
+    /// The limits are shared with the [`ServerInstance`], so that the operator's changes apply to the running server.
+    limits: Arc<RwLock<ConnectionLimits>>,
+
+    /// The amount of admitted connections which haven't finished the metadata exchange yet.
+    pending_handshakes: usize,
+
+    /// The times of the connections every IP address has opened in the last minute.
+    recent_connections: HashMap<IpAddr, Vec<Instant>>,
+
+    /// The banned IP addresses with the time their ban ends at.
+    banned: HashMap<IpAddr, Instant>,
+}
+
+impl ConnectionGate {
+    pub fn new(limits: Arc<RwLock<ConnectionLimits>>) -> Self {
+        Self {
+            limits,
+            pending_handshakes: 0,
+            recent_connections: HashMap::new(),
+            banned: HashMap::new(),
+        }
+    }
+
+    /// Decides whether the connection from the address is admitted, the reason is returned if it isn't.
+    /// The admitted connections have to call [`Self::finish_handshake`] once their metadata exchange has finished.
+    pub fn admit(&mut self, ip: IpAddr) -> Result<(), String> {
+        let now = Instant::now();
+
+        let limits = *self.limits.read();
+
+        self.banned.retain(|_, ban_end| *ban_end > now);
+
+        if self.banned.contains_key(&ip) {
+            return Err(String::from("The address is banned"));
+        }
+
+        // Forget the connections older than a minute
+        self.recent_connections.retain(|_, connections| {
+            connections
+                .retain(|connection| now.duration_since(*connection) < Duration::from_secs(60));
+
+            !connections.is_empty()
+        });
+
+        // A busy server mustn't count the refused connections towards the rate limit, so that honest clients aren't banned
+        if self.pending_handshakes >= limits.max_pending_handshakes {
+            return Err(String::from("Too many pending connections"));
+        }
+
+        let connections = self.recent_connections.entry(ip).or_default();
+
+        if connections.len() >= limits.connections_per_minute {
+            if let Some(flood_ban_secs) = limits.flood_ban_secs {
+                self.banned
+                    .insert(ip, now + Duration::from_secs(flood_ban_secs));
+                self.recent_connections.remove(&ip);
+
+                return Err(format!("Flooding, banned for {flood_ban_secs} seconds"));
+            }
+
+            return Err(String::from("Exceeded the connection rate limit"));
+        }
+
+        connections.push(now);
+
+        self.pending_handshakes += 1;
+
+        Ok(())
+    }
+
+    /// Frees the place of an admitted connection, whether its metadata exchange has succeeded or not.
+    pub fn finish_handshake(&mut self) {
+        self.pending_handshakes = self.pending_handshakes.saturating_sub(1);
+    }
+}
+
+/// Returns the handles of the connected clients who are playing in the arena, this is used to only send the arena's updates to its players.
+pub fn clients_in_arena(
+    connected_clients: &DashMap<SocketAddr, (Uuid, Arc<Mutex<OwnedWriteHalf>>)>,
+    arena_assignments: &ArenaAssignments,
+    arena: usize,
+) -> Arc<DashMap<SocketAddr, (Uuid, Arc<Mutex<OwnedWriteHalf>>)>> {
+    Arc::new(
+        connected_clients
+            .iter()
+            .filter(|client| arena_assignments.arena_of(&client.value().0) == Some(arena))
+            .map(|client| (*client.key(), client.value().clone()))
+            .collect(),
+    )
+}
+
 pub struct ServerInstance {
     pub tcp_listener: Arc<Mutex<TcpListener>>,
     pub udp_socket: Arc<UdpSocket>,
@@ -57,11 +290,37 @@ pub struct ServerInstance {
 
     pub connected_clients_stats: Arc<RwLock<BTreeSet<ClientStatistics>>>,
 
-    pub game_state: Arc<RwLock<ServerGameState>>,
+    /// The state of every arena's match indexed by the arena, every arena plays its own rounds on its own map.
+    pub game_states: Arc<RwLock<Vec<ServerGameState>>>,
+
+    pub player_slots: Arc<Mutex<PlayerSlots>>,
+
+    pub arena_assignments: Arc<Mutex<ArenaAssignments>>,
+
+    /// The persistent identities of the connected clients keyed by the ids of their connections.
+    pub client_identities: Arc<DashMap<Uuid, Uuid>>,
+
+    /// The password the clients have to send to connect, this is [`None`] if the server isn't password protected.
+    pub password: Option<String>,
+
+    /// The limits of the incoming connections, the connections above them are closed before the metadata exchange.
+    pub connection_limits: Arc<RwLock<ConnectionLimits>>,
+
+    /// The server's health over the last sampling period, this is shared with the metrics endpoint.
+    pub metrics_report: Arc<RwLock<MetricsReport>>,
+
+    /// The round trip times of the connected clients keyed by the ids of their connections, these are measured with the keep-alives.
+    pub client_round_trip_times: Arc<DashMap<Uuid, Duration>>,
 }
 
 impl ServerInstance {
-    pub async fn create_server() -> anyhow::Result<Self> {
+    /// Creates a new server listening on random ports, the seed is sent to the clients in the [`ServerMetadata`].
+    pub async fn create_server(
+        physics_settings: PhysicsSettings,
+        arena_count: usize,
+        modifiers: MatchModifiers,
+        seed: u64,
+    ) -> Result<Self, NetError> {
         let tcp_socket = TcpSocket::new_v6()?;
 
         tcp_socket.bind("[::]:0".parse()?)?;
@@ -76,29 +335,48 @@ impl ServerInstance {
 
         let round_start_date = Local::now().to_utc();
 
+        let arena_assignments = ArenaAssignments::new(arena_count);
+
+        let game_state = ServerGameState::OngoingGame(OngoingGameData::new(
+            (|| {
+                #[cfg(debug_assertions)]
+                return MapInstance::map_test();
+
+                #[cfg(not(debug_assertions))]
+                return MapInstance::map_flatground();
+            })(),
+            round_start_date,
+            round_start_date
+                .checked_add_signed(TimeDelta::minutes(8))
+                .unwrap(),
+            modifiers,
+        ));
+
         Ok(Self {
             tcp_listener: Arc::new(Mutex::new(tcp_listener)),
             udp_socket: Arc::new(udp_socket),
             tcp_listener_port,
             client_udp_receiver: None,
-            metadata: ConnectionMetadata::new(udp_socket_port),
+            metadata: ConnectionMetadata::new(
+                udp_socket_port,
+                physics_settings,
+                SERVER_TICK_RATE,
+                seed,
+            ),
             connected_client_tcp_handles: Arc::new(DashMap::new()),
             client_tcp_receiver: None,
-            game_state: Arc::new(RwLock::new(ServerGameState::OngoingGame(
-                OngoingGameData::new(
-                    (|| {
-                        #[cfg(debug_assertions)]
-                        return MapInstance::map_test();
-
-                        #[cfg(not(debug_assertions))]
-                        return MapInstance::map_flatground();
-                    })(),
-                    round_start_date
-                        .checked_add_signed(TimeDelta::from_std(Duration::from_secs(8 * 60))?)
-                        .unwrap(),
-                ),
-            ))),
+            game_states: Arc::new(RwLock::new(vec![
+                game_state;
+                arena_assignments.arena_count()
+            ])),
             connected_clients_stats: Arc::new(RwLock::new(BTreeSet::new())),
+            player_slots: Arc::new(Mutex::new(PlayerSlots::default())),
+            arena_assignments: Arc::new(Mutex::new(arena_assignments)),
+            client_identities: Arc::new(DashMap::new()),
+            password: None,
+            connection_limits: Arc::new(RwLock::new(ConnectionLimits::default())),
+            metrics_report: Arc::new(RwLock::new(MetricsReport::default())),
+            client_round_trip_times: Arc::new(DashMap::new()),
         })
     }
 }
@@ -124,16 +402,34 @@ pub fn setup_remote_client_handler(
 
     let connected_clients_clone = client_game_socket_list.clone();
 
-    let server_game_state = server_instance.game_state.clone();
+    let server_game_states = server_instance.game_states.clone();
 
     server_instance.client_tcp_receiver = Some(tcp_receiver);
     server_instance.client_udp_receiver = Some(receiver);
 
     let connected_clients_stats = server_instance.connected_clients_stats.clone();
 
+    let player_slots = server_instance.player_slots.clone();
+
+    let arena_assignments = server_instance.arena_assignments.clone();
+
+    let client_identities = server_instance.client_identities.clone();
+
+    let client_round_trip_times = server_instance.client_round_trip_times.clone();
+
+    let password = server_instance.password.clone();
+
+    let connection_gate = Arc::new(Mutex::new(ConnectionGate::new(
+        server_instance.connection_limits.clone(),
+    )));
+
     // Spawn the incoming connection accepter thread
     tokio_runtime.spawn_background_task(move |mut ctx| async move {
         setup_client_listener(udp_socket.clone(), cancellation_token_clone.clone(), sender.clone(), connected_clients_clone.clone());
+
+        // The refused connections are counted between the reports
+        let mut refused_connections = 0;
+        let mut last_refusal_log: Option<Instant> = None;
         
         loop {
             select! {
@@ -142,36 +438,118 @@ pub fn setup_remote_client_handler(
                 },
 
                 Ok((tcp_stream, socket_addr)) = handle_incoming_request(tcp_listener.clone()) => {
-                    // Create a new unique id for the connected client
-                    let uuid = Uuid::new_v4();
+                    // The connection is closed right away if it is above the limits
+                    if let Err(reason) = connection_gate.lock().admit(socket_addr.ip()) {
+                        refused_connections += 1;
+
+                        if last_refusal_log.is_none_or(|last_refusal_log| last_refusal_log.elapsed() >= REFUSED_CONNECTIONS_LOG_INTERVAL) {
+                            eprintln!("Refused {refused_connections} connection(s) since the last report, the last of {socket_addr}: {reason}");
+
+                            refused_connections = 0;
+                            last_refusal_log = Some(Instant::now());
+                        }
+
+                        continue;
+                    }
+
+                    let connection_gate = connection_gate.clone();
+                    let metadata = metadata.clone();
+                    let password = password.clone();
+                    let server_game_states = server_game_states.clone();
+                    let connected_clients_stats = connected_clients_stats.clone();
+                    let player_slots = player_slots.clone();
+                    let arena_assignments = arena_assignments.clone();
+                    let client_identities = client_identities.clone();
+                    let client_round_trip_times = client_round_trip_times.clone();
+                    let collision_groups = collision_groups.clone();
+                    let connected_clients_clone = connected_clients_clone.clone();
+                    let tcp_sender = tcp_sender.clone();
+                    let cancellation_token_clone = cancellation_token_clone.clone();
+                    let mut ctx = ctx.clone();
+
+                    // The connection is handled in its own task, so that a slow handshake doesn't hold up the other incoming connections
+                    tokio::spawn(async move {
+                        // Create a new unique id for the connected client
+                        let uuid = Uuid::new_v4();
+
+                        let (mut read_half, mut write_half) = tcp_stream.into_split();
+
+                        // Exchange metadata between client and server
+                        // The connection is closed if the metadata exchange fails or times out, this happens if the client has sent a wrong password
+                        let handshake = timeout(HANDSHAKE_TIMEOUT, exchange_metadata(&mut read_half, &mut write_half, metadata.into_server_metadata(uuid), password.as_deref())).await;
+
+                        connection_gate.lock().finish_handshake();
 
-                    let (mut read_half, mut write_half) = tcp_stream.into_split();
+                        let Ok(Ok(client_metadata)) = handshake else {
+                            return;
+                        };
 
-                    
-                    // Exchange metadata between client and server
-                    if let Ok(client_metadata) = exchange_metadata(&mut read_half, &mut write_half, metadata.into_server_metadata(uuid)).await {
-                        // Send the server's game state
-                        let _ = send_request_to_client(&mut write_half, RemoteServerRequest { request: ServerRequest::ServerGameStateControl(server_game_state.read().clone()) }).await;
+                        // Route the connected client to the least full arena
+                        let arena = arena_assignments.lock().assign(uuid);
+
+                        let game_state = server_game_states.read()[arena].clone();
+
+                        let is_ongoing_game = matches!(game_state, ServerGameState::OngoingGame(_));
+
+                        // Send the state of the client's arena
+                        let _ = send_request_to_client(&mut write_half, RemoteServerRequest { request: ServerRequest::ServerGameStateControl(game_state) }).await;
+
+                        client_identities.insert(uuid, client_metadata.identity);
+
+                        // Assign a player slot to the connected client
+                        let player_slot = player_slots.lock().assign(uuid, client_metadata.identity);
+
+                        // Nudge the player's tint if another player has already picked a similar one
+                        let tint = client_metadata.tint.map(|tint| {
+                            let taken_tints = connected_clients_stats.read().iter().filter_map(|client_stats| client_stats.tint).collect::<Vec<_>>();
+
+                            distinct_tint(tint, &taken_tints)
+                        });
 
                         // Spawn a new entity for the connected client
-                        ctx.run_on_main_thread(move |main_ctx| {
-                            let mut worlds_commands = main_ctx.world.commands();
+                        // The clients joining between the rounds are queued, their pawns are spawned when the next round starts
+                        let identity = client_metadata.identity;
+
+                        let (has_spawned, restored_stats) = ctx.run_on_main_thread(move |main_ctx| {
+                            let mut app_ctx = main_ctx.world.resource_mut::<ApplicationCtx>();
+
+                            // The clients joining mid-round are queued too if the server makes them spectate, unless the round is still waiting for players
+                            let is_joinable = is_ongoing_game && (!app_ctx.settings.late_joiners_spectate || app_ctx.arenas.get(arena).is_some_and(|arena_round| arena_round.waiting_for_players));
+
+                            // Give the player their standings back if they have played in the match resumed after a restart
+                            let restored_stats = app_ctx.restored_match.as_mut().and_then(|restored_match| restored_match.take_client_stats(identity));
 
-                            spawn_pawn(&mut worlds_commands, uuid, collision_groups.pawn);
+                            let pawn_scale = app_ctx.settings.pawn_scale;
+
+                            if is_joinable {
+                                let mut worlds_commands = main_ctx.world.commands();
+
+                                spawn_pawn(&mut worlds_commands, uuid, player_slot, tint, PawnType::default(), arena, DEFAULT_SPAWN_POSITION, pawn_scale, &collision_groups);
+                            }
+
+                            (is_joinable, restored_stats)
                         }).await;
 
+                        if !has_spawned {
+                            let _ = send_request_to_client(&mut write_half, RemoteServerRequest { request: ServerRequest::QueuedForNextRound }).await;
+                        }
+
+                        let game_socket_address = SocketAddr::new(socket_addr.ip(), client_metadata.game_socket_port);
+
+                        let write_half = Arc::new(Mutex::new(write_half));
+
                         // Save the connected clients handle and ports
-                        connected_clients_clone.insert(SocketAddr::new(socket_addr.ip(), client_metadata.game_socket_port), (uuid, Arc::new(Mutex::new(write_half))));
+                        connected_clients_clone.insert(game_socket_address, (uuid, write_half.clone()));
 
                         // This shit aint working fix it up!!!!
                         // Try sending a made up client request to the server's client handler, so that if a client joins it will already send every information present for them even if theyre not moving.
                         tcp_sender.send((RemoteClientRequest {uuid, request: crate::networking::ClientRequest::ClientPawnSync}, socket_addr)).await.unwrap_or_default();
-                        
-                        // Clone the cancellation token
-                        let cancellation_token_clone = cancellation_token_clone.clone();
-                        
+
                         // Create the new stats field
-                        let new_statistics_field = ClientStatistics::new(uuid, client_metadata.username.clone());
+                        let new_statistics_field = match restored_stats {
+                            Some(restored_stats) => ClientStatistics { uuid, username: client_metadata.username.clone(), player_slot, tint, ..restored_stats },
+                            None => ClientStatistics { tint, ..ClientStatistics::new(uuid, client_metadata.username.clone(), player_slot) },
+                        };
 
                         // Create a new field in the Statistics list
                         connected_clients_stats.write().insert(new_statistics_field.clone());
@@ -179,32 +557,75 @@ pub fn setup_remote_client_handler(
                         // Notify all the clients about the new field
                         send_request_to_all_clients(RemoteServerRequest { request: ServerRequest::PlayersStatisticsChange(vec![new_statistics_field]) }, connected_clients_clone.clone()).await;
 
-                        // Clone the TcpSender
-                        let tcp_sender = tcp_sender.clone();
+                        // The keep-alives are sent until the client's connection is closed
+                        let connection_token = cancellation_token_clone.child_token();
+
+                        // The time the oldest unanswered keep-alive has been sent at
+                        let keep_alive_sent_at = Arc::new(Mutex::new(None));
 
-                        let socket_addr = socket_addr;
+                        spawn_keep_alive_sender(write_half, keep_alive_sent_at.clone(), connection_token.clone());
 
                         // Create tcp listener
                         tokio::spawn(async move {
+                            // Stop the keep-alives once the listener has stopped
+                            let _connection_guard = connection_token.drop_guard();
+
                             loop {
                                 select! {
                                     _ = cancellation_token_clone.cancelled() => {
                                         break;
                                     }
 
-                                    Ok(message_length) = read_half.read_u32() => {
+                                    // The client answers the keep-alives, so it is considered dead if nothing has been read for a while
+                                    message_length = timeout(KEEP_ALIVE_TIMEOUT, read_half.read_u32()) => {
+                                        let message_length = match message_length {
+                                            Ok(Ok(message_length)) => message_length,
+                                            // The client has disconnected, it may have crashed without sending an exit
+                                            Ok(Err(_)) => {
+                                                forget_client(&player_slots, &arena_assignments, &connected_clients_stats, &client_identities, &uuid);
+
+                                                remove_disconnected_client(&mut ctx, &connected_clients_clone, game_socket_address, uuid).await;
+
+                                                break;
+                                            }
+                                            // The client's network has dropped without the connection being closed, so the client won't send an exit either
+                                            Err(_) => {
+                                                eprintln!("The connection of {} has timed out", client_metadata.username);
+
+                                                forget_client(&player_slots, &arena_assignments, &connected_clients_stats, &client_identities, &uuid);
+
+                                                remove_disconnected_client(&mut ctx, &connected_clients_clone, game_socket_address, uuid).await;
+
+                                                break;
+                                            }
+                                        };
+
                                         let mut buf = vec![0; message_length as usize];
 
                                         read_half.read_exact(&mut buf).await.unwrap();
 
+                                        SERVER_METRICS.record_tcp_received(4 + buf.len());
+
                                         let message = rmp_serde::from_slice::<RemoteClientRequest>(&buf).unwrap();
 
-                                        tcp_sender.send((message, socket_addr)).await.unwrap();
+                                        // The client answers the keep-alives right away, so the client's round trip time is measured with them
+                                        if matches!(message.request, crate::networking::ClientRequest::KeepAlive) {
+                                            if let Some(sent_at) = keep_alive_sent_at.lock().take() {
+                                                client_round_trip_times.insert(uuid, sent_at.elapsed());
+                                            }
+                                        }
+
+                                        // The server has been stopped if the receiver has been dropped
+                                        if tcp_sender.send((message, socket_addr)).await.is_err() {
+                                            break;
+                                        }
                                     }
                                 }
                             }
+
+                            client_round_trip_times.remove(&uuid);
                         });
-                    }
+                    });
                 }
             }
         }
@@ -226,6 +647,9 @@ fn setup_client_listener(
     connected_clients: Arc<DashMap<SocketAddr, (Uuid, Arc<Mutex<OwnedWriteHalf>>)>>,
 ) {
     tokio::spawn(async move {
+        // The amount of malformed packets every sender has sent in a row
+        let mut decode_failures: HashMap<SocketAddr, u32> = HashMap::new();
+
         loop {
             // Allocate the buffer for peeking the message's lenght
             let mut buf = vec![0; UDP_DATAGRAM_SIZE];
@@ -241,16 +665,48 @@ fn setup_client_listener(
                 read_result = socket.recv_from(&mut buf) => {
                     // Check the peek's result
                     match read_result {
-                        Ok((_, address)) => {
+                        Ok((read_bytes, address)) => {
+                            SERVER_METRICS.record_udp_received(read_bytes);
+
+                            // Drop the packets of the senders which have sent too many malformed packets
+                            if decode_failures.get(&address).is_some_and(|failures| *failures >= MAX_UDP_DECODE_FAILURES) {
+                                continue;
+                            }
+
                             // Check if the remote address has already been connected to the main server
                             if connected_clients.contains_key(&address) {
+                                // The packet has to contain the message's length, which is skipped
+                                let client_request = buf
+                                    .get(4..read_bytes)
+                                    .and_then(|message| rmp_serde::from_slice::<RemoteClientGameRequest>(message).ok());
+
                                 // Serialize the bytes from the message
-                                if let Ok(client_request) = rmp_serde::from_slice::<RemoteClientGameRequest>(&buf[4..]) {
+                                if let Some(client_request) = client_request {
+                                    decode_failures.remove(&address);
+
+                                    // The server has been stopped if the receiver has been dropped
+                                    if client_request_channel.is_closed() {
+                                        break;
+                                    }
+
+                                    let client_request_channel = client_request_channel.clone();
+
                                     // Send the message to the server's receiver
-                                    client_request_channel.send((client_request, address)).await.unwrap();
+                                    deliver_packet(async move {
+                                        let _ = client_request_channel.send((client_request, address)).await;
+                                    }).await;
                                 }
                                 else {
-                                    println!("Received a message unsupported.");
+                                    let failures = decode_failures.entry(address).or_default();
+
+                                    *failures += 1;
+
+                                    if *failures == MAX_UDP_DECODE_FAILURES {
+                                        println!("Dropping the packets of {address}, too many malformed messages have been received.");
+                                    }
+                                    else {
+                                        println!("Received a message unsupported.");
+                                    }
                                 }
                             }
                             else {
@@ -268,26 +724,187 @@ fn setup_client_listener(
     });
 }
 
+/// Exchanges the metadata with the connecting client.
+/// If the server is password protected, the client is sent a [`ServerRequest::ConnectionRejected`] and an error is returned if the client's password doesn't match.
 async fn exchange_metadata(
     read_half: &mut OwnedReadHalf,
     write_half: &mut OwnedWriteHalf,
     metadata: ServerMetadata,
-) -> anyhow::Result<ClientMetadata> {
+    password: Option<&str>,
+) -> Result<ClientMetadata, NetError> {
     let slice = rmp_serde::to_vec(&metadata)?;
 
     write_to_buf_with_len(write_half, &slice).await?;
 
-    let metadata_length = read_half.read_u32().await?;
+    // A client announcing an oversized message is rejected before the message is read
+    let buf = read_metadata_with_len(read_half).await?;
 
-    let mut buf = vec![0; metadata_length as usize];
+    let client_metadata = rmp_serde::from_slice::<ClientMetadata>(&buf)?;
 
-    read_half.read_exact(&mut buf).await?;
+    if let Some(password) = password {
+        if !passwords_match(password.as_bytes(), client_metadata.password.as_bytes()) {
+            // The connection is closed either way, so the rejection is only sent on a best-effort basis
+            let _ = send_request_to_client(
+                write_half,
+                RemoteServerRequest {
+                    request: ServerRequest::ConnectionRejected(String::from("Wrong password")),
+                },
+            )
+            .await;
 
-    let client_metadata = rmp_serde::from_slice::<ClientMetadata>(&buf)?;
+            return Err(NetError::WrongPassword);
+        }
+    }
 
     Ok(client_metadata)
 }
 
+/// Sends a [`ServerRequest::KeepAlive`] to the client every [`KEEP_ALIVE_INTERVAL`], until the token is cancelled or the connection is closed.
+/// The time of the oldest unanswered keep-alive is stored in `sent_at`, the connection's listener measures the client's round trip time with it.
+fn spawn_keep_alive_sender(
+    write_half: Arc<Mutex<OwnedWriteHalf>>,
+    sent_at: Arc<Mutex<Option<Instant>>>,
+    cancellation_token: CancellationToken,
+) {
+    tokio::spawn(async move {
+        let mut keep_alive_interval = tokio::time::interval(KEEP_ALIVE_INTERVAL);
+
+        loop {
+            select! {
+                _ = cancellation_token.cancelled() => {
+                    break;
+                }
+
+                _ = keep_alive_interval.tick() => {
+                    sent_at.lock().get_or_insert_with(Instant::now);
+
+                    let result = send_request_to_client(&mut write_half.lock(), RemoteServerRequest { request: ServerRequest::KeepAlive }).await;
+
+                    if result.is_err() {
+                        break;
+                    }
+                }
+            }
+        }
+    });
+}
+
+/// Frees the disconnected client's slot and arena, and forgets its statistics so that the leaderboard doesn't keep the players who have left.
+pub fn forget_client(
+    player_slots: &Mutex<PlayerSlots>,
+    arena_assignments: &Mutex<ArenaAssignments>,
+    connected_clients_stats: &RwLock<BTreeSet<ClientStatistics>>,
+    client_identities: &DashMap<Uuid, Uuid>,
+    uuid: &Uuid,
+) {
+    player_slots.lock().free(uuid);
+    arena_assignments.lock().free(uuid);
+
+    connected_clients_stats
+        .write()
+        .retain(|client_stats| client_stats.uuid != *uuid);
+    client_identities.remove(uuid);
+}
+
+/// Removes the client whose connection has ended, this does what a [`super::GameInput::Exit`] does for the clients which couldn't send it.
+/// The client's pawn is despawned and the other players are notified about the disconnect, see [`forget_client`] for the rest of the cleanup.
+async fn remove_disconnected_client(
+    ctx: &mut TaskContext,
+    connected_clients: &Arc<DashMap<SocketAddr, (Uuid, Arc<Mutex<OwnedWriteHalf>>)>>,
+    game_socket_address: SocketAddr,
+    uuid: Uuid,
+) {
+    // The client has already been removed if it has sent an exit before closing the connection
+    if connected_clients.remove(&game_socket_address).is_none() {
+        return;
+    }
+
+    despawn_removed_client(ctx, connected_clients, uuid).await;
+}
+
+/// Despawns the pawn of the client who has been removed from the connected clients, and notifies the other players about the disconnect.
+async fn despawn_removed_client(
+    ctx: &mut TaskContext,
+    connected_clients: &Arc<DashMap<SocketAddr, (Uuid, Arc<Mutex<OwnedWriteHalf>>)>>,
+    uuid: Uuid,
+) {
+    ctx.run_on_main_thread(move |main_ctx| {
+        let mut pawns = main_ctx.world.query::<(Entity, &Pawn)>();
+
+        let entity = pawns
+            .iter(main_ctx.world)
+            .find(|(_, pawn)| pawn.uuid == uuid)
+            .map(|(entity, _)| entity);
+
+        if let Some(entity) = entity {
+            main_ctx.world.despawn(entity);
+        }
+
+        // Forget the state kept about the disconnected client
+        main_ctx
+            .world
+            .resource_mut::<ApplicationCtx>()
+            .forget_player_state(&uuid);
+    })
+    .await;
+
+    send_request_to_all_clients(
+        RemoteServerRequest {
+            request: ServerRequest::PlayerDisconnect(uuid),
+        },
+        connected_clients.clone(),
+    )
+    .await;
+
+    send_request_to_all_clients(
+        RemoteServerRequest {
+            request: ServerRequest::PlayerStatsRemoved(uuid),
+        },
+        connected_clients.clone(),
+    )
+    .await;
+}
+
+/// Disconnects the client, the client is sent a [`ServerRequest::Kicked`] with the reason before its connection is closed.
+/// The client is removed like the clients who have disconnected, the caller has to [`forget_client`] too.
+pub async fn kick_client(
+    ctx: &mut TaskContext,
+    connected_clients: &Arc<DashMap<SocketAddr, (Uuid, Arc<Mutex<OwnedWriteHalf>>)>>,
+    game_socket_address: SocketAddr,
+    reason: String,
+) {
+    // The client is removed before its connection is closed, so that the broadcasts don't write to the closed connection
+    let Some((_, (uuid, write_half))) = connected_clients.remove(&game_socket_address) else {
+        return;
+    };
+
+    // The connection is closed either way, so the reason is only sent on a best-effort basis
+    let _ = send_request_to_client(
+        &mut write_half.lock(),
+        RemoteServerRequest {
+            request: ServerRequest::Kicked(reason),
+        },
+    )
+    .await;
+
+    let _ = write_half.lock().shutdown().await;
+
+    despawn_removed_client(ctx, connected_clients, uuid).await;
+}
+
+/// Compares the passwords in constant time, so that the password can't be guessed from the time it takes to reject it.
+fn passwords_match(expected: &[u8], received: &[u8]) -> bool {
+    // Every byte of the received password is compared, even if the lengths differ
+    let difference = received
+        .iter()
+        .enumerate()
+        .fold(0, |difference, (idx, byte)| {
+            difference | (byte ^ expected.get(idx).copied().unwrap_or_default())
+        });
+
+    difference == 0 && expected.len() == received.len()
+}
+
 pub async fn notify_client_about_player_disconnect(
     write_half: &mut OwnedWriteHalf,
     uuid: Uuid,
@@ -296,7 +913,11 @@ pub async fn notify_client_about_player_disconnect(
         request: ServerRequest::PlayerDisconnect(uuid),
     };
 
-    write_to_buf_with_len(write_half, &rmp_serde::to_vec(&message)?).await?;
+    let message_bytes = rmp_serde::to_vec(&message)?;
+
+    write_to_buf_with_len(write_half, &message_bytes).await?;
+
+    SERVER_METRICS.record_tcp_sent(4 + message_bytes.len());
 
     Ok(())
 }
@@ -305,7 +926,11 @@ pub async fn send_request_to_client(
     tcp_stream: &mut OwnedWriteHalf,
     message: RemoteServerRequest,
 ) -> anyhow::Result<()> {
-    write_to_buf_with_len(tcp_stream, &rmp_serde::to_vec(&message)?).await?;
+    let message_bytes = rmp_serde::to_vec(&message)?;
+
+    write_to_buf_with_len(tcp_stream, &message_bytes).await?;
+
+    SERVER_METRICS.record_tcp_sent(4 + message_bytes.len());
 
     Ok(())
 }
@@ -322,18 +947,22 @@ pub async fn send_request_to_all_clients(
         >,
     >,
 ) {
-    // Get the connected clients list
-    for connected_client in connected_clients_clone.iter_mut() {
-        // Get the handle of the TcpStream established when the client was connecting to the server
-        let (_, tcp_stream) = connected_client.value();
-
-        let owned_write_half = &mut *tcp_stream.lock();
-
-        write_to_buf_with_len(
-            owned_write_half,
-            &rmp_serde::to_vec(&request.clone()).unwrap(),
-        )
-        .await
-        .unwrap();
+    // Get the handles of the TcpStreams established when the clients were connecting to the server, so that the list isn't locked while sending
+    let connected_clients = connected_clients_clone
+        .iter()
+        .map(|connected_client| (*connected_client.key(), connected_client.value().1.clone()))
+        .collect::<Vec<_>>();
+
+    let message_bytes = rmp_serde::to_vec(&request).unwrap();
+
+    for (socket_addr, tcp_stream) in connected_clients {
+        // The connection of the client may have died, its own listener removes it so the rest of the clients are still sent the request
+        if let Err(err) = write_to_buf_with_len(&mut *tcp_stream.lock(), &message_bytes).await {
+            eprintln!("Failed to send a request to {socket_addr}: {err}");
+
+            continue;
+        }
+
+        SERVER_METRICS.record_tcp_sent(4 + message_bytes.len());
     }
 }
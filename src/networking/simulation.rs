@@ -0,0 +1,78 @@
+use std::future::Future;
+
+#[cfg(feature = "network-simulation")]
+use std::{sync::LazyLock, time::Duration};
+
+#[cfg(all(feature = "network-simulation", not(debug_assertions)))]
+compile_error!("The `network-simulation` feature must not be enabled in release builds.");
+
+/// The environment variable setting the latency added to every UDP packet in milliseconds.
+pub const SIMULATED_LATENCY_ENV: &str = "PUNCHAFRIEND_SIMULATED_LATENCY_MS";
+
+/// The environment variable setting the percentage of the UDP packets dropped.
+pub const SIMULATED_PACKET_LOSS_ENV: &str = "PUNCHAFRIEND_SIMULATED_PACKET_LOSS";
+
+/// The simulated network conditions, these are read from the environment variables the first time a packet is handled.
+#[cfg(feature = "network-simulation")]
+static NETWORK_CONDITIONS: LazyLock<NetworkConditions> = LazyLock::new(NetworkConditions::from_env);
+
+/// The artificial network conditions used to reproduce lag and packet loss locally when testing the netcode.
+#[cfg(feature = "network-simulation")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NetworkConditions {
+    /// The fixed latency added to every packet.
+    pub latency: Duration,
+
+    /// The chance of a packet being dropped, between 0 and 1.
+    pub packet_loss: f64,
+}
+
+#[cfg(feature = "network-simulation")]
+impl NetworkConditions {
+    /// Reads the network conditions from the [`SIMULATED_LATENCY_ENV`] and [`SIMULATED_PACKET_LOSS_ENV`] environment variables.
+    /// Missing or invalid values leave the condition disabled.
+    pub fn from_env() -> Self {
+        let latency = std::env::var(SIMULATED_LATENCY_ENV)
+            .ok()
+            .and_then(|latency| latency.parse::<u64>().ok())
+            .map(Duration::from_millis)
+            .unwrap_or_default();
+
+        let packet_loss = std::env::var(SIMULATED_PACKET_LOSS_ENV)
+            .ok()
+            .and_then(|packet_loss| packet_loss.parse::<f64>().ok())
+            .map(|packet_loss| (packet_loss / 100.).clamp(0., 1.))
+            .unwrap_or_default();
+
+        Self {
+            latency,
+            packet_loss,
+        }
+    }
+}
+
+/// Delivers a UDP packet by running `deliver`, this is where the simulated network conditions are applied.
+/// With the `network-simulation` feature the packet is dropped or delivered in a separate task after the added latency, so that the delay doesn't stack up for the following packets.
+/// Without the feature the packet is delivered immediately.
+pub async fn deliver_packet(deliver: impl Future<Output = ()> + Send + 'static) {
+    #[cfg(feature = "network-simulation")]
+    {
+        let network_conditions = *NETWORK_CONDITIONS;
+
+        if rand::random::<f64>() < network_conditions.packet_loss {
+            return;
+        }
+
+        if !network_conditions.latency.is_zero() {
+            tokio::spawn(async move {
+                tokio::time::sleep(network_conditions.latency).await;
+
+                deliver.await;
+            });
+
+            return;
+        }
+    }
+
+    deliver.await;
+}